@@ -1,8 +1,10 @@
+use base64;
 use chrono::prelude::*;
 
 use domain::error::domain as ed;
-use domain::model::Admin;
+use domain::model::{Admin, SigningKey, SigningKeyStatus};
 use domain::repository::{AdminRepository, AdminRepositoryComponent};
+use domain::service::{KeyService, KeyServiceComponent};
 
 pub struct RegisterAdminCmd {
     pub name: String,
@@ -36,7 +38,36 @@ impl AdminRepr {
     }
 }
 
-pub trait AdminService: AdminRepositoryComponent {
+pub struct RegisterSigningKeyCmd {
+    pub self_id: Option<String>,
+    pub private_key: String,
+    pub public_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SigningKeyRepr {
+    pub id: String,
+    pub status: SigningKeyStatus,
+    pub created_at: DateTime<Utc>,
+    pub activated_at: Option<DateTime<Utc>>,
+    pub retired_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl SigningKeyRepr {
+    fn from_signing_key(key: &SigningKey) -> Self {
+        SigningKeyRepr {
+            id: key.id.clone(),
+            status: key.status.clone(),
+            created_at: key.created_at.clone(),
+            activated_at: key.activated_at.clone(),
+            retired_at: key.retired_at.clone(),
+            expires_at: key.expires_at.clone(),
+        }
+    }
+}
+
+pub trait AdminService: AdminRepositoryComponent + KeyServiceComponent {
     fn log_in(&self, name: &String, password: &String) -> Result<AdminRepr, ed::Error> {
         let repository = self.admin_repository();
         let admin = repository.find_by_name(name)?;
@@ -112,6 +143,39 @@ pub trait AdminService: AdminRepositoryComponent {
             None => Err(ed::ErrorKind::EntityNotFound(format!("ID => {}", target_id)).into()),
         }
     }
+
+    fn get_signing_keys(
+        &self,
+        self_id: &Option<String>,
+    ) -> Result<Vec<SigningKeyRepr>, ed::Error> {
+        check_admin(self.admin_repository(), self_id)?;
+        let service = self.key_service();
+        let keys = service.signing_keys()?;
+        Ok(keys.iter().map(|k| SigningKeyRepr::from_signing_key(k)).collect())
+    }
+
+    fn register_signing_key(
+        &self,
+        cmd: &RegisterSigningKeyCmd,
+    ) -> Result<SigningKeyRepr, ed::Error> {
+        check_admin(self.admin_repository(), &cmd.self_id)?;
+        let private_key = base64::decode(&cmd.private_key).map_err(|_| {
+            ed::ErrorKind::InvalidRequest("private_key must be base64 encoded DER.".to_string())
+        })?;
+        let public_key = base64::decode(&cmd.public_key).map_err(|_| {
+            ed::ErrorKind::InvalidRequest("public_key must be base64 encoded DER.".to_string())
+        })?;
+        let service = self.key_service();
+        let key = service.register_next_signing_key(&private_key, &public_key)?;
+        Ok(SigningKeyRepr::from_signing_key(&key))
+    }
+
+    fn execute_key_rotation(&self, self_id: &Option<String>) -> Result<SigningKeyRepr, ed::Error> {
+        check_admin(self.admin_repository(), self_id)?;
+        let service = self.key_service();
+        let key = service.rotate_signing_keys()?;
+        Ok(SigningKeyRepr::from_signing_key(&key))
+    }
 }
 
 pub trait AdminServiceComponent {
@@ -120,4 +184,15 @@ pub trait AdminServiceComponent {
 }
 
 // Implement
-impl<T: AdminRepositoryComponent + AdminRepositoryComponent> AdminService for T {}
+impl<T: AdminRepositoryComponent + KeyServiceComponent> AdminService for T {}
+
+// Private functions
+/// Checks that the session belongs to an existing admin.
+fn check_admin(repository: &AdminRepository, self_id: &Option<String>) -> Result<(), ed::Error> {
+    if let Some(ref id) = *self_id {
+        if repository.find_by_id(id)?.is_some() {
+            return Ok(());
+        }
+    }
+    Err(ed::ErrorKind::AccessDenied("Admin login is required.".to_string()).into())
+}
@@ -1,6 +1,9 @@
 use chrono::prelude::*;
+use serde_json::{Map, Value};
+use config::AppConfig;
 use domain::error::domain as ed;
-use domain::model::{Client, ClientType};
+use domain::model::{Client, ClientMetadata, ClientType, JwkSet};
+use util::{constant_time_eq, generate_random_id};
 use domain::repository::{AdminRepository, AdminRepositoryComponent, ClientRepository,
                          ClientRepositoryComponent, ResourceRepository,
                          ResourceRepositoryComponent};
@@ -12,6 +15,10 @@ pub struct RegisterClientCmd {
     pub client_type: String,
     pub redirect_uris: Vec<String>,
     pub resource_id: String,
+    pub require_pkce: bool,
+    pub post_logout_redirect_uris: Vec<String>,
+    pub backchannel_logout_uri: Option<String>,
+    pub frontchannel_logout_uri: Option<String>,
 }
 
 pub struct UpdateClientCmd {
@@ -24,9 +31,33 @@ pub struct UpdateClientCmd {
     pub client_type: Option<String>,
     pub redirect_uris: Option<Vec<String>>,
     pub resource_id: Option<String>,
+    pub require_pkce: Option<bool>,
+    pub post_logout_redirect_uris: Option<Vec<String>>,
+    pub backchannel_logout_uri: Option<String>,
+    pub frontchannel_logout_uri: Option<String>,
+    pub metadata: Option<ClientMetadata>,
     pub current_password: Option<String>,
 }
 
+pub struct DynamicRegisterClientCmd {
+    pub initial_access_token: Option<String>,
+    pub redirect_uris: Vec<String>,
+    pub resource_id: Option<String>,
+    pub metadata: ClientMetadata,
+}
+
+pub struct UpdateClientRegistrationCmd {
+    pub target_id: String,
+    pub registration_access_token: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// None keeps the current redirect_uris.
+    pub redirect_uris: Option<Vec<String>>,
+    pub resource_id: Option<String>,
+    /// The metadata fields sent by the client. The fields not sent are kept.
+    pub metadata: Map<String, Value>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ClientRepr {
     pub id: String,
@@ -59,6 +90,10 @@ pub struct DetailedClientRepr {
     pub client_secret: String,
     pub redirect_uris: Vec<String>,
     pub resource_id: String,
+    pub require_pkce: bool,
+    pub post_logout_redirect_uris: Vec<String>,
+    pub backchannel_logout_uri: Option<String>,
+    pub frontchannel_logout_uri: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -73,12 +108,120 @@ impl DetailedClientRepr {
             client_secret: client.client_secret.clone(),
             redirect_uris: client.redirect_uris.clone(),
             resource_id: client.resource_id.clone(),
+            require_pkce: client.require_pkce,
+            post_logout_redirect_uris: client.metadata.post_logout_redirect_uris.clone(),
+            backchannel_logout_uri: client.metadata.backchannel_logout_uri.clone(),
+            frontchannel_logout_uri: client.metadata.frontchannel_logout_uri.clone(),
             created_at: client.created_at.clone(),
             updated_at: client.updated_at.clone(),
         }
     }
 }
 
+/// `ClientRegistrationRepr` is the client information response of
+/// Dynamic Client Registration.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientRegistrationRepr {
+    pub client_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    pub client_id_issued_at: i64,
+    pub client_secret_expires_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_access_token: Option<String>,
+    pub registration_client_uri: String,
+    pub redirect_uris: Vec<String>,
+    pub resource_id: String,
+    pub grant_types: Vec<String>,
+    pub response_types: Vec<String>,
+    pub token_endpoint_auth_method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logo_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tos_uri: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub contacts: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub post_logout_redirect_uris: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backchannel_logout_uri: Option<String>,
+    pub backchannel_logout_session_required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frontchannel_logout_uri: Option<String>,
+    pub frontchannel_logout_session_required: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jwks: Option<JwkSet>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub request_uris: Vec<String>,
+    pub require_pushed_authorization_requests: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_client_auth_subject_dn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_client_auth_san_dns: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_client_auth_san_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_client_auth_san_ip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls_client_auth_san_email: Option<String>,
+    pub tls_client_certificate_bound_access_tokens: bool,
+}
+
+impl ClientRegistrationRepr {
+    fn from_client(client: &Client, registration_access_token: Option<String>) -> Self {
+        let metadata = &client.metadata;
+        ClientRegistrationRepr {
+            client_id: client.id.clone(),
+            client_secret: if client.is_public() {
+                None
+            } else {
+                Some(client.client_secret.clone())
+            },
+            client_id_issued_at: client.created_at.timestamp(),
+            // The secret never expires
+            client_secret_expires_at: 0,
+            registration_access_token,
+            registration_client_uri: format!(
+                "{}/oidc/register/{}",
+                AppConfig::endpoint().trim_right_matches('/'),
+                client.id
+            ),
+            redirect_uris: client.redirect_uris.clone(),
+            resource_id: client.resource_id.clone(),
+            grant_types: metadata.grant_types.clone(),
+            response_types: metadata.response_types.clone(),
+            token_endpoint_auth_method: metadata.token_endpoint_auth_method.clone(),
+            client_name: metadata.client_name.clone(),
+            logo_uri: metadata.logo_uri.clone(),
+            client_uri: metadata.client_uri.clone(),
+            policy_uri: metadata.policy_uri.clone(),
+            tos_uri: metadata.tos_uri.clone(),
+            contacts: metadata.contacts.clone(),
+            post_logout_redirect_uris: metadata.post_logout_redirect_uris.clone(),
+            backchannel_logout_uri: metadata.backchannel_logout_uri.clone(),
+            backchannel_logout_session_required: metadata.backchannel_logout_session_required,
+            frontchannel_logout_uri: metadata.frontchannel_logout_uri.clone(),
+            frontchannel_logout_session_required: metadata.frontchannel_logout_session_required,
+            jwks: metadata.jwks.clone(),
+            request_uris: metadata.request_uris.clone(),
+            require_pushed_authorization_requests: metadata.require_pushed_authorization_requests,
+            tls_client_auth_subject_dn: metadata.tls_client_auth_subject_dn.clone(),
+            tls_client_auth_san_dns: metadata.tls_client_auth_san_dns.clone(),
+            tls_client_auth_san_uri: metadata.tls_client_auth_san_uri.clone(),
+            tls_client_auth_san_ip: metadata.tls_client_auth_san_ip.clone(),
+            tls_client_auth_san_email: metadata.tls_client_auth_san_email.clone(),
+            tls_client_certificate_bound_access_tokens: metadata
+                .tls_client_certificate_bound_access_tokens,
+        }
+    }
+}
+
 pub trait ClientService
     : AdminRepositoryComponent + ClientRepositoryComponent + ResourceRepositoryComponent
     {
@@ -146,11 +289,157 @@ pub trait ClientService
         let client = Client::builder(&cmd.name, &cmd.password, &cmd.website, &cmd.resource_id)
             .client_type(&ClientType::new(&cmd.client_type)?)
             .redirect_uris(&cmd.redirect_uris)
+            .require_pkce(cmd.require_pkce)
+            .metadata(&ClientMetadata {
+                post_logout_redirect_uris: cmd.post_logout_redirect_uris.clone(),
+                backchannel_logout_uri: cmd.backchannel_logout_uri.clone(),
+                frontchannel_logout_uri: cmd.frontchannel_logout_uri.clone(),
+                ..ClientMetadata::default()
+            })
             .build();
         repository.add(&client)?;
         Ok(DetailedClientRepr::from_client(&client))
     }
 
+    fn register_client_dynamically(
+        &self,
+        cmd: &DynamicRegisterClientCmd,
+    ) -> Result<ClientRegistrationRepr, ed::Error> {
+        // Registration is closed unless the initial access token is configured
+        // or the open registration is enabled explicitly.
+        match AppConfig::registration_initial_access_token() {
+            Some(expected) => {
+                let valid = cmd.initial_access_token
+                    .as_ref()
+                    .map_or(false, |t| constant_time_eq(t.as_bytes(), expected.as_bytes()));
+                if !valid {
+                    return Err(ed::ErrorKind::RegistrationTokenError(
+                        "Invalid initial access token.".to_string(),
+                    ).into());
+                }
+            }
+            None if AppConfig::registration_open() => {}
+            None => {
+                return Err(ed::ErrorKind::AccessDenied(
+                    "Dynamic client registration is disabled.".to_string(),
+                ).into())
+            }
+        }
+
+        let repository = self.client_repository();
+        let resource_repository = self.resource_repository();
+        let resource_id = cmd.resource_id.as_ref().ok_or::<ed::Error>(
+            ed::ErrorKind::InvalidClientMetadata("resource_id is required.".to_string()).into(),
+        )?;
+        if resource_repository.find_by_id(resource_id)?.is_none() {
+            return Err(ed::ErrorKind::InvalidClientMetadata(format!(
+                "Resource not found. ID => {}",
+                resource_id
+            )).into());
+        }
+        let metadata = cmd.metadata.clone().validate(&cmd.redirect_uris)?;
+
+        // Clients registered dynamically don't log in with a password.
+        // client_name is only for display, so the unique name is generated.
+        let name = generate_random_id(32usize);
+        let password = generate_random_id(32usize);
+        let website = metadata.client_uri.clone().unwrap_or_default();
+        let mut client = Client::builder(&name, &password, &website, resource_id)
+            .client_type(&metadata.client_type())
+            .redirect_uris(&cmd.redirect_uris)
+            .metadata(&metadata)
+            .build();
+        let token = client.issue_registration_access_token();
+        repository.add(&client)?;
+        Ok(ClientRegistrationRepr::from_client(&client, Some(token)))
+    }
+
+    fn get_client_registration(
+        &self,
+        target_id: &String,
+        registration_access_token: &Option<String>,
+    ) -> Result<ClientRegistrationRepr, ed::Error> {
+        let repository = self.client_repository();
+        let client = authenticate_by_registration_access_token(
+            repository,
+            target_id,
+            registration_access_token,
+        )?;
+        Ok(ClientRegistrationRepr::from_client(&client, None))
+    }
+
+    fn update_client_registration(
+        &self,
+        cmd: &UpdateClientRegistrationCmd,
+    ) -> Result<ClientRegistrationRepr, ed::Error> {
+        let repository = self.client_repository();
+        let client = authenticate_by_registration_access_token(
+            repository,
+            &cmd.target_id,
+            &cmd.registration_access_token,
+        )?;
+
+        // The request MUST contain the client_id and the current client_secret
+        // if the client has one (RFC 7592 section 2.2).
+        if cmd.client_id.as_ref() != Some(&client.id) {
+            return Err(ed::ErrorKind::InvalidClientMetadata(
+                "client_id does not match.".to_string(),
+            ).into());
+        }
+        if !client.is_public() {
+            let matched = cmd.client_secret
+                .as_ref()
+                .map_or(false, |secret| client.is_authenticated_by_secret(secret));
+            if !matched {
+                return Err(ed::ErrorKind::InvalidClientMetadata(
+                    "client_secret is missing or does not match.".to_string(),
+                ).into());
+            }
+        }
+
+        let redirect_uris = cmd.redirect_uris
+            .clone()
+            .unwrap_or_else(|| client.redirect_uris.clone());
+        let metadata = client.metadata.merge(&cmd.metadata)?.validate(&redirect_uris)?;
+        let update_cmd = UpdateClientCmd {
+            target_id: client.id.clone(),
+            self_id: Some(client.id.clone()),
+            admin_id: None,
+            // The name is internal, and client_name is kept in the metadata.
+            name: None,
+            new_password: None,
+            website: metadata.client_uri.clone(),
+            client_type: None,
+            redirect_uris: Some(redirect_uris),
+            resource_id: cmd.resource_id.clone(),
+            require_pkce: None,
+            post_logout_redirect_uris: None,
+            backchannel_logout_uri: None,
+            frontchannel_logout_uri: None,
+            metadata: Some(metadata),
+            current_password: None,
+        };
+        self.update_client(&update_cmd)?;
+        let client = repository.find_by_id(&client.id)?.ok_or::<ed::Error>(
+            ed::ErrorKind::ServerError(format!("Client not found. ID => {}", client.id)).into(),
+        )?;
+        Ok(ClientRegistrationRepr::from_client(&client, None))
+    }
+
+    fn delete_client_registration(
+        &self,
+        target_id: &String,
+        registration_access_token: &Option<String>,
+    ) -> Result<(), ed::Error> {
+        let repository = self.client_repository();
+        let client = authenticate_by_registration_access_token(
+            repository,
+            target_id,
+            registration_access_token,
+        )?;
+        self.delete_client(&client.id, &Some(client.id.clone()), &None)
+    }
+
     fn update_client(&self, cmd: &UpdateClientCmd) -> Result<(), ed::Error> {
         let repository = self.client_repository();
         let resource_repository = self.resource_repository();
@@ -180,6 +469,25 @@ pub trait ClientService
                         }
                         client.resource_id = resource_id;
                     }
+                    if cmd.require_pkce.is_some() {
+                        client.require_pkce = cmd.require_pkce.unwrap();
+                    }
+                    if cmd.post_logout_redirect_uris.is_some() {
+                        client.metadata.post_logout_redirect_uris =
+                            cmd.post_logout_redirect_uris.as_ref().unwrap().clone();
+                    }
+                    if cmd.backchannel_logout_uri.is_some() {
+                        client.metadata.backchannel_logout_uri = cmd.backchannel_logout_uri.clone();
+                    }
+                    if cmd.frontchannel_logout_uri.is_some() {
+                        client.metadata.frontchannel_logout_uri =
+                            cmd.frontchannel_logout_uri.clone();
+                    }
+                    if cmd.metadata.is_some() {
+                        let metadata = cmd.metadata.as_ref().unwrap().clone();
+                        client.client_type = metadata.client_type();
+                        client.metadata = metadata;
+                    }
                     if cmd.new_password.is_some() {
                         client.update_password(
                             cmd.new_password.as_ref().unwrap(),
@@ -218,6 +526,26 @@ pub trait ClientService
                             }
                             client.resource_id = resource_id;
                         }
+                        if cmd.require_pkce.is_some() {
+                            client.require_pkce = cmd.require_pkce.unwrap();
+                        }
+                        if cmd.post_logout_redirect_uris.is_some() {
+                            client.metadata.post_logout_redirect_uris =
+                                cmd.post_logout_redirect_uris.as_ref().unwrap().clone();
+                        }
+                        if cmd.backchannel_logout_uri.is_some() {
+                            client.metadata.backchannel_logout_uri =
+                                cmd.backchannel_logout_uri.clone();
+                        }
+                        if cmd.frontchannel_logout_uri.is_some() {
+                            client.metadata.frontchannel_logout_uri =
+                                cmd.frontchannel_logout_uri.clone();
+                        }
+                        if cmd.metadata.is_some() {
+                            let metadata = cmd.metadata.as_ref().unwrap().clone();
+                            client.client_type = metadata.client_type();
+                            client.metadata = metadata;
+                        }
                         client.update_timestamp();
                         return repository.update(&client);
                     }
@@ -268,3 +596,25 @@ impl<
 > ClientService for T
 {
 }
+
+// Private functions
+/// Finds the client authenticated by the registration access token.
+/// An unknown client is also treated as an invalid token not to reveal its existence.
+fn authenticate_by_registration_access_token(
+    repository: &ClientRepository,
+    target_id: &String,
+    registration_access_token: &Option<String>,
+) -> Result<Client, ed::Error> {
+    let token = registration_access_token.as_ref().ok_or::<ed::Error>(
+        ed::ErrorKind::RegistrationTokenError("Registration access token is required.".to_string())
+            .into(),
+    )?;
+    match repository.find_by_id(target_id)? {
+        Some(ref client) if client.is_authenticated_by_registration_access_token(token) => {
+            Ok(client.clone())
+        }
+        _ => Err(ed::ErrorKind::RegistrationTokenError(
+            "Invalid registration access token.".to_string(),
+        ).into()),
+    }
+}
@@ -1,7 +1,7 @@
 use chrono::prelude::*;
 
 use domain::error::domain as ed;
-use domain::model::{AcceptedClient, EndUser};
+use domain::model::{AcceptedClient, Address, EndUser};
 use domain::repository::{AdminRepository, AdminRepositoryComponent, EndUserRepository,
                          EndUserRepositoryComponent};
 
@@ -21,6 +21,7 @@ pub struct RegisterEndUserCmd {
     pub zoneinfo: Option<String>,
     pub locale: Option<String>,
     pub phone_number: Option<String>,
+    pub address: Option<Address>,
 }
 
 pub struct UpdateEndUserCmd {
@@ -43,6 +44,7 @@ pub struct UpdateEndUserCmd {
     pub zoneinfo: Option<String>,
     pub locale: Option<String>,
     pub phone_number: Option<String>,
+    pub address: Option<Address>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -81,6 +83,7 @@ pub struct DetailedEndUserRepr {
     pub zoneinfo: Option<String>,
     pub locale: Option<String>,
     pub phone_number: Option<String>,
+    pub address: Option<Address>,
     pub accepted_clients: Vec<AcceptedClient>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -104,6 +107,7 @@ impl DetailedEndUserRepr {
             zoneinfo: end_user.zoneinfo.clone(),
             locale: end_user.locale.clone(),
             phone_number: end_user.phone_number.clone(),
+            address: end_user.address.clone(),
             accepted_clients: end_user.accepted_clients.clone(),
             created_at: end_user.created_at.clone(),
             updated_at: end_user.updated_at.clone(),
@@ -115,8 +119,10 @@ pub trait EndUserService: AdminRepositoryComponent + EndUserRepositoryComponent
     fn log_in(&self, name: &String, password: &String) -> Result<EndUserRepr, ed::Error> {
         let repository = self.end_user_repository();
         let end_user = repository.find_by_name(name)?;
-        if let Some(v) = end_user {
+        if let Some(mut v) = end_user {
             if v.is_authenticated(password) {
+                v.update_authenticated_timestamp();
+                repository.update(&v)?;
                 return Ok(EndUserRepr::from_end_user(&v));
             }
         };
@@ -182,6 +188,7 @@ pub trait EndUserService: AdminRepositoryComponent + EndUserRepositoryComponent
             .zoneinfo(&cmd.zoneinfo)
             .locale(&cmd.locale)
             .phone_number(&cmd.phone_number)
+            .address(&cmd.address)
             .build();
         repository.add(&end_user)?;
         Ok(DetailedEndUserRepr::from_end_user(&end_user))
@@ -235,6 +242,9 @@ pub trait EndUserService: AdminRepositoryComponent + EndUserRepositoryComponent
                     if cmd.phone_number.is_some() {
                         end_user.phone_number = cmd.phone_number.clone();
                     }
+                    if cmd.address.is_some() {
+                        end_user.address = cmd.address.clone();
+                    }
                     if cmd.new_password.is_some() {
                         end_user.update_password(
                             cmd.new_password.as_ref().unwrap(),
@@ -292,6 +302,9 @@ pub trait EndUserService: AdminRepositoryComponent + EndUserRepositoryComponent
                         if cmd.phone_number.is_some() {
                             end_user.phone_number = cmd.phone_number.clone();
                         }
+                        if cmd.address.is_some() {
+                            end_user.address = cmd.address.clone();
+                        }
                         end_user.update_timestamp();
                         return repository.update(&end_user);
                     }
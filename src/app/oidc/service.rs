@@ -1,33 +1,70 @@
+use error_chain::ChainedError;
+
+use config::AppConfig;
 use domain::error::domain as ed;
 use self::ed::ErrorKind as ek;
-use domain::model::{EndUserClaims, GrantType};
-use domain::service::{AcceptClientCmd, AcceptGrantCmd, AuthorizeCmd, AuthorizeRet,
-                      AuthorizeService, AuthorizeServiceComponent, ClientCredentialsCmd,
-                      ClientCredentialsService, ClientCredentialsServiceComponent, IntrospectCmd,
-                      IntrospectRet, IntrospectService, IntrospectServiceComponent, KeyService,
-                      KeyServiceComponent, RefreshTokenCmd, RefreshTokenService,
+use domain::model::{Confirmation, EndUserClaims, GrantType, JwkSet, ProviderMetadata};
+use domain::service::{AcceptClientCmd, AcceptDeviceCmd, AcceptGrantCmd, AuthorizeCmd,
+                      AuthorizeRet, AuthorizeService, AuthorizeServiceComponent,
+                      BackchannelLogoutService, BackchannelLogoutServiceComponent,
+                      ClientAuthentication, ClientAuthenticationService,
+                      ClientAuthenticationServiceComponent, ClientCredentialsCmd,
+                      ClientCredentialsService,
+                      ClientCredentialsServiceComponent, DeviceAuthorizationCmd,
+                      DeviceAuthorizationRet, DeviceAuthorizationService,
+                      DeviceAuthorizationServiceComponent, DeviceCodeCmd, DpopProofCmd,
+                      DpopService, DpopServiceComponent, EndSessionCmd,
+                      EndSessionRet, EndSessionService, EndSessionServiceComponent, IntrospectCmd,
+                      IntrospectRet, IntrospectService, IntrospectServiceComponent, JwtBearerCmd,
+                      JwtBearerService, JwtBearerServiceComponent, KeyService,
+                      KeyServiceComponent, PushAuthorizationRequestCmd,
+                      PushAuthorizationRequestRet, PushedAuthorizationService,
+                      PushedAuthorizationServiceComponent, RefreshTokenCmd, RefreshTokenService,
                       RefreshTokenServiceComponent, ResourceOwnerPasswordCredentialsCmd,
                       ResourceOwnerPasswordCredentialsService,
-                      ResourceOwnerPasswordCredentialsServiceComponent, TokensRet, UserinfoCmd,
-                      UserinfoService, UserinfoServiceComponent};
+                      ResourceOwnerPasswordCredentialsServiceComponent, RevocationCmd,
+                      ReviewDeviceCmd, ReviewDeviceRet, RevocationService,
+                      RevocationServiceComponent, TokenExchangeCmd, TokenExchangeService,
+                      TokenExchangeServiceComponent, TokensRet, UserinfoCmd, UserinfoService,
+                      UserinfoServiceComponent};
 
 pub struct GetTokensCmd {
-    pub client_id: Option<String>,
-    pub client_secret: Option<String>,
+    pub client_auth: ClientAuthentication,
     pub grant_type: Option<String>,
     pub code: Option<String>,
     pub refresh_token: Option<String>,
     pub scope: Option<String>,
     pub username: Option<String>,
     pub password: Option<String>,
+    pub code_verifier: Option<String>,
+    pub device_code: Option<String>,
+    pub subject_token: Option<String>,
+    pub subject_token_type: Option<String>,
+    pub actor_token: Option<String>,
+    pub actor_token_type: Option<String>,
+    pub resource: Option<String>,
+    pub audience: Option<String>,
+    pub requested_token_type: Option<String>,
+    pub assertion: Option<String>,
+    /// The value of the DPoP header.
+    pub dpop_proof: Option<String>,
 }
 
 pub trait OidcService
     : AuthorizeServiceComponent
+    + BackchannelLogoutServiceComponent
+    + ClientAuthenticationServiceComponent
     + ClientCredentialsServiceComponent
+    + DeviceAuthorizationServiceComponent
+    + DpopServiceComponent
+    + EndSessionServiceComponent
     + IntrospectServiceComponent
+    + JwtBearerServiceComponent
+    + PushedAuthorizationServiceComponent
     + RefreshTokenServiceComponent
     + ResourceOwnerPasswordCredentialsServiceComponent
+    + RevocationServiceComponent
+    + TokenExchangeServiceComponent
     + UserinfoServiceComponent
     + KeyServiceComponent {
     fn authorize(&self, cmd: &AuthorizeCmd) -> AuthorizeRet {
@@ -40,6 +77,32 @@ pub trait OidcService
         service.accept_client(cmd)
     }
 
+    fn push_authorization_request(
+        &self,
+        cmd: &PushAuthorizationRequestCmd,
+    ) -> Result<PushAuthorizationRequestRet, ed::Error> {
+        let service = self.pushed_authorization_service();
+        service.push_authorization_request(cmd)
+    }
+
+    fn authorize_device(
+        &self,
+        cmd: &DeviceAuthorizationCmd,
+    ) -> Result<DeviceAuthorizationRet, ed::Error> {
+        let service = self.device_authorization_service();
+        service.authorize_device(cmd)
+    }
+
+    fn review_device(&self, cmd: &ReviewDeviceCmd) -> Result<ReviewDeviceRet, ed::Error> {
+        let service = self.device_authorization_service();
+        service.review_device(cmd)
+    }
+
+    fn accept_device(&self, cmd: &AcceptDeviceCmd) -> Result<(), ed::Error> {
+        let service = self.device_authorization_service();
+        service.accept_device(cmd)
+    }
+
     fn get_tokens(&self, cmd: &GetTokensCmd) -> TokensRet {
         if cmd.grant_type.is_none() {
             return TokensRet::error(
@@ -49,45 +112,119 @@ pub trait OidcService
 
         let grant_type = GrantType::new(&cmd.grant_type.as_ref().unwrap());
 
+        // The client is authenticated before the DPoP proof is verified
+        // so that an unauthenticated request cannot consume the proof.
+        let client = match self.client_authentication_service()
+            .authenticate_client(&cmd.client_auth)
+        {
+            Ok(client) => client,
+            Err(e) => return TokensRet::error(e),
+        };
+        // The client MUST have registered the grant type.
+        match grant_type {
+            GrantType::Undefined(_) => {}
+            ref g if !client.allows_grant_type(g) => {
+                return TokensRet::error(
+                    ek::UnauthorizedClient(format!(
+                        "The client is not allowed to use {}.",
+                        g.to_str()
+                    )).into(),
+                )
+            }
+            _ => {}
+        }
+
+        // Tokens are bound to the key of the DPoP proof (RFC 9449)
+        // and the client certificate (RFC 8705) as the client requires.
+        let jkt = match cmd.dpop_proof {
+            Some(ref proof) => {
+                let dpop_cmd = DpopProofCmd {
+                    proof: proof.clone(),
+                    htm: "POST".to_string(),
+                    htu: format!("{}/oidc/tokens", AppConfig::endpoint().trim_right_matches('/')),
+                    access_token: None,
+                };
+                match self.dpop_service().verify_dpop_proof(&dpop_cmd) {
+                    Ok(jkt) => Some(jkt),
+                    Err(e) => return TokensRet::error(e),
+                }
+            }
+            None => None,
+        };
+        let x5t_s256 = cmd.client_auth
+            .client_certificate
+            .as_ref()
+            .map(|c| c.thumbprint());
+        let cnf = Some(Confirmation { jkt, x5t_s256 });
+
         match grant_type {
             GrantType::AuthorizationCode => {
                 let service = self.authorize_service();
                 let cmd = AcceptGrantCmd {
-                    client_id: cmd.client_id.clone(),
-                    client_secret: cmd.client_secret.clone(),
                     code: cmd.code.clone(),
+                    code_verifier: cmd.code_verifier.clone(),
+                    cnf,
                 };
-                service.accept_grant(&cmd)
+                service.accept_grant(&client, &cmd)
             }
             GrantType::RefreshToken => {
                 let service = self.refresh_token_service();
                 let cmd = RefreshTokenCmd {
-                    client_id: cmd.client_id.clone(),
-                    client_secret: cmd.client_secret.clone(),
                     refresh_token: cmd.refresh_token.clone(),
                     scope: cmd.scope.clone(),
+                    cnf,
                 };
-                service.refresh_token(&cmd)
+                service.refresh_token(&client, &cmd)
             }
             GrantType::ClientCredentials => {
                 let service = self.client_credentials_service();
                 let cmd = ClientCredentialsCmd {
-                    client_id: cmd.client_id.clone(),
-                    client_secret: cmd.client_secret.clone(),
                     scope: cmd.scope.clone(),
+                    cnf,
                 };
-                service.execute_client_credentials(&cmd)
+                service.execute_client_credentials(&client, &cmd)
             }
             GrantType::Password => {
                 let service = self.resource_owner_password_credentials_service();
                 let cmd = ResourceOwnerPasswordCredentialsCmd {
-                    client_id: cmd.client_id.clone(),
-                    client_secret: cmd.client_secret.clone(),
                     username: cmd.username.clone(),
                     password: cmd.password.clone(),
                     scope: cmd.scope.clone(),
+                    cnf,
+                };
+                service.execute_resource_owner_password_credentials(&client, &cmd)
+            }
+            GrantType::DeviceCode => {
+                let service = self.device_authorization_service();
+                let cmd = DeviceCodeCmd {
+                    device_code: cmd.device_code.clone(),
+                    cnf,
+                };
+                service.execute_device_code(&client, &cmd)
+            }
+            GrantType::TokenExchange => {
+                let service = self.token_exchange_service();
+                let cmd = TokenExchangeCmd {
+                    subject_token: cmd.subject_token.clone(),
+                    subject_token_type: cmd.subject_token_type.clone(),
+                    actor_token: cmd.actor_token.clone(),
+                    actor_token_type: cmd.actor_token_type.clone(),
+                    resource: cmd.resource.clone(),
+                    audience: cmd.audience.clone(),
+                    requested_token_type: cmd.requested_token_type.clone(),
+                    scope: cmd.scope.clone(),
+                    cnf,
                 };
-                service.execute_resource_owner_password_credentials(&cmd)
+                service.execute_token_exchange(&client, &cmd)
+            }
+            GrantType::JwtBearer => {
+                let service = self.jwt_bearer_service();
+                let cmd = JwtBearerCmd {
+                    assertion: cmd.assertion.clone(),
+                    scope: cmd.scope.clone(),
+                    cnf,
+                };
+                service.execute_jwt_bearer(&client, &cmd)
             }
             GrantType::Undefined(ref raw) => TokensRet::error(
                 ek::UnsupportedGrantType(format!("Unsupported grant_type. {}", raw)).into(),
@@ -100,14 +237,45 @@ pub trait OidcService
         service.introspect(cmd)
     }
 
+    fn revoke_token(&self, cmd: &RevocationCmd) -> Result<(), ed::Error> {
+        let service = self.revocation_service();
+        service.revoke(cmd)
+    }
+
+    fn end_session(&self, cmd: &EndSessionCmd) -> Result<EndSessionRet, ed::Error> {
+        let service = self.end_session_service();
+        service.end_session(cmd)
+    }
+
+    /// Notifies the clients in the session of the logout through the back channel.
+    /// The logout itself succeeds even if the notification fails.
+    fn notify_logout(&self, sid: &String, end_user_id: &String) {
+        let service = self.backchannel_logout_service();
+        if let Err(e) = service.notify_logout(sid, end_user_id) {
+            error!("{}", e.display_chain().to_string());
+        }
+    }
+
+    /// Sends the queued logout tokens. It is called periodically in the background.
+    fn deliver_pending_logout_tokens(&self) {
+        let service = self.backchannel_logout_service();
+        if let Err(e) = service.deliver_pending_logout_tokens() {
+            error!("{}", e.display_chain().to_string());
+        }
+    }
+
     fn get_userinfo(&self, cmd: &UserinfoCmd) -> Result<EndUserClaims, ed::Error> {
         let service = self.userinfo_service();
         service.get_userinfo(cmd)
     }
 
-    fn get_publickey(&self) -> String {
+    fn get_jwks(&self) -> Result<JwkSet, ed::Error> {
         let service = self.key_service();
-        service.jwt_public_key_pem().clone()
+        service.jwt_public_jwk_set()
+    }
+
+    fn get_provider_metadata(&self) -> ProviderMetadata {
+        ProviderMetadata::new(&AppConfig::issuer(), &AppConfig::endpoint())
     }
 }
 
@@ -119,10 +287,19 @@ pub trait OidcServiceComponent {
 // Implement
 impl<
     T: AuthorizeServiceComponent
+        + BackchannelLogoutServiceComponent
+        + ClientAuthenticationServiceComponent
         + ClientCredentialsServiceComponent
+        + DeviceAuthorizationServiceComponent
+        + DpopServiceComponent
+        + EndSessionServiceComponent
         + IntrospectServiceComponent
+        + JwtBearerServiceComponent
+        + PushedAuthorizationServiceComponent
         + RefreshTokenServiceComponent
         + ResourceOwnerPasswordCredentialsServiceComponent
+        + RevocationServiceComponent
+        + TokenExchangeServiceComponent
         + UserinfoServiceComponent
         + KeyServiceComponent,
 > OidcService for T
@@ -1,4 +1,6 @@
+use base64;
 use std::env;
+use std::net::IpAddr;
 
 pub struct AppConfig {}
 
@@ -7,6 +9,9 @@ impl AppConfig {
     pub fn issuer() -> String {
         env::var("ISSUER").unwrap()
     }
+    pub fn endpoint() -> String {
+        env::var("ENDPOINT").unwrap()
+    }
     pub fn default_grant_max_age_sec() -> i64 {
         env::var("DEFAULT_GRANT_MAX_AGE_SEC")
             .map(|s| s.parse::<i64>().unwrap())
@@ -28,6 +33,135 @@ impl AppConfig {
             .unwrap()
     }
 
+    /// Returns the initial access token required for Dynamic Client Registration.
+    pub fn registration_initial_access_token() -> Option<String> {
+        match env::var("REGISTRATION_INITIAL_ACCESS_TOKEN") {
+            Ok(ref s) if !s.is_empty() => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns true if anyone can register clients without the initial access token.
+    pub fn registration_open() -> bool {
+        env::var("REGISTRATION_OPEN")
+            .map(|s| s.parse::<bool>().unwrap())
+            .unwrap_or(false)
+    }
+
+    /// Returns how long a login satisfies `prompt=login` after the authentication.
+    pub fn prompt_login_grace_sec() -> i64 {
+        env::var("PROMPT_LOGIN_GRACE_SEC")
+            .map(|s| s.parse::<i64>().unwrap())
+            .unwrap()
+    }
+
+    /// Returns how long a JWT secured authorization response is valid.
+    pub fn authorization_response_max_age_sec() -> i64 {
+        env::var("AUTHORIZATION_RESPONSE_MAX_AGE_SEC")
+            .map(|s| s.parse::<i64>().unwrap())
+            .unwrap()
+    }
+
+    /// Returns how many times a logout token is sent to each client.
+    pub fn backchannel_logout_max_attempts() -> i64 {
+        env::var("BACKCHANNEL_LOGOUT_MAX_ATTEMPTS")
+            .map(|s| s.parse::<i64>().unwrap())
+            .unwrap()
+    }
+    /// Returns the interval before the first retry of a logout token,
+    /// which doubles on every failure.
+    pub fn backchannel_logout_retry_interval_sec() -> i64 {
+        env::var("BACKCHANNEL_LOGOUT_RETRY_INTERVAL_SEC")
+            .map(|s| s.parse::<i64>().unwrap())
+            .unwrap()
+    }
+    /// Returns how often pending logout tokens are sent.
+    pub fn backchannel_logout_poll_interval_sec() -> u64 {
+        env::var("BACKCHANNEL_LOGOUT_POLL_INTERVAL_SEC")
+            .map(|s| s.parse::<u64>().unwrap())
+            .unwrap()
+    }
+    pub fn backchannel_logout_timeout_sec() -> u64 {
+        env::var("BACKCHANNEL_LOGOUT_TIMEOUT_SEC")
+            .map(|s| s.parse::<u64>().unwrap())
+            .unwrap()
+    }
+    pub fn request_object_fetch_timeout_sec() -> u64 {
+        env::var("REQUEST_OBJECT_FETCH_TIMEOUT_SEC")
+            .map(|s| s.parse::<u64>().unwrap())
+            .unwrap()
+    }
+    /// Returns the largest request object which is fetched from a request_uri.
+    pub fn request_object_max_bytes() -> u64 {
+        env::var("REQUEST_OBJECT_MAX_BYTES")
+            .map(|s| s.parse::<u64>().unwrap())
+            .unwrap()
+    }
+    /// Returns the longest seconds until request objects expire.
+    pub fn request_object_max_age_sec() -> i64 {
+        env::var("REQUEST_OBJECT_MAX_AGE_SEC")
+            .map(|s| s.parse::<i64>().unwrap())
+            .unwrap()
+    }
+    pub fn pushed_authorization_request_max_age_sec() -> i64 {
+        env::var("PUSHED_AUTHORIZATION_REQUEST_MAX_AGE_SEC")
+            .map(|s| s.parse::<i64>().unwrap())
+            .unwrap()
+    }
+    pub fn device_code_max_age_sec() -> i64 {
+        env::var("DEVICE_CODE_MAX_AGE_SEC")
+            .map(|s| s.parse::<i64>().unwrap())
+            .unwrap()
+    }
+    /// Returns the minimum seconds which the device waits between polling requests.
+    pub fn device_code_interval_sec() -> i64 {
+        env::var("DEVICE_CODE_INTERVAL_SEC")
+            .map(|s| s.parse::<i64>().unwrap())
+            .unwrap()
+    }
+    /// Returns the page of the SPA where the end user enters the user_code.
+    pub fn device_verification_uri() -> String {
+        env::var("DEVICE_VERIFICATION_URI").unwrap()
+    }
+    /// Returns the number of wrong user_codes which an end user can type.
+    pub fn device_user_code_max_attempts() -> i64 {
+        env::var("DEVICE_USER_CODE_MAX_ATTEMPTS")
+            .map(|s| s.parse::<i64>().unwrap())
+            .unwrap()
+    }
+    /// Returns the longest seconds until client assertions expire.
+    pub fn client_assertion_max_age_sec() -> i64 {
+        env::var("CLIENT_ASSERTION_MAX_AGE_SEC")
+            .map(|s| s.parse::<i64>().unwrap())
+            .unwrap()
+    }
+    /// Returns the seconds which DPoP proofs are accepted around their `iat`.
+    pub fn dpop_proof_max_age_sec() -> i64 {
+        env::var("DPOP_PROOF_MAX_AGE_SEC")
+            .map(|s| s.parse::<i64>().unwrap())
+            .unwrap()
+    }
+    /// Returns the header in which the TLS terminating proxy forwards the client certificate.
+    pub fn tls_client_cert_header() -> String {
+        env::var("TLS_CLIENT_CERT_HEADER").unwrap()
+    }
+    /// Returns the header in which the proxy forwards the result of verifying the chain.
+    pub fn tls_client_cert_verify_header() -> String {
+        env::var("TLS_CLIENT_CERT_VERIFY_HEADER").unwrap()
+    }
+    /// Returns the addresses of the proxies allowed to forward the client certificate.
+    pub fn tls_client_cert_trusted_proxies() -> Vec<IpAddr> {
+        env::var("TLS_CLIENT_CERT_TRUSTED_PROXIES")
+            .map(|s| {
+                s.split(',')
+                    .map(|ip| ip.trim())
+                    .filter(|ip| !ip.is_empty())
+                    .map(|ip| ip.parse::<IpAddr>().unwrap())
+                    .collect()
+            })
+            .unwrap()
+    }
+
     // MongoDB
     pub fn mongo_db() -> String {
         env::var("MONGO_DB").unwrap()
@@ -53,7 +187,19 @@ impl AppConfig {
     pub fn jwt_public_key() -> String {
         env::var("JWT_PUBLIC_KEY").unwrap()
     }
-    pub fn jwt_public_key_pem() -> String {
-        env::var("JWT_PUBLIC_KEY_PEM").unwrap()
+    /// Returns the key to encrypt the private signing keys stored in the database.
+    /// Panics unless the key is set to a base64 encoded 256-bit key.
+    pub fn signing_key_encryption_key() -> Vec<u8> {
+        let key = env::var("SIGNING_KEY_ENCRYPTION_KEY")
+            .ok()
+            .and_then(|s| base64::decode(&s).ok())
+            .unwrap_or_default();
+        if key.len() != 32 {
+            panic!(
+                "SIGNING_KEY_ENCRYPTION_KEY must be a base64 encoded 256-bit key. \
+                 Generate one by `openssl rand -base64 32`."
+            );
+        }
+        key
     }
 }
@@ -5,4 +5,7 @@ pub const ACTION_ACCEPT: &str = "accept";
 pub const ADMIN_SESS_ID_FIELD: &str = "admin_id";
 pub const CLIENT_SESS_ID_FIELD: &str = "client_id";
 pub const END_USER_SESS_ID_FIELD: &str = "end_user_id";
+pub const END_USER_OIDC_SID_FIELD: &str = "oidc_sid";
+pub const END_USER_AUTH_TIME_FIELD: &str = "auth_time";
+pub const END_USER_AMR_FIELD: &str = "amr";
 pub const RESOURCE_SESS_ID_FIELD: &str = "resource_id";
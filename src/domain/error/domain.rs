@@ -57,11 +57,81 @@ error_chain! {
             display("Invalid token: {}", desc)
         }
 
+        InvalidRedirectUri(desc: String) {
+            description("invalid_redirect_uri")
+            display("Invalid redirect uri: {}", desc)
+        }
+
+        InvalidClientMetadata(desc: String) {
+            description("invalid_client_metadata")
+            display("Invalid client metadata: {}", desc)
+        }
+
+        LoginRequired(desc: String) {
+            description("login_required")
+            display("Login required: {}", desc)
+        }
+
+        ConsentRequired(desc: String) {
+            description("consent_required")
+            display("Consent required: {}", desc)
+        }
+
+        InteractionRequired(desc: String) {
+            description("interaction_required")
+            display("Interaction required: {}", desc)
+        }
+
+        UnmetAuthenticationRequirements(desc: String) {
+            description("unmet_authentication_requirements")
+            display("Unmet authentication requirements: {}", desc)
+        }
+
+        InvalidRequestObject(desc: String) {
+            description("invalid_request_object")
+            display("Invalid request object: {}", desc)
+        }
+
+        InvalidRequestUri(desc: String) {
+            description("invalid_request_uri")
+            display("Invalid request uri: {}", desc)
+        }
+
+        AuthorizationPending(desc: String) {
+            description("authorization_pending")
+            display("Authorization pending: {}", desc)
+        }
+
+        SlowDown(desc: String) {
+            description("slow_down")
+            display("Slow down: {}", desc)
+        }
+
+        ExpiredToken(desc: String) {
+            description("expired_token")
+            display("Expired token: {}", desc)
+        }
+
+        InvalidTarget(desc: String) {
+            description("invalid_target")
+            display("Invalid target: {}", desc)
+        }
+
+        InvalidDpopProof(desc: String) {
+            description("invalid_dpop_proof")
+            display("Invalid DPoP proof: {}", desc)
+        }
+
         UserinfoError(desc: String) {
             description("invalid_token")
             display("{}", desc)
         }
 
+        RegistrationTokenError(desc: String) {
+            description("invalid_token")
+            display("{}", desc)
+        }
+
         // Application errors
         RequireLogin(desc: String) {
             description("login_required")
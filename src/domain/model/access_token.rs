@@ -1,9 +1,32 @@
 use chrono::prelude::*;
+use std::collections::HashMap;
 use time::Duration;
 
 use config::AppConfig;
+use domain::model::{ClaimRequest, ClaimsRequest};
 use util::generate_random_id;
 
+/// `Actor` is the type that represents the party acting on behalf of the subject
+/// of a token issued by Token Exchange (RFC 8693).
+/// The nested `act` is the prior actor in the delegation chain.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Actor {
+    pub sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub act: Option<Box<Actor>>,
+}
+
+/// `Confirmation` is the type that represents the keys which an access token is bound to.
+/// `jkt` is the JWK thumbprint of the DPoP proof key (RFC 9449)
+/// and `x5t#S256` is the thumbprint of the client certificate (RFC 8705).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Confirmation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jkt: Option<String>,
+    #[serde(rename = "x5t#S256", skip_serializing_if = "Option::is_none")]
+    pub x5t_s256: Option<String>,
+}
+
 /// `AccessToken` is a type that represents an *access token*
 /// in the context of OAuth2 and OpenID Connect.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -18,6 +41,19 @@ pub struct AccessToken {
     pub end_user_id: Option<String>,
     pub state: Option<String>,
     pub nonce: Option<String>,
+    #[serde(default)]
+    pub acr: Option<String>,
+    #[serde(default)]
+    pub amr: Option<Vec<String>>,
+    /// The time when the end user authenticated in the session which authorized the token.
+    #[serde(default)]
+    pub auth_time: Option<i64>,
+    #[serde(default)]
+    pub claims: Option<ClaimsRequest>,
+    #[serde(default)]
+    pub act: Option<Actor>,
+    #[serde(default)]
+    pub cnf: Option<Confirmation>,
     pub is_deleted: bool,
 }
 
@@ -37,6 +73,25 @@ impl AccessToken {
         !(self.expires_at().timestamp() < Utc::now().timestamp() || self.is_deleted)
     }
 
+    /// Returns `DPoP` if the token is bound to a DPoP proof key (RFC 9449).
+    /// Certificate-bound tokens remain `Bearer` (RFC 8705).
+    pub fn token_type(&self) -> &str {
+        match self.cnf {
+            Some(Confirmation { jkt: Some(_), .. }) => "DPoP",
+            _ => "Bearer",
+        }
+    }
+
+    /// Returns the individual claims requested from the userinfo.
+    pub fn userinfo_claims(&self) -> Option<&HashMap<String, Option<ClaimRequest>>> {
+        self.claims.as_ref().and_then(|c| c.userinfo.as_ref())
+    }
+
+    /// Returns the individual claims requested in the id token.
+    pub fn id_token_claims(&self) -> Option<&HashMap<String, Option<ClaimRequest>>> {
+        self.claims.as_ref().and_then(|c| c.id_token.as_ref())
+    }
+
     pub fn builder(client_id: &String, resource_id: &String) -> AccessTokenBuilder {
         AccessTokenBuilder::new(client_id, resource_id)
     }
@@ -50,6 +105,12 @@ pub struct AccessTokenBuilder {
     scope: Vec<String>,
     state: Option<String>,
     nonce: Option<String>,
+    acr: Option<String>,
+    amr: Option<Vec<String>>,
+    auth_time: Option<i64>,
+    claims: Option<ClaimsRequest>,
+    act: Option<Actor>,
+    cnf: Option<Confirmation>,
 }
 
 impl AccessTokenBuilder {
@@ -62,6 +123,12 @@ impl AccessTokenBuilder {
             end_user_id: None,
             state: None,
             nonce: None,
+            acr: None,
+            amr: None,
+            auth_time: None,
+            claims: None,
+            act: None,
+            cnf: None,
         }
     }
 
@@ -97,6 +164,48 @@ impl AccessTokenBuilder {
         }
     }
 
+    pub fn acr(self, acr: &Option<String>) -> Self {
+        AccessTokenBuilder {
+            acr: acr.clone(),
+            ..self
+        }
+    }
+
+    pub fn amr(self, amr: &Option<Vec<String>>) -> Self {
+        AccessTokenBuilder {
+            amr: amr.clone(),
+            ..self
+        }
+    }
+
+    pub fn auth_time(self, auth_time: &Option<i64>) -> Self {
+        AccessTokenBuilder {
+            auth_time: auth_time.clone(),
+            ..self
+        }
+    }
+
+    pub fn claims(self, claims: &Option<ClaimsRequest>) -> Self {
+        AccessTokenBuilder {
+            claims: claims.clone(),
+            ..self
+        }
+    }
+
+    pub fn act(self, act: &Option<Actor>) -> Self {
+        AccessTokenBuilder {
+            act: act.clone(),
+            ..self
+        }
+    }
+
+    pub fn cnf(self, cnf: &Option<Confirmation>) -> Self {
+        AccessTokenBuilder {
+            cnf: cnf.clone(),
+            ..self
+        }
+    }
+
     pub fn build(self) -> AccessToken {
         AccessToken {
             id: generate_random_id(32usize),
@@ -109,6 +218,12 @@ impl AccessTokenBuilder {
             end_user_id: self.end_user_id,
             state: self.state,
             nonce: self.nonce,
+            acr: self.acr,
+            amr: self.amr,
+            auth_time: self.auth_time,
+            claims: self.claims,
+            act: self.act,
+            cnf: self.cnf,
             is_deleted: false,
         }
     }
@@ -0,0 +1,140 @@
+/// `AuthenticationMethod` is the type that represents a value of `amr`,
+/// the method used to authenticate the end user in a session.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthenticationMethod {
+    Pwd,
+}
+
+impl AuthenticationMethod {
+    pub fn new(method: &str) -> Option<Self> {
+        match method {
+            "pwd" => Some(AuthenticationMethod::Pwd),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &str {
+        match *self {
+            AuthenticationMethod::Pwd => "pwd",
+        }
+    }
+
+    /// Returns `AuthenticationMethod`s stored in the session as a space-delimited string.
+    /// Unknown methods are ignored.
+    pub fn from_str(methods: &Option<String>) -> Vec<Self> {
+        let mut ret = Vec::new();
+        if let Some(ref methods) = *methods {
+            for m in methods.split(' ').filter_map(AuthenticationMethod::new) {
+                if !ret.contains(&m) {
+                    ret.push(m);
+                }
+            }
+        }
+        ret
+    }
+
+    /// Returns the space-delimited string to be stored in the session.
+    pub fn to_space_delimited(methods: &Vec<Self>) -> String {
+        methods
+            .iter()
+            .map(|m| m.to_str())
+            .collect::<Vec<&str>>()
+            .join(" ")
+    }
+
+    /// Returns the value of `amr` claim.
+    pub fn to_amr(methods: &Vec<Self>) -> Vec<String> {
+        methods.iter().map(|m| m.to_str().to_string()).collect()
+    }
+}
+
+/// `AuthenticationLevel` is the type that represents a value of `acr`.
+/// The end user logs in only with a password, so no level above `SingleFactor` is defined.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum AuthenticationLevel {
+    None,
+    SingleFactor,
+}
+
+impl AuthenticationLevel {
+    pub fn new(acr: &str) -> Option<Self> {
+        match acr {
+            "0" => Some(AuthenticationLevel::None),
+            "1" => Some(AuthenticationLevel::SingleFactor),
+            _ => None,
+        }
+    }
+
+    /// Returns the levels which a login of this server can achieve.
+    pub fn supported() -> Vec<Self> {
+        vec![AuthenticationLevel::None, AuthenticationLevel::SingleFactor]
+    }
+
+    pub fn to_str(&self) -> &str {
+        match *self {
+            AuthenticationLevel::None => "0",
+            AuthenticationLevel::SingleFactor => "1",
+        }
+    }
+
+    /// Returns the level which the authentication methods of a session achieve.
+    pub fn from_methods(methods: &Vec<AuthenticationMethod>) -> Self {
+        if methods.is_empty() {
+            AuthenticationLevel::None
+        } else {
+            AuthenticationLevel::SingleFactor
+        }
+    }
+
+    /// Returns the lowest level of the space-delimited `acr_values`.
+    /// Unknown values are ignored, so `None` is returned if no value is known.
+    pub fn from_acr_values(acr_values: &Option<String>) -> Option<Self> {
+        let mut ret: Option<Self> = None;
+        if let Some(ref acr_values) = *acr_values {
+            for level in acr_values.split(' ').filter_map(AuthenticationLevel::new) {
+                ret = match ret {
+                    Some(r) => Some(if level < r { level } else { r }),
+                    None => Some(level),
+                };
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_convert_methods_to_amr_and_acr() {
+        let methods = AuthenticationMethod::from_str(&Some("pwd unknown pwd".to_string()));
+        assert_eq!(methods, vec![AuthenticationMethod::Pwd]);
+        assert_eq!(AuthenticationMethod::to_amr(&methods), vec!["pwd".to_string()]);
+        assert_eq!(
+            AuthenticationLevel::from_methods(&methods),
+            AuthenticationLevel::SingleFactor
+        );
+        assert_eq!(
+            AuthenticationLevel::from_methods(&AuthenticationMethod::from_str(&None)),
+            AuthenticationLevel::None
+        );
+    }
+
+    #[test]
+    fn should_parse_acr_values() {
+        assert_eq!(
+            AuthenticationLevel::from_acr_values(&Some("2 1 unknown".to_string())),
+            Some(AuthenticationLevel::SingleFactor)
+        );
+        assert_eq!(
+            AuthenticationLevel::from_acr_values(&Some("unknown".to_string())),
+            None
+        );
+        assert_eq!(
+            AuthenticationLevel::from_acr_values(&Some("2".to_string())),
+            None
+        );
+        assert_eq!(AuthenticationLevel::from_acr_values(&None), None);
+    }
+}
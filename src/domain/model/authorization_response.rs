@@ -0,0 +1,135 @@
+use chrono::prelude::*;
+use jsonwebtoken::{encode, Algorithm, Header};
+use serde::Serialize;
+use serde_json::{self, Map, Value};
+
+use domain::error::domain as ed;
+use domain::model::SigningKey;
+use self::ed::ResultExt;
+
+/// `AuthorizationResponseClaims` is the claims of an authorization response
+/// returned as a JWT by JWT Secured Authorization Response Mode (JARM).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuthorizationResponseClaims {
+    pub iss: String,
+    pub aud: String,
+    pub exp: i64,
+    /// The parameters of the authorization response such as `code` and `state`.
+    #[serde(flatten)]
+    pub params: Map<String, Value>,
+}
+
+impl AuthorizationResponseClaims {
+    /// The response expires `max_age_sec` seconds later.
+    pub fn new<T: Serialize>(
+        issuer: &str,
+        client_id: &String,
+        params: &T,
+        max_age_sec: i64,
+    ) -> Result<Self, ed::Error> {
+        let params = match serde_json::to_value(params) {
+            Ok(Value::Object(map)) => map.into_iter().filter(|&(_, ref v)| !v.is_null()).collect(),
+            _ => {
+                return Err(ed::ErrorKind::ServerError(
+                    "The authorization response is not an object.".to_string(),
+                ).into())
+            }
+        };
+        Ok(AuthorizationResponseClaims {
+            iss: issuer.to_string(),
+            aud: client_id.clone(),
+            exp: Utc::now().timestamp() + max_age_sec,
+            params,
+        })
+    }
+
+    /// Signs the claims with the key and returns the `response` parameter.
+    pub fn publish(&self, signing_key: &SigningKey) -> Result<String, ed::Error> {
+        let mut header = Header::default();
+        header.alg = Algorithm::RS256;
+        header.kid = Some(signing_key.id.clone());
+        let key = signing_key.private_key_der()?;
+        encode(&header, self, key.as_ref()).chain_err(|| {
+            ed::ErrorKind::ServerError("Encoding claims to JWT failed.".to_string())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use jsonwebtoken::{decode, Validation};
+    use domain::model::{test_signing_key, Jwk, OTHER_PUBLIC_KEY};
+    use util::decode_jwt_header;
+
+    #[test]
+    fn should_sign_authorization_response() {
+        let mut params = HashMap::new();
+        params.insert("code", Some("abc"));
+        params.insert("state", None);
+        let claims = AuthorizationResponseClaims::new(
+            "https://op.example.com",
+            &"client".to_string(),
+            &params,
+            600,
+        ).unwrap();
+        let key = test_signing_key();
+        let response = claims.publish(&key).unwrap();
+
+        // The response is verified with the key published in the JWK Set.
+        let jwk = key.to_jwk().unwrap();
+        assert_eq!(decode_jwt_header(&response).unwrap().kid, jwk.kid);
+        let mut validation = Validation {
+            algorithms: Some(vec![Algorithm::RS256]),
+            iss: Some("https://op.example.com".to_string()),
+            ..Validation::default()
+        };
+        validation.set_audience(&"client");
+        let decoded = decode::<AuthorizationResponseClaims>(
+            &response,
+            &jwk.to_rsa_public_key_der().unwrap(),
+            &validation,
+        ).unwrap()
+            .claims;
+        let now = Utc::now().timestamp();
+        assert!(decoded.exp > now + 590 && decoded.exp <= now + 600);
+        assert_eq!(decoded.params.get("code"), Some(&Value::String("abc".to_string())));
+        assert!(decoded.params.get("state").is_none());
+
+        let other = Jwk::from_rsa_public_key_der(OTHER_PUBLIC_KEY).unwrap();
+        assert!(
+            decode::<AuthorizationResponseClaims>(
+                &response,
+                &other.to_rsa_public_key_der().unwrap(),
+                &validation,
+            ).is_err()
+        );
+        validation.set_audience(&"other");
+        assert!(
+            decode::<AuthorizationResponseClaims>(
+                &response,
+                &jwk.to_rsa_public_key_der().unwrap(),
+                &validation,
+            ).is_err()
+        );
+
+        // An expired response
+        validation.set_audience(&"client");
+        let expired = AuthorizationResponseClaims::new(
+            "https://op.example.com",
+            &"client".to_string(),
+            &params,
+            -60,
+        ).unwrap()
+            .publish(&key)
+            .unwrap();
+        assert!(
+            decode::<AuthorizationResponseClaims>(
+                &expired,
+                &jwk.to_rsa_public_key_der().unwrap(),
+                &validation,
+            ).is_err()
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use serde_json::{self, Value};
+use std::collections::HashMap;
+
+use domain::error::domain as ed;
+
+/// `ClaimRequest` is the type that represents a request of an individual claim.
+/// `null` in the `claims` parameter is the same as the default request.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ClaimRequest {
+    #[serde(default)]
+    pub essential: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<Value>>,
+}
+
+impl ClaimRequest {
+    /// Returns the string values requested by `value` and `values`.
+    pub fn requested_values(&self) -> Vec<String> {
+        let mut ret = Vec::new();
+        if let Some(Value::String(ref v)) = self.value {
+            ret.push(v.clone());
+        }
+        if let Some(ref values) = self.values {
+            for v in values {
+                if let Value::String(ref v) = *v {
+                    ret.push(v.clone());
+                }
+            }
+        }
+        ret
+    }
+}
+
+/// `ClaimsRequest` is the type that represents a value of `claims` parameter,
+/// which requests individual claims to be returned from the userinfo and in the id token.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ClaimsRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub userinfo: Option<HashMap<String, Option<ClaimRequest>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<HashMap<String, Option<ClaimRequest>>>,
+}
+
+impl ClaimsRequest {
+    pub fn from_str(claims: &Option<String>) -> Result<Option<Self>, ed::Error> {
+        match *claims {
+            Some(ref c) => serde_json::from_str::<ClaimsRequest>(c)
+                .map(|v| Some(v))
+                .map_err(|_| {
+                    ed::ErrorKind::InvalidRequest(format!("Invalid claims. claims => {}", c))
+                        .into()
+                }),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the space-delimited values of the essential `acr` of the id token.
+    /// Voluntary `acr` and essential `acr` without values require nothing.
+    pub fn essential_acr_values(&self) -> Option<String> {
+        let request = self.id_token
+            .as_ref()
+            .and_then(|c| c.get("acr"))
+            .and_then(|r| r.as_ref());
+        match request {
+            Some(r) if r.essential && !r.requested_values().is_empty() => {
+                Some(r.requested_values().join(" "))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_claims() {
+        let claims = ClaimsRequest::from_str(&Some(
+            r#"{
+                "userinfo": {"given_name": {"essential": true}, "nickname": null},
+                "id_token": {"acr": {"essential": true, "values": ["2", "1"]}}
+            }"#.to_string(),
+        )).unwrap()
+            .unwrap();
+        let userinfo = claims.userinfo.as_ref().unwrap();
+        assert!(userinfo.get("given_name").unwrap().as_ref().unwrap().essential);
+        assert_eq!(userinfo.get("nickname").unwrap(), &None);
+        assert_eq!(
+            claims.essential_acr_values(),
+            Some("2 1".to_string())
+        );
+
+        assert!(ClaimsRequest::from_str(&None).unwrap().is_none());
+        assert!(ClaimsRequest::from_str(&Some("{".to_string())).is_err());
+    }
+}
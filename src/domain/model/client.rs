@@ -1,7 +1,9 @@
 use chrono::prelude::*;
 
 use domain::error::domain as ed;
-use util::{generate_random_id, hash_str};
+use domain::model::{ClientMetadata, Confirmation, GrantType, ResponseType,
+                    TOKEN_ENDPOINT_AUTH_METHOD_BASIC, TOKEN_ENDPOINT_AUTH_METHOD_NONE};
+use util::{constant_time_eq, generate_random_id, hash_str};
 
 /// `ClientType` is the type of client in the context of
 /// OAuth2 and OpenID Connect
@@ -38,6 +40,13 @@ pub struct Client {
     pub client_secret: String,
     pub redirect_uris: Vec<String>,
     pub resource_id: String,
+    #[serde(default)]
+    pub require_pkce: bool,
+    #[serde(default)]
+    pub metadata: ClientMetadata,
+    /// The hashed registration access token for Dynamic Client Registration.
+    #[serde(default)]
+    pub registration_access_token: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub is_deleted: bool,
@@ -54,11 +63,108 @@ impl Client {
         &self.client_secret == secret
     }
 
+    /// Issues a new registration access token and returns the raw value.
+    /// Only the hash is stored in this client.
+    pub fn issue_registration_access_token(&mut self) -> String {
+        let token = generate_random_id(64usize);
+        self.registration_access_token = Some(hash_str(&token));
+        token
+    }
+
+    /// Authenticates a client by a registration access token.
+    pub fn is_authenticated_by_registration_access_token(&self, token: &String) -> bool {
+        self.registration_access_token
+            .as_ref()
+            .map_or(false, |t| constant_time_eq(t.as_bytes(), hash_str(token).as_bytes()))
+    }
+
+    /// Returns true if this client is a public client.
+    pub fn is_public(&self) -> bool {
+        match self.client_type {
+            ClientType::Public => true,
+            ClientType::Confidential => false,
+        }
+    }
+
+    /// Returns the method to authenticate this client at the token endpoint.
+    /// Clients registered without metadata fall back to the method of the client type.
+    pub fn token_endpoint_auth_method(&self) -> &str {
+        match self.metadata.token_endpoint_auth_method {
+            Some(ref m) => m,
+            None if self.is_public() => TOKEN_ENDPOINT_AUTH_METHOD_NONE,
+            None => TOKEN_ENDPOINT_AUTH_METHOD_BASIC,
+        }
+    }
+
+    /// Returns the confirmation which the tokens issued to this client are bound to.
+    /// The certificate is bound only if the client requested certificate-bound access tokens.
+    pub fn confirmation(
+        &self,
+        cnf: &Option<Confirmation>,
+    ) -> Result<Option<Confirmation>, ed::Error> {
+        let cnf = cnf.clone().unwrap_or_default();
+        let x5t_s256 = if self.metadata.tls_client_certificate_bound_access_tokens {
+            if cnf.x5t_s256.is_none() {
+                return Err(ed::ErrorKind::InvalidRequest(
+                    "The client certificate is required to bind access tokens.".to_string(),
+                ).into());
+            }
+            cnf.x5t_s256
+        } else {
+            None
+        };
+        if cnf.jkt.is_none() && x5t_s256.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Confirmation {
+            jkt: cnf.jkt,
+            x5t_s256,
+        }))
+    }
+
+    /// Returns true if the client has registered the grant type.
+    /// Clients registered without grant_types fall back to authorization_code,
+    /// the default of the client registration.
+    pub fn allows_grant_type(&self, grant_type: &GrantType) -> bool {
+        if self.metadata.grant_types.is_empty() {
+            return grant_type.to_str() == GrantType::AuthorizationCode.to_str();
+        }
+        self.metadata
+            .grant_types
+            .iter()
+            .any(|g| g == grant_type.to_str())
+    }
+
+    /// Returns true if the client has registered the response_type in any order of the values.
+    /// Clients registered without response_types fall back to code.
+    pub fn allows_response_type(&self, response_type: &ResponseType) -> bool {
+        let sorted = |values: Vec<String>| {
+            let mut values = values;
+            values.sort();
+            values
+        };
+        let requested = sorted(response_type.to_vec());
+        if self.metadata.response_types.is_empty() {
+            return requested == vec!["code".to_string()];
+        }
+        self.metadata.response_types.iter().any(|r| {
+            sorted(r.split(' ').map(|v| v.to_string()).collect()) == requested
+        })
+    }
+
     /// Returns true if the redirect_uri matches any uris of this client.
     pub fn validate_redirect_uri(&self, redirect_uri: &String) -> bool {
         self.redirect_uris.iter().any(|uri| uri == redirect_uri)
     }
 
+    /// Returns true if the post_logout_redirect_uri matches any registered uris of this client.
+    pub fn validate_post_logout_redirect_uri(&self, post_logout_redirect_uri: &String) -> bool {
+        self.metadata
+            .post_logout_redirect_uris
+            .iter()
+            .any(|uri| uri == post_logout_redirect_uri)
+    }
+
     pub fn update_password(
         &mut self,
         new_password: &String,
@@ -89,6 +195,8 @@ impl Client {
             client_type: ClientType::Confidential,
             redirect_uris: Vec::new(),
             resource_id: resource_id.clone(),
+            require_pkce: false,
+            metadata: ClientMetadata::default(),
         }
     }
 }
@@ -100,6 +208,8 @@ pub struct ClientBuilder {
     client_type: ClientType,
     redirect_uris: Vec<String>,
     resource_id: String,
+    require_pkce: bool,
+    metadata: ClientMetadata,
 }
 
 impl ClientBuilder {
@@ -117,6 +227,20 @@ impl ClientBuilder {
         }
     }
 
+    pub fn require_pkce(self, require_pkce: bool) -> Self {
+        ClientBuilder {
+            require_pkce,
+            ..self
+        }
+    }
+
+    pub fn metadata(self, metadata: &ClientMetadata) -> Self {
+        ClientBuilder {
+            metadata: metadata.clone(),
+            ..self
+        }
+    }
+
     pub fn build(self) -> Client {
         let created_at = Utc::now();
         Client {
@@ -128,9 +252,57 @@ impl ClientBuilder {
             client_secret: generate_random_id(32usize),
             redirect_uris: self.redirect_uris,
             resource_id: self.resource_id,
+            require_pkce: self.require_pkce,
+            metadata: self.metadata,
+            registration_access_token: None,
             created_at,
             updated_at: created_at,
             is_deleted: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::model::test_client;
+
+    fn registered(grant_types: Vec<&str>, response_types: Vec<&str>) -> Client {
+        let metadata = ClientMetadata {
+            grant_types: grant_types.iter().map(|v| v.to_string()).collect(),
+            response_types: response_types.iter().map(|v| v.to_string()).collect(),
+            ..ClientMetadata::default()
+        };
+        test_client("client", metadata)
+    }
+
+    #[test]
+    fn should_allow_only_registered_grant_types() {
+        let client = registered(vec!["authorization_code", "refresh_token"], vec![]);
+        assert!(client.allows_grant_type(&GrantType::AuthorizationCode));
+        assert!(client.allows_grant_type(&GrantType::RefreshToken));
+        assert!(!client.allows_grant_type(&GrantType::ClientCredentials));
+        assert!(!client.allows_grant_type(&GrantType::Password));
+
+        // Falls back to authorization_code
+        let client = registered(vec![], vec![]);
+        assert!(client.allows_grant_type(&GrantType::AuthorizationCode));
+        assert!(!client.allows_grant_type(&GrantType::RefreshToken));
+    }
+
+    #[test]
+    fn should_allow_only_registered_response_types() {
+        let response_type = |v: &str| ResponseType::from_str(&v.to_string()).unwrap();
+
+        let client = registered(vec![], vec!["code", "code id_token"]);
+        assert!(client.allows_response_type(&response_type("code")));
+        assert!(client.allows_response_type(&response_type("id_token code")));
+        assert!(!client.allows_response_type(&response_type("token")));
+        assert!(!client.allows_response_type(&response_type("code token")));
+
+        // Falls back to code
+        let client = registered(vec![], vec![]);
+        assert!(client.allows_response_type(&response_type("code")));
+        assert!(!client.allows_response_type(&response_type("id_token")));
+    }
+}
@@ -0,0 +1,139 @@
+use jsonwebtoken::{decode, Algorithm, Validation};
+use serde_json::Value;
+
+use domain::error::domain as ed;
+use domain::model::JwkSet;
+use util::decode_jwt_header;
+
+pub const CLIENT_ASSERTION_TYPE_JWT_BEARER: &str =
+    "urn:ietf:params:oauth:client-assertion-type:jwt-bearer";
+
+/// `ClientAssertion` is the type that represents claims of a JWT
+/// which a client presents to authenticate itself (RFC 7523 section 2.2).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientAssertion {
+    pub iss: String,
+    pub sub: String,
+    pub aud: Value,
+    pub exp: i64,
+    pub jti: String,
+    #[serde(default)]
+    pub iat: Option<i64>,
+}
+
+impl ClientAssertion {
+    /// Reads `sub` without the verification to find the client.
+    pub fn peek_subject(token: &str) -> Result<String, ed::Error> {
+        let unverified = Validation {
+            validate_signature: false,
+            validate_exp: false,
+            validate_iat: false,
+            validate_nbf: false,
+            ..Validation::default()
+        };
+        decode::<ClientAssertion>(token, &[], &unverified)
+            .map(|data| data.claims.sub)
+            .map_err(|e| {
+                ed::ErrorKind::UnauthorizedClient(format!("Invalid client assertion: {}", e)).into()
+            })
+    }
+
+    /// Decodes and verifies an assertion signed with the client secret (`client_secret_jwt`).
+    pub fn decode_with_secret(
+        token: &str,
+        client_id: &String,
+        secret: &String,
+    ) -> Result<Self, ed::Error> {
+        let validation = Self::validation(client_id, Algorithm::HS256);
+        decode::<ClientAssertion>(token, secret.as_bytes(), &validation)
+            .map(|data| data.claims)
+            .map_err(|e| {
+                ed::ErrorKind::UnauthorizedClient(format!("Verification failed: {}", e)).into()
+            })
+    }
+
+    /// Decodes and verifies an assertion signed with the private key of the client
+    /// (`private_key_jwt`). The key is selected in the same way as request objects.
+    pub fn decode_with_jwks(
+        token: &str,
+        client_id: &String,
+        jwks: &JwkSet,
+    ) -> Result<Self, ed::Error> {
+        let header = decode_jwt_header(token).ok_or::<ed::Error>(
+            ed::ErrorKind::UnauthorizedClient("Invalid JWT header.".to_string()).into(),
+        )?;
+        let jwk = match header.kid {
+            Some(ref kid) => jwks.find_by_kid(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        }.ok_or::<ed::Error>(
+            ed::ErrorKind::UnauthorizedClient("No key to verify the signature.".to_string())
+                .into(),
+        )?;
+        let validation = Self::validation(client_id, Algorithm::RS256);
+        decode::<ClientAssertion>(token, &jwk.to_rsa_public_key_der()?, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| {
+                ed::ErrorKind::UnauthorizedClient(format!("Verification failed: {}", e)).into()
+            })
+    }
+
+    /// Returns true if `aud` includes any of the audiences.
+    pub fn has_audience(&self, audiences: &Vec<String>) -> bool {
+        match self.aud {
+            Value::String(ref aud) => audiences.contains(aud),
+            Value::Array(ref auds) => auds.iter().any(|a| {
+                a.as_str()
+                    .map_or(false, |a| audiences.iter().any(|v| v == a))
+            }),
+            _ => false,
+        }
+    }
+
+    /// Both `iss` and `sub` MUST be the client_id.
+    fn validation(client_id: &String, algorithm: Algorithm) -> Validation {
+        Validation {
+            algorithms: Some(vec![algorithm]),
+            iss: Some(client_id.clone()),
+            sub: Some(client_id.clone()),
+            ..Validation::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::prelude::*;
+    use jsonwebtoken::{encode, Header};
+
+    fn assertion(iss: &str) -> ClientAssertion {
+        ClientAssertion {
+            iss: iss.to_string(),
+            sub: "client".to_string(),
+            aud: Value::String("http://127.0.0.1:8000/oidc/tokens".to_string()),
+            exp: Utc::now().timestamp() + 60,
+            jti: "jti".to_string(),
+            iat: None,
+        }
+    }
+
+    #[test]
+    fn should_verify_assertion_with_secret() {
+        let client_id = "client".to_string();
+        let secret = "secret".to_string();
+        let sign = |claims: &ClientAssertion, key: &str| {
+            encode(&Header::new(Algorithm::HS256), claims, key.as_bytes()).unwrap()
+        };
+
+        let token = sign(&assertion("client"), "secret");
+        assert_eq!(ClientAssertion::peek_subject(&token).unwrap(), "client");
+        assert!(ClientAssertion::decode_with_secret(&token, &client_id, &secret).is_ok());
+
+        let token = sign(&assertion("client"), "other");
+        assert!(ClientAssertion::decode_with_secret(&token, &client_id, &secret).is_err());
+
+        let token = sign(&assertion("other"), "secret");
+        assert!(ClientAssertion::decode_with_secret(&token, &client_id, &secret).is_err());
+    }
+}
@@ -0,0 +1,409 @@
+use base64::decode;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use domain::error::domain as ed;
+use domain::model::{ClientMetadata, Jwk, JwkSet};
+use util::{encode_base64url, encode_der, hash_sha256, DerReader, DER_TAG_BOOLEAN,
+           DER_TAG_IA5_STRING, DER_TAG_INTEGER, DER_TAG_OCTET_STRING, DER_TAG_OID,
+           DER_TAG_PRINTABLE_STRING, DER_TAG_SEQUENCE, DER_TAG_SET, DER_TAG_T61_STRING,
+           DER_TAG_UTF8_STRING};
+
+// Context-specific tags of TBSCertificate and GeneralName
+const DER_TAG_VERSION: u8 = 0xa0;
+const DER_TAG_EXTENSIONS: u8 = 0xa3;
+const DER_TAG_RFC822_NAME: u8 = 0x81;
+const DER_TAG_DNS_NAME: u8 = 0x82;
+const DER_TAG_URI: u8 = 0x86;
+const DER_TAG_IP_ADDRESS: u8 = 0x87;
+
+const OID_SUBJECT_ALT_NAME: &str = "2.5.29.17";
+
+/// `SubjectAltName` is the type represents an entry of subjectAltName of a certificate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubjectAltName {
+    Dns(String),
+    Uri(String),
+    Email(String),
+    Ip(IpAddr),
+}
+
+/// `ClientCertificate` is the type represents an X.509 certificate
+/// which a client presents in the mutual TLS handshake (RFC 8705).
+/// The chain of the certificate is verified by the TLS terminating proxy, not here.
+#[derive(Debug, Clone)]
+pub struct ClientCertificate {
+    pub der: Vec<u8>,
+    /// True if the proxy verified the chain against the trust anchors.
+    /// Self-signed certificates are forwarded without it.
+    pub verified: bool,
+}
+
+impl ClientCertificate {
+    /// Reads a PEM encoded certificate. The base64 encoded DER without the markers is accepted.
+    pub fn from_pem(pem: &str) -> Result<Self, ed::Error> {
+        let invalid = || -> ed::Error {
+            ed::ErrorKind::InvalidRequest("Invalid client certificate.".to_string()).into()
+        };
+        // Some proxies forward the PEM in a single line.
+        let body: String = pem.replace("-----BEGIN CERTIFICATE-----", "")
+            .replace("-----END CERTIFICATE-----", "")
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        let der = decode(&body).map_err(|_| invalid())?;
+        parse_tbs_certificate(&der).ok_or_else(&invalid)?;
+        Ok(ClientCertificate {
+            der,
+            verified: false,
+        })
+    }
+
+    /// Returns the base64url encoded SHA-256 thumbprint, `x5t#S256` of the certificate.
+    pub fn thumbprint(&self) -> String {
+        encode_base64url(&hash_sha256(&self.der))
+    }
+
+    /// Returns the subject DN in the string representation of RFC 4514.
+    pub fn subject_dn(&self) -> Option<String> {
+        parse_tbs_certificate(&self.der).and_then(|tbs| format_name(tbs.subject))
+    }
+
+    /// Returns the entries of subjectAltName which are used for `tls_client_auth`.
+    pub fn subject_alt_names(&self) -> Vec<SubjectAltName> {
+        parse_tbs_certificate(&self.der)
+            .and_then(|tbs| tbs.extensions)
+            .and_then(parse_subject_alt_names)
+            .unwrap_or_default()
+    }
+
+    /// Returns the public key of the certificate if it is an RSA key.
+    pub fn public_key(&self) -> Option<Jwk> {
+        parse_tbs_certificate(&self.der).and_then(|tbs| {
+            Jwk::from_rsa_public_key_der(&encode_der(DER_TAG_SEQUENCE, tbs.subject_public_key_info))
+                .ok()
+        })
+    }
+
+    /// Returns true if the certificate is issued to the client of `tls_client_auth`,
+    /// which is identified by the subject DN or one of the SAN entries (RFC 8705 section 2.1.2).
+    /// The subject is trusted only if the chain is verified.
+    pub fn is_issued_to(&self, metadata: &ClientMetadata) -> bool {
+        if !self.verified {
+            return false;
+        }
+        if let Some(ref dn) = metadata.tls_client_auth_subject_dn {
+            return self.subject_dn()
+                .map_or(false, |v| normalize_dn(&v) == normalize_dn(dn));
+        }
+        self.subject_alt_names().iter().any(|san| match *san {
+            SubjectAltName::Dns(ref v) => metadata
+                .tls_client_auth_san_dns
+                .as_ref()
+                .map_or(false, |d| d.eq_ignore_ascii_case(v)),
+            SubjectAltName::Uri(ref v) => metadata.tls_client_auth_san_uri.as_ref() == Some(v),
+            SubjectAltName::Email(ref v) => metadata.tls_client_auth_san_email.as_ref() == Some(v),
+            SubjectAltName::Ip(ref v) => metadata
+                .tls_client_auth_san_ip
+                .as_ref()
+                .and_then(|ip| ip.parse::<IpAddr>().ok())
+                .map_or(false, |ip| &ip == v),
+        })
+    }
+
+    /// Returns true if the public key of the self-signed certificate is one of the keys
+    /// registered by the client (RFC 8705 section 2.2.2).
+    pub fn has_key_in(&self, jwks: &JwkSet) -> bool {
+        self.public_key().map_or(false, |key| {
+            let thumbprint = key.thumbprint();
+            jwks.keys.iter().any(|k| k.thumbprint() == thumbprint)
+        })
+    }
+}
+
+/// The fields of TBSCertificate used to authenticate clients.
+struct TbsCertificate<'a> {
+    subject: &'a [u8],
+    subject_public_key_info: &'a [u8],
+    extensions: Option<&'a [u8]>,
+}
+
+// Private functions
+fn parse_tbs_certificate(der: &[u8]) -> Option<TbsCertificate> {
+    // Certificate: SEQUENCE { TBSCertificate, AlgorithmIdentifier, BIT STRING }
+    let cert = DerReader::new(der).read_expected(DER_TAG_SEQUENCE)?;
+    let tbs = DerReader::new(cert).read_expected(DER_TAG_SEQUENCE)?;
+    let mut reader = DerReader::new(tbs);
+    // The version is omitted for v1 certificates.
+    let mut field = reader.read()?;
+    if field.0 == DER_TAG_VERSION {
+        field = reader.read()?;
+    }
+    if field.0 != DER_TAG_INTEGER {
+        return None;
+    }
+    // signature, issuer and validity
+    for _ in 0..3 {
+        reader.read_expected(DER_TAG_SEQUENCE)?;
+    }
+    let subject = reader.read_expected(DER_TAG_SEQUENCE)?;
+    let subject_public_key_info = reader.read_expected(DER_TAG_SEQUENCE)?;
+    let mut extensions = None;
+    while let Some((tag, content)) = reader.read() {
+        if tag == DER_TAG_EXTENSIONS {
+            extensions = DerReader::new(content).read_expected(DER_TAG_SEQUENCE);
+        }
+    }
+    Some(TbsCertificate {
+        subject,
+        subject_public_key_info,
+        extensions,
+    })
+}
+
+/// Formats a Name as RFC 4514, which starts with the last RDN.
+fn format_name(name: &[u8]) -> Option<String> {
+    let mut rdns = Vec::new();
+    let mut reader = DerReader::new(name);
+    while !reader.is_empty() {
+        let set = reader.read_expected(DER_TAG_SET)?;
+        let mut set_reader = DerReader::new(set);
+        let mut attributes = Vec::new();
+        while !set_reader.is_empty() {
+            let attribute = set_reader.read_expected(DER_TAG_SEQUENCE)?;
+            let mut attribute_reader = DerReader::new(attribute);
+            let oid = format_oid(attribute_reader.read_expected(DER_TAG_OID)?)?;
+            let (tag, value) = attribute_reader.read()?;
+            attributes.push(format!(
+                "{}={}",
+                attribute_type(&oid),
+                attribute_value(tag, value)
+            ));
+        }
+        rdns.push(attributes.join("+"));
+    }
+    rdns.reverse();
+    Some(rdns.join(","))
+}
+
+fn attribute_type(oid: &str) -> String {
+    match oid {
+        "2.5.4.3" => "CN",
+        "2.5.4.6" => "C",
+        "2.5.4.7" => "L",
+        "2.5.4.8" => "ST",
+        "2.5.4.9" => "STREET",
+        "2.5.4.10" => "O",
+        "2.5.4.11" => "OU",
+        "0.9.2342.19200300.100.1.1" => "UID",
+        "0.9.2342.19200300.100.1.25" => "DC",
+        other => other,
+    }.to_string()
+}
+
+/// Escapes a string value, or returns the hex of the whole element for other types.
+fn attribute_value(tag: u8, value: &[u8]) -> String {
+    let is_string = [
+        DER_TAG_UTF8_STRING,
+        DER_TAG_PRINTABLE_STRING,
+        DER_TAG_T61_STRING,
+        DER_TAG_IA5_STRING,
+    ].contains(&tag);
+    match String::from_utf8(value.to_vec()) {
+        Ok(ref s) if is_string => {
+            let last = s.chars().count().saturating_sub(1);
+            s.chars().enumerate().fold(String::new(), |mut ret, (i, c)| {
+                let special = "\"+,;<>\\".contains(c) || (i == 0 && (c == '#' || c == ' '))
+                    || (i == last && c == ' ');
+                if special {
+                    ret.push('\\');
+                }
+                ret.push(c);
+                ret
+            })
+        }
+        _ => format!("#{}", to_hex(&encode_der(tag, value))),
+    }
+}
+
+fn format_oid(oid: &[u8]) -> Option<String> {
+    let mut values: Vec<u64> = Vec::new();
+    let mut value = 0u64;
+    for b in oid.iter() {
+        value = (value << 7) | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            values.push(value);
+            value = 0;
+        }
+    }
+    let first = *values.first()?;
+    let mut arcs = if first < 80 {
+        vec![first / 40, first % 40]
+    } else {
+        vec![2, first - 80]
+    };
+    arcs.extend(values[1..].iter().cloned());
+    Some(
+        arcs.iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join("."),
+    )
+}
+
+fn parse_subject_alt_names(extensions: &[u8]) -> Option<Vec<SubjectAltName>> {
+    let mut reader = DerReader::new(extensions);
+    while !reader.is_empty() {
+        // Extension: SEQUENCE { OID, BOOLEAN DEFAULT FALSE, OCTET STRING }
+        let extension = reader.read_expected(DER_TAG_SEQUENCE)?;
+        let mut extension_reader = DerReader::new(extension);
+        if format_oid(extension_reader.read_expected(DER_TAG_OID)?)? != OID_SUBJECT_ALT_NAME {
+            continue;
+        }
+        let mut field = extension_reader.read()?;
+        if field.0 == DER_TAG_BOOLEAN {
+            field = extension_reader.read()?;
+        }
+        if field.0 != DER_TAG_OCTET_STRING {
+            return None;
+        }
+        let names = DerReader::new(field.1).read_expected(DER_TAG_SEQUENCE)?;
+        let mut names_reader = DerReader::new(names);
+        let mut ret = Vec::new();
+        while let Some((tag, value)) = names_reader.read() {
+            let text = String::from_utf8(value.to_vec()).ok();
+            match tag {
+                DER_TAG_RFC822_NAME => ret.extend(text.map(SubjectAltName::Email)),
+                DER_TAG_DNS_NAME => ret.extend(text.map(SubjectAltName::Dns)),
+                DER_TAG_URI => ret.extend(text.map(SubjectAltName::Uri)),
+                DER_TAG_IP_ADDRESS => ret.extend(parse_ip_address(value).map(SubjectAltName::Ip)),
+                _ => {}
+            }
+        }
+        return Some(ret);
+    }
+    Some(Vec::new())
+}
+
+fn parse_ip_address(value: &[u8]) -> Option<IpAddr> {
+    match value.len() {
+        4 => Some(IpAddr::V4(Ipv4Addr::new(
+            value[0], value[1], value[2], value[3],
+        ))),
+        16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(value);
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        }
+        _ => None,
+    }
+}
+
+/// Normalizes a DN to compare. Spaces around the separators are ignored
+/// and the comparison is case-insensitive.
+fn normalize_dn(dn: &str) -> String {
+    let mut rdns = Vec::new();
+    let mut rdn = String::new();
+    let mut escaped = false;
+    for c in dn.chars() {
+        if c == ',' && !escaped {
+            rdns.push(rdn.clone());
+            rdn.clear();
+            continue;
+        }
+        escaped = c == '\\' && !escaped;
+        rdn.push(c);
+    }
+    rdns.push(rdn);
+    rdns.iter()
+        .map(|rdn| match rdn.find('=') {
+            Some(i) => format!("{}={}", rdn[..i].trim(), rdn[i + 1..].trim()),
+            None => rdn.trim().to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+        .to_lowercase()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<String>>()
+        .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generated by `openssl req -x509 -newkey rsa:1024 -subj "/C=JP/O=Jongleur/CN=client"`
+    // with DNS, URI, email and IP entries of subjectAltName.
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIClzCCAgCgAwIBAgIULUs74k3uK0iAzAXFVLSgauNW08YwDQYJKoZIhvcNAQEL
+BQAwMTELMAkGA1UEBhMCSlAxETAPBgNVBAoMCEpvbmdsZXVyMQ8wDQYDVQQDDAZj
+bGllbnQwIBcNMjYxMDE3MDYxNzA5WhgPMjEyNjA5MjMwNjE3MDlaMDExCzAJBgNV
+BAYTAkpQMREwDwYDVQQKDAhKb25nbGV1cjEPMA0GA1UEAwwGY2xpZW50MIGfMA0G
+CSqGSIb3DQEBAQUAA4GNADCBiQKBgQDsQfJvYmA/O36hJccRJ6fj+bnffdJwnajR
+wLAVtwPgG0N4g2+7OLiJK+R1wxATMyXVUHmyefHzrEJrdWT8PXCEseSAshvjebxk
+A/9VFRo54gZV7wp4au4kP6sLKEWf5zniezNx60uzrSYMnRyekpHRU5pV6EIEI5n7
+u1xEZ2RESQIDAQABo4GpMIGmMB0GA1UdDgQWBBTUN6jRU3eWNqeW3wy9Fd7r5L3u
+WjAfBgNVHSMEGDAWgBTUN6jRU3eWNqeW3wy9Fd7r5L3uWjAPBgNVHRMBAf8EBTAD
+AQH/MFMGA1UdEQRMMEqCEmNsaWVudC5leGFtcGxlLmNvbYYaaHR0cHM6Ly9jbGll
+bnQuZXhhbXBsZS5jb22BEmNsaWVudEBleGFtcGxlLmNvbYcEfwAAATANBgkqhkiG
+9w0BAQsFAAOBgQCULuIxJ+b1A16Hgmfrqd5Kj4VG02X7EejqgJrd2SzTyevb74wW
+QrAefjNM0LlG+ZaP06h6FsCUhzOQcLzCRULtW3JjyGPpM06h5r7kPMYX1A4prYdw
+nXwNE9Zc/HjfJ2g7QdeztHKk6fzcP1bmpGUXTkoqHYhVq7tHJ9gBLphkyg==
+-----END CERTIFICATE-----";
+
+    #[test]
+    fn should_read_client_certificate() {
+        let cert = ClientCertificate::from_pem(CERTIFICATE).unwrap();
+
+        assert_eq!(
+            cert.thumbprint(),
+            "UvzyDoaCw-TTPjLsV0_MxFu_NPUxP6HNwEwwx7UPV_M"
+        );
+        assert_eq!(cert.subject_dn().unwrap(), "CN=client,O=Jongleur,C=JP");
+        assert_eq!(
+            cert.subject_alt_names(),
+            vec![
+                SubjectAltName::Dns("client.example.com".to_string()),
+                SubjectAltName::Uri("https://client.example.com".to_string()),
+                SubjectAltName::Email("client@example.com".to_string()),
+                SubjectAltName::Ip("127.0.0.1".parse().unwrap()),
+            ]
+        );
+        assert_eq!(cert.public_key().unwrap().e, "AQAB");
+
+        // Forwarded in a single line
+        let single_line = CERTIFICATE.replace("\n", " ");
+        assert_eq!(
+            ClientCertificate::from_pem(&single_line).unwrap().der,
+            cert.der
+        );
+        assert!(ClientCertificate::from_pem("-----BEGIN CERTIFICATE-----").is_err());
+    }
+
+    #[test]
+    fn should_authenticate_by_subject() {
+        let cert = ClientCertificate {
+            verified: true,
+            ..ClientCertificate::from_pem(CERTIFICATE).unwrap()
+        };
+        let metadata = |dn: Option<&str>, dns: Option<&str>| ClientMetadata {
+            tls_client_auth_subject_dn: dn.map(|v| v.to_string()),
+            tls_client_auth_san_dns: dns.map(|v| v.to_string()),
+            ..ClientMetadata::default()
+        };
+
+        assert!(cert.is_issued_to(&metadata(Some("cn=client, o=Jongleur, c=JP"), None)));
+        assert!(!cert.is_issued_to(&metadata(Some("CN=other,O=Jongleur,C=JP"), None)));
+        assert!(cert.is_issued_to(&metadata(None, Some("CLIENT.example.com"))));
+        assert!(!cert.is_issued_to(&metadata(None, Some("other.example.com"))));
+        assert!(!cert.is_issued_to(&metadata(None, None)));
+
+        // The chain is not verified by the proxy.
+        let unverified = ClientCertificate::from_pem(CERTIFICATE).unwrap();
+        assert!(!unverified.is_issued_to(&metadata(Some("CN=client,O=Jongleur,C=JP"), None)));
+    }
+}
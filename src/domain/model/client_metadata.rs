@@ -0,0 +1,373 @@
+use serde_json::{self, Map, Value};
+use std::net::IpAddr;
+use url::Url;
+
+use domain::error::domain as ed;
+use domain::model::{ClientType, GrantType, JwkSet, ResponseType};
+
+pub const TOKEN_ENDPOINT_AUTH_METHOD_BASIC: &str = "client_secret_basic";
+pub const TOKEN_ENDPOINT_AUTH_METHOD_POST: &str = "client_secret_post";
+pub const TOKEN_ENDPOINT_AUTH_METHOD_SECRET_JWT: &str = "client_secret_jwt";
+pub const TOKEN_ENDPOINT_AUTH_METHOD_PRIVATE_KEY_JWT: &str = "private_key_jwt";
+pub const TOKEN_ENDPOINT_AUTH_METHOD_TLS_CLIENT_AUTH: &str = "tls_client_auth";
+pub const TOKEN_ENDPOINT_AUTH_METHOD_SELF_SIGNED_TLS_CLIENT_AUTH: &str =
+    "self_signed_tls_client_auth";
+pub const TOKEN_ENDPOINT_AUTH_METHOD_NONE: &str = "none";
+
+/// The metadata which only admins can change.
+const ADMIN_ONLY_METADATA: [&str; 8] = [
+    "trusted_issuers",
+    "token_exchange_subject_clients",
+    "token_exchange_resources",
+    "tls_client_auth_subject_dn",
+    "tls_client_auth_san_dns",
+    "tls_client_auth_san_uri",
+    "tls_client_auth_san_ip",
+    "tls_client_auth_san_email",
+];
+
+/// `TrustedIssuer` is the type represents an issuer of JWT assertions
+/// which the client presents as authorization grants (RFC 7523).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrustedIssuer {
+    pub issuer: String,
+    /// The keys to verify assertions signed by the issuer.
+    pub jwks: JwkSet,
+    /// The end users identified by the subjects of the issuer.
+    /// Assertions about the other subjects are rejected.
+    #[serde(default)]
+    pub subjects: Vec<TrustedSubject>,
+}
+
+/// `TrustedSubject` maps a subject of the trusted issuer to an end user of this server.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TrustedSubject {
+    pub sub: String,
+    pub end_user_id: String,
+}
+
+/// `ClientMetadata` is the type represents the client metadata
+/// of OpenID Connect Dynamic Client Registration and RFC 7591.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct ClientMetadata {
+    pub client_name: Option<String>,
+    pub grant_types: Vec<String>,
+    pub response_types: Vec<String>,
+    pub token_endpoint_auth_method: Option<String>,
+    pub logo_uri: Option<String>,
+    pub client_uri: Option<String>,
+    pub policy_uri: Option<String>,
+    pub tos_uri: Option<String>,
+    pub contacts: Vec<String>,
+    pub post_logout_redirect_uris: Vec<String>,
+    pub backchannel_logout_uri: Option<String>,
+    pub backchannel_logout_session_required: bool,
+    pub frontchannel_logout_uri: Option<String>,
+    pub frontchannel_logout_session_required: bool,
+    /// The keys to verify request objects signed by the client.
+    pub jwks: Option<JwkSet>,
+    pub request_uris: Vec<String>,
+    /// Authorization requests of the client MUST be pushed in advance.
+    pub require_pushed_authorization_requests: bool,
+    pub trusted_issuers: Vec<TrustedIssuer>,
+    /// The clients whose tokens the client can exchange by Token Exchange (RFC 8693).
+    pub token_exchange_subject_clients: Vec<String>,
+    /// The resources which the client can request as the target of Token Exchange
+    /// besides the resource of the subject token.
+    pub token_exchange_resources: Vec<String>,
+    /// The expected subject of the certificate for `tls_client_auth` (RFC 8705).
+    /// Exactly one of the subject DN and the SAN entries is registered by admins,
+    /// since a client could otherwise claim the certificate of another party.
+    pub tls_client_auth_subject_dn: Option<String>,
+    pub tls_client_auth_san_dns: Option<String>,
+    pub tls_client_auth_san_uri: Option<String>,
+    pub tls_client_auth_san_ip: Option<String>,
+    pub tls_client_auth_san_email: Option<String>,
+    /// Access tokens of the client are bound to its certificate.
+    pub tls_client_certificate_bound_access_tokens: bool,
+}
+
+impl ClientMetadata {
+    /// Validates the metadata with redirect_uris and fills default values.
+    pub fn validate(self, redirect_uris: &Vec<String>) -> Result<Self, ed::Error> {
+        let grant_types = if self.grant_types.is_empty() {
+            vec!["authorization_code".to_string()]
+        } else {
+            self.grant_types.clone()
+        };
+        for grant_type in grant_types.iter() {
+            let supported = grant_type == "implicit" || match GrantType::new(grant_type) {
+                GrantType::Undefined(_) => false,
+                _ => true,
+            };
+            if !supported {
+                return Err(ed::ErrorKind::InvalidClientMetadata(format!(
+                    "Unsupported grant_type: {}",
+                    grant_type
+                )).into());
+            }
+        }
+
+        let response_types = if self.response_types.is_empty() {
+            vec!["code".to_string()]
+        } else {
+            self.response_types.clone()
+        };
+        for response_type in response_types.iter() {
+            ResponseType::from_str(response_type).map_err(|_| {
+                ed::ErrorKind::InvalidClientMetadata(format!(
+                    "Unsupported response_type: {}",
+                    response_type
+                ))
+            })?;
+        }
+
+        let token_endpoint_auth_method = self.token_endpoint_auth_method
+            .clone()
+            .unwrap_or_else(|| TOKEN_ENDPOINT_AUTH_METHOD_BASIC.to_string());
+        let supported = [
+            TOKEN_ENDPOINT_AUTH_METHOD_BASIC,
+            TOKEN_ENDPOINT_AUTH_METHOD_POST,
+            TOKEN_ENDPOINT_AUTH_METHOD_SECRET_JWT,
+            TOKEN_ENDPOINT_AUTH_METHOD_PRIVATE_KEY_JWT,
+            TOKEN_ENDPOINT_AUTH_METHOD_TLS_CLIENT_AUTH,
+            TOKEN_ENDPOINT_AUTH_METHOD_SELF_SIGNED_TLS_CLIENT_AUTH,
+            TOKEN_ENDPOINT_AUTH_METHOD_NONE,
+        ];
+        if !supported.contains(&token_endpoint_auth_method.as_str()) {
+            return Err(ed::ErrorKind::InvalidClientMetadata(format!(
+                "Unsupported token_endpoint_auth_method: {}",
+                token_endpoint_auth_method
+            )).into());
+        }
+        // private_key_jwt and self_signed_tls_client_auth are verified with the registered keys.
+        let uses_jwks = token_endpoint_auth_method == TOKEN_ENDPOINT_AUTH_METHOD_PRIVATE_KEY_JWT
+            || token_endpoint_auth_method == TOKEN_ENDPOINT_AUTH_METHOD_SELF_SIGNED_TLS_CLIENT_AUTH;
+        if uses_jwks && self.jwks.as_ref().map_or(true, |jwks| jwks.keys.is_empty()) {
+            return Err(ed::ErrorKind::InvalidClientMetadata(format!(
+                "{} requires jwks.",
+                token_endpoint_auth_method
+            )).into());
+        }
+        // tls_client_auth identifies the certificate by exactly one of them.
+        if token_endpoint_auth_method == TOKEN_ENDPOINT_AUTH_METHOD_TLS_CLIENT_AUTH {
+            let subjects = [
+                &self.tls_client_auth_subject_dn,
+                &self.tls_client_auth_san_dns,
+                &self.tls_client_auth_san_uri,
+                &self.tls_client_auth_san_ip,
+                &self.tls_client_auth_san_email,
+            ];
+            if subjects.iter().filter(|s| s.is_some()).count() != 1 {
+                return Err(ed::ErrorKind::InvalidClientMetadata(
+                    "tls_client_auth requires exactly one of the subject DN and SAN entries."
+                        .to_string(),
+                ).into());
+            }
+        }
+        if let Some(ref ip) = self.tls_client_auth_san_ip {
+            if ip.parse::<IpAddr>().is_err() {
+                return Err(ed::ErrorKind::InvalidClientMetadata(format!(
+                    "Invalid tls_client_auth_san_ip: {}",
+                    ip
+                )).into());
+            }
+        }
+
+        // Redirection based flows require redirect_uris
+        let uses_redirect = grant_types
+            .iter()
+            .any(|g| g == "authorization_code" || g == "implicit");
+        if uses_redirect && redirect_uris.is_empty() {
+            return Err(
+                ed::ErrorKind::InvalidRedirectUri("redirect_uris is required.".to_string()).into(),
+            );
+        }
+        for uri in redirect_uris.iter() {
+            let valid = Url::parse(uri)
+                .map(|u| u.fragment().is_none())
+                .unwrap_or(false);
+            if !valid {
+                return Err(
+                    ed::ErrorKind::InvalidRedirectUri(format!("Invalid redirect_uri: {}", uri))
+                        .into(),
+                );
+            }
+        }
+
+        for uri in self.post_logout_redirect_uris.iter() {
+            if Url::parse(uri).is_err() {
+                return Err(ed::ErrorKind::InvalidClientMetadata(format!(
+                    "Invalid post_logout_redirect_uri: {}",
+                    uri
+                )).into());
+            }
+        }
+
+        // The logout uris MUST NOT include a fragment
+        let logout_uris = [
+            ("backchannel_logout_uri", &self.backchannel_logout_uri),
+            ("frontchannel_logout_uri", &self.frontchannel_logout_uri),
+        ];
+        for &(name, uri) in logout_uris.iter() {
+            if let Some(ref uri) = *uri {
+                let valid = Url::parse(uri)
+                    .map(|u| u.fragment().is_none())
+                    .unwrap_or(false);
+                if !valid {
+                    return Err(ed::ErrorKind::InvalidClientMetadata(format!(
+                        "Invalid {}: {}",
+                        name, uri
+                    )).into());
+                }
+            }
+        }
+
+        // This server fetches request objects only over https
+        for uri in self.request_uris.iter() {
+            if Url::parse(uri).map(|u| u.scheme() != "https").unwrap_or(true) {
+                return Err(ed::ErrorKind::InvalidClientMetadata(format!(
+                    "Invalid request_uri: {}",
+                    uri
+                )).into());
+            }
+        }
+
+        if let Some(ref jwks) = self.jwks {
+            for jwk in jwks.keys.iter() {
+                jwk.to_rsa_public_key_der()?;
+            }
+        }
+
+        for trusted in self.trusted_issuers.iter() {
+            if trusted.issuer.is_empty() || trusted.jwks.keys.is_empty() {
+                return Err(ed::ErrorKind::InvalidClientMetadata(
+                    "trusted_issuers requires issuer and jwks.".to_string(),
+                ).into());
+            }
+            for jwk in trusted.jwks.keys.iter() {
+                jwk.to_rsa_public_key_der()?;
+            }
+        }
+
+        let uris = [
+            &self.logo_uri,
+            &self.client_uri,
+            &self.policy_uri,
+            &self.tos_uri,
+        ];
+        for uri in uris.iter().filter_map(|u| Option::as_ref(*u)) {
+            if Url::parse(uri).is_err() {
+                return Err(
+                    ed::ErrorKind::InvalidClientMetadata(format!("Invalid URI: {}", uri)).into(),
+                );
+            }
+        }
+
+        Ok(ClientMetadata {
+            grant_types,
+            response_types,
+            token_endpoint_auth_method: Some(token_endpoint_auth_method),
+            ..self
+        })
+    }
+
+    /// Applies the fields sent by the client configuration request (RFC 7592) to this metadata.
+    /// The fields which are not sent are kept, and the admin-only fields can't be changed.
+    pub fn merge(&self, fields: &Map<String, Value>) -> Result<Self, ed::Error> {
+        let mut merged = match serde_json::to_value(self) {
+            Ok(Value::Object(m)) => m,
+            _ => {
+                return Err(ed::ErrorKind::ServerError(
+                    "Serializing the client metadata failed.".to_string(),
+                ).into())
+            }
+        };
+        for (name, value) in fields.iter() {
+            // The other fields such as client_id are not the metadata.
+            if !merged.contains_key(name) {
+                continue;
+            }
+            if ADMIN_ONLY_METADATA.contains(&name.as_str()) {
+                if merged.get(name) != Some(value) {
+                    return Err(ed::ErrorKind::InvalidClientMetadata(format!(
+                        "{} can't be changed by the client.",
+                        name
+                    )).into());
+                }
+                continue;
+            }
+            merged.insert(name.clone(), value.clone());
+        }
+        serde_json::from_value(Value::Object(merged)).map_err(|e| {
+            ed::ErrorKind::InvalidClientMetadata(format!("Invalid client metadata: {}", e)).into()
+        })
+    }
+
+    /// Returns the `ClientType` which corresponds to token_endpoint_auth_method.
+    pub fn client_type(&self) -> ClientType {
+        match self.token_endpoint_auth_method {
+            Some(ref m) if m == TOKEN_ENDPOINT_AUTH_METHOD_NONE => ClientType::Public,
+            _ => ClientType::Confidential,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    fn fields(json: &str) -> Map<String, Value> {
+        match serde_json::from_str(json).unwrap() {
+            Value::Object(m) => m,
+            _ => panic!("Not an object."),
+        }
+    }
+
+    #[test]
+    fn should_merge_client_metadata() {
+        let current = ClientMetadata {
+            client_name: Some("client".to_string()),
+            logo_uri: Some("https://client.example.com/logo.png".to_string()),
+            backchannel_logout_session_required: true,
+            trusted_issuers: vec![
+                TrustedIssuer {
+                    issuer: "https://issuer.example.com".to_string(),
+                    jwks: JwkSet { keys: vec![] },
+                    subjects: vec![],
+                },
+            ],
+            ..ClientMetadata::default()
+        };
+
+        let merged = current
+            .merge(&fields(r#"{"client_id": "id", "client_name": "new", "contacts": []}"#))
+            .unwrap();
+        assert_eq!(merged.client_name, Some("new".to_string()));
+        assert_eq!(merged.logo_uri, current.logo_uri);
+        assert!(merged.backchannel_logout_session_required);
+        assert_eq!(merged.trusted_issuers.len(), 1);
+
+        // Sent fields replace the current values even if they are empty.
+        let merged = current
+            .merge(&fields(r#"{"logo_uri": null, "backchannel_logout_session_required": false}"#))
+            .unwrap();
+        assert_eq!(merged.logo_uri, None);
+        assert!(!merged.backchannel_logout_session_required);
+
+        // Admin-only fields can be echoed back, but not changed.
+        let echoed = serde_json::to_value(&current.trusted_issuers).unwrap();
+        let mut echo = Map::new();
+        echo.insert("trusted_issuers".to_string(), echoed);
+        assert!(current.merge(&echo).is_ok());
+        assert!(current.merge(&fields(r#"{"trusted_issuers": []}"#)).is_err());
+        assert!(
+            current
+                .merge(&fields(r#"{"tls_client_auth_subject_dn": "CN=client"}"#))
+                .is_err()
+        );
+        assert!(current.merge(&fields(r#"{"contacts": "not a list"}"#)).is_err());
+    }
+}
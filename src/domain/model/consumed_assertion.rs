@@ -0,0 +1,30 @@
+use chrono::prelude::*;
+
+use util::generate_random_id;
+
+/// `ConsumedAssertion` is the type that records `jti` of a JWT assertion already used
+/// as an authorization grant or for client authentication,
+/// so that the same assertion cannot be replayed.
+/// It is kept until the assertion expires.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConsumedAssertion {
+    pub id: String,
+    pub issuer: String,
+    pub jti: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub is_deleted: bool,
+}
+
+impl ConsumedAssertion {
+    pub fn new(issuer: &String, jti: &String, exp: i64) -> Self {
+        ConsumedAssertion {
+            id: generate_random_id(32usize),
+            issuer: issuer.clone(),
+            jti: jti.clone(),
+            expires_at: DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(exp, 0), Utc),
+            created_at: Utc::now(),
+            is_deleted: false,
+        }
+    }
+}
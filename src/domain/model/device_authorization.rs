@@ -0,0 +1,165 @@
+use chrono::prelude::*;
+use time::Duration;
+
+use config::AppConfig;
+use domain::error::domain as ed;
+use util::{generate_random_id, generate_random_string};
+
+/// The characters of `user_code`.
+/// Vowels are excluded so that the code never spells a word, and the case is ignored.
+const USER_CODE_CHARSET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+const USER_CODE_LEN: usize = 8;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DeviceAuthorizationStatus {
+    #[serde(rename = "pending")]
+    Pending,
+    #[serde(rename = "approved")]
+    Approved,
+    #[serde(rename = "denied")]
+    Denied,
+    #[serde(rename = "issued")]
+    Issued,
+}
+
+/// `DeviceAuthorization` is the type that represents an authorization request of a device
+/// (RFC 8628). The end user approves it by entering `user_code` on another device,
+/// while the device polls the token endpoint with `device_code`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeviceAuthorization {
+    pub id: String,
+    pub device_code: String,
+    pub user_code: String,
+    pub client_id: String,
+    pub resource_id: String,
+    pub scope: Vec<String>,
+    /// The id token is issued only if `openid` scope was requested.
+    pub openid: bool,
+    pub status: DeviceAuthorizationStatus,
+    pub end_user_id: Option<String>,
+    /// The session ID of the end user who approved this request.
+    pub sid: Option<String>,
+    pub auth_time: Option<i64>,
+    pub acr: Option<String>,
+    pub amr: Option<Vec<String>>,
+    /// The minimum seconds which the device waits between polling requests.
+    pub interval: i64,
+    pub last_polled_at: Option<DateTime<Utc>>,
+    pub expires_in: i64,
+    pub created_at: DateTime<Utc>,
+    pub is_deleted: bool,
+}
+
+impl DeviceAuthorization {
+    pub fn new(client_id: &String, resource_id: &String, scope: &Vec<String>) -> Self {
+        DeviceAuthorization {
+            id: generate_random_id(32usize),
+            device_code: generate_random_id(64usize),
+            user_code: format_user_code(&generate_random_string(
+                USER_CODE_LEN,
+                USER_CODE_CHARSET,
+            )).unwrap(),
+            client_id: client_id.clone(),
+            resource_id: resource_id.clone(),
+            scope: scope.clone(),
+            openid: false,
+            status: DeviceAuthorizationStatus::Pending,
+            end_user_id: None,
+            sid: None,
+            auth_time: None,
+            acr: None,
+            amr: None,
+            interval: AppConfig::device_code_interval_sec(),
+            last_polled_at: None,
+            expires_in: AppConfig::device_code_max_age_sec(),
+            created_at: Utc::now(),
+            is_deleted: false,
+        }
+    }
+
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.created_at.clone() + Duration::seconds(self.expires_in)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !(self.expires_at().timestamp() < Utc::now().timestamp() || self.is_deleted)
+    }
+
+    /// Records the polling request of the device.
+    /// The interval is increased by 5 seconds if the device polls too quickly.
+    pub fn poll(&mut self, now: DateTime<Utc>) -> Result<(), ed::Error> {
+        let too_quick = self.last_polled_at
+            .as_ref()
+            .map_or(false, |t| now.timestamp() - t.timestamp() < self.interval);
+        self.last_polled_at = Some(now);
+        if too_quick {
+            self.interval += 5;
+            return Err(ed::ErrorKind::SlowDown(format!(
+                "Poll at intervals of {} seconds.",
+                self.interval
+            )).into());
+        }
+        Ok(())
+    }
+}
+
+/// Normalizes the `user_code` typed by the end user into the form of `XXXX-XXXX`.
+/// Returns None if it cannot be a `user_code`.
+pub fn format_user_code(user_code: &str) -> Option<String> {
+    let chars: Vec<char> = user_code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if chars.len() != USER_CODE_LEN {
+        return None;
+    }
+    let (first, second) = chars.split_at(USER_CODE_LEN / 2);
+    Some(format!(
+        "{}-{}",
+        first.iter().collect::<String>(),
+        second.iter().collect::<String>()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_format_user_code() {
+        assert_eq!(format_user_code("bcdf-ghjk"), Some("BCDF-GHJK".to_string()));
+        assert_eq!(format_user_code(" BCDF GHJK "), Some("BCDF-GHJK".to_string()));
+        assert_eq!(format_user_code("BCDFGHJ"), None);
+    }
+
+    #[test]
+    fn should_slow_down_quick_polling() {
+        let mut device = DeviceAuthorization {
+            id: "id".to_string(),
+            device_code: "device_code".to_string(),
+            user_code: "BCDF-GHJK".to_string(),
+            client_id: "client".to_string(),
+            resource_id: "resource".to_string(),
+            scope: Vec::new(),
+            openid: false,
+            status: DeviceAuthorizationStatus::Pending,
+            end_user_id: None,
+            sid: None,
+            auth_time: None,
+            acr: None,
+            amr: None,
+            interval: 5,
+            last_polled_at: None,
+            expires_in: 600,
+            created_at: Utc::now(),
+            is_deleted: false,
+        };
+        let now = Utc::now();
+        assert!(device.poll(now).is_ok());
+        let e = device.poll(now + Duration::seconds(2)).unwrap_err();
+        assert_eq!(e.description(), "slow_down");
+        assert_eq!(device.interval, 10);
+        assert!(device.poll(now + Duration::seconds(12)).is_ok());
+    }
+}
@@ -0,0 +1,250 @@
+use chrono::prelude::*;
+use jsonwebtoken::{decode, Algorithm, Validation};
+use serde_json::{self, Value};
+use url::Url;
+
+use domain::error::domain as ed;
+use domain::model::Jwk;
+use util::{decode_base64url, encode_base64url, hash_sha256};
+
+/// `DpopProof` is the type that represents claims of a DPoP proof JWT (RFC 9449).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DpopProof {
+    pub jti: String,
+    pub htm: String,
+    pub htu: String,
+    pub iat: i64,
+    /// The hash of the access token presented with the proof.
+    #[serde(default)]
+    pub ath: Option<String>,
+    /// The JWK thumbprint of the public key in the JOSE header.
+    #[serde(skip)]
+    pub jkt: String,
+}
+
+impl DpopProof {
+    /// Decodes and verifies a proof with the public key in its JOSE header.
+    pub fn decode(token: &str) -> Result<Self, ed::Error> {
+        let header: Value = token
+            .split('.')
+            .next()
+            .and_then(|h| decode_base64url(h))
+            .and_then(|h| serde_json::from_slice(&h).ok())
+            .ok_or_else(|| invalid("Invalid JWT header."))?;
+        if header["typ"].as_str() != Some("dpop+jwt") {
+            return Err(invalid("typ must be dpop+jwt."));
+        }
+        if header["alg"].as_str() != Some("RS256") {
+            return Err(invalid("Unsupported alg."));
+        }
+        // The header MUST NOT contain a private key.
+        if header["jwk"].get("d").is_some() {
+            return Err(invalid("jwk must be a public key."));
+        }
+        let jwk: Jwk = serde_json::from_value(header["jwk"].clone())
+            .map_err(|_| invalid("jwk is required."))?;
+        if jwk.kty != "RSA" {
+            return Err(invalid("Unsupported kty."));
+        }
+
+        // iat is checked in `validate` in both directions.
+        let validation = Validation {
+            algorithms: Some(vec![Algorithm::RS256]),
+            validate_exp: false,
+            validate_iat: false,
+            validate_nbf: false,
+            ..Validation::default()
+        };
+        let key = jwk.to_rsa_public_key_der()
+            .map_err(|_| invalid("Invalid jwk."))?;
+        decode::<DpopProof>(token, &key, &validation)
+            .map(|data| DpopProof {
+                jkt: jwk.thumbprint(),
+                ..data.claims
+            })
+            .map_err(|e| invalid(&format!("Verification failed: {}", e)))
+    }
+
+    /// Validates that the proof is created for the request and the access token.
+    /// iat is accepted within `max_age_sec` in both directions.
+    pub fn validate(
+        &self,
+        htm: &str,
+        htu: &str,
+        access_token: Option<&String>,
+        max_age_sec: i64,
+    ) -> Result<(), ed::Error> {
+        if self.htm != htm {
+            return Err(invalid(&format!("htm does not match: {}", self.htm)));
+        }
+        if !is_same_uri(&self.htu, htu) {
+            return Err(invalid(&format!("htu does not match: {}", self.htu)));
+        }
+        if (Utc::now().timestamp() - self.iat).abs() > max_age_sec {
+            return Err(invalid("iat is out of the acceptable range."));
+        }
+        if let Some(token) = access_token {
+            if self.ath.as_ref() != Some(&access_token_hash(token)) {
+                return Err(invalid("ath does not match the access token."));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns `ath` of the access token, the base64url encoded SHA-256 hash.
+pub fn access_token_hash(access_token: &str) -> String {
+    encode_base64url(&hash_sha256(access_token.as_bytes()))
+}
+
+// Private functions
+fn invalid(desc: &str) -> ed::Error {
+    ed::ErrorKind::InvalidDpopProof(desc.to_string()).into()
+}
+
+/// Compares URIs without the query and the fragment.
+fn is_same_uri(htu: &str, uri: &str) -> bool {
+    let strip = |s: &str| {
+        Url::parse(s).ok().map(|mut u| {
+            u.set_query(None);
+            u.set_fragment(None);
+            u
+        })
+    };
+    match (strip(htu), strip(uri)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::sign;
+
+    use domain::model::{test_jwk, OTHER_PRIVATE_KEY, TEST_PRIVATE_KEY};
+
+    const HTU: &str = "http://127.0.0.1:8000/oidc/tokens";
+
+    #[derive(Serialize)]
+    struct TestHeader {
+        typ: &'static str,
+        alg: &'static str,
+        jwk: Value,
+    }
+
+    fn header() -> TestHeader {
+        TestHeader {
+            typ: "dpop+jwt",
+            alg: "RS256",
+            jwk: serde_json::to_value(test_jwk()).unwrap(),
+        }
+    }
+
+    fn claims(iat: i64, ath: Option<String>) -> DpopProof {
+        DpopProof {
+            jti: "jti".to_string(),
+            htm: "POST".to_string(),
+            htu: HTU.to_string(),
+            iat,
+            ath,
+            jkt: "".to_string(),
+        }
+    }
+
+    fn proof(header: &TestHeader, claims: &DpopProof, key: &[u8]) -> String {
+        let input = format!(
+            "{}.{}",
+            encode_base64url(serde_json::to_string(header).unwrap().as_bytes()),
+            encode_base64url(serde_json::to_string(claims).unwrap().as_bytes())
+        );
+        let signature = sign(&input, key, Algorithm::RS256).unwrap();
+        format!("{}.{}", input, signature)
+    }
+
+    fn now() -> i64 {
+        Utc::now().timestamp()
+    }
+
+    #[test]
+    fn should_decode_proof_with_jwk_thumbprint() {
+        let token = proof(&header(), &claims(now(), None), TEST_PRIVATE_KEY);
+
+        let decoded = DpopProof::decode(&token).unwrap();
+        assert_eq!(decoded.jkt, test_jwk().thumbprint());
+        assert!(decoded.validate("POST", HTU, None, 60).is_ok());
+    }
+
+    #[test]
+    fn should_reject_proof_with_invalid_header() {
+        let claims = claims(now(), None);
+
+        let typ = TestHeader {
+            typ: "JWT",
+            ..header()
+        };
+        assert!(DpopProof::decode(&proof(&typ, &claims, TEST_PRIVATE_KEY)).is_err());
+
+        let alg = TestHeader {
+            alg: "none",
+            ..header()
+        };
+        assert!(DpopProof::decode(&proof(&alg, &claims, TEST_PRIVATE_KEY)).is_err());
+
+        let mut private_jwk = header();
+        private_jwk.jwk["d"] = Value::String("private".to_string());
+        assert!(DpopProof::decode(&proof(&private_jwk, &claims, TEST_PRIVATE_KEY)).is_err());
+
+        // Signed with a key other than the jwk
+        assert!(DpopProof::decode(&proof(&header(), &claims, OTHER_PRIVATE_KEY)).is_err());
+    }
+
+    #[test]
+    fn should_validate_ath() {
+        let access_token = "access_token".to_string();
+        let ath = Some(access_token_hash(&access_token));
+
+        let decoded = DpopProof::decode(&proof(&header(), &claims(now(), ath), TEST_PRIVATE_KEY))
+            .unwrap();
+        assert!(decoded.validate("POST", HTU, Some(&access_token), 60).is_ok());
+        assert!(decoded.validate("POST", HTU, Some(&"other".to_string()), 60).is_err());
+
+        let decoded = DpopProof::decode(&proof(&header(), &claims(now(), None), TEST_PRIVATE_KEY))
+            .unwrap();
+        assert!(decoded.validate("POST", HTU, Some(&access_token), 60).is_err());
+    }
+
+    #[test]
+    fn should_validate_iat_in_both_directions() {
+        let validate = |iat: i64| {
+            DpopProof::decode(&proof(&header(), &claims(iat, None), TEST_PRIVATE_KEY))
+                .unwrap()
+                .validate("POST", HTU, None, 60)
+        };
+
+        assert!(validate(now() - 30).is_ok());
+        assert!(validate(now() + 30).is_ok());
+        assert!(validate(now() - 120).is_err());
+        assert!(validate(now() + 120).is_err());
+    }
+
+    #[test]
+    fn should_validate_htm_and_htu() {
+        let decoded = DpopProof::decode(&proof(&header(), &claims(now(), None), TEST_PRIVATE_KEY))
+            .unwrap();
+
+        assert!(decoded.validate("GET", HTU, None, 60).is_err());
+        assert!(decoded.validate("POST", "http://127.0.0.1:8000/oidc/userinfo", None, 60).is_err());
+    }
+
+    #[test]
+    fn should_compare_uri_without_query() {
+        let uri = "http://127.0.0.1:8000/oidc/tokens";
+
+        assert!(is_same_uri("http://127.0.0.1:8000/oidc/tokens", uri));
+        assert!(is_same_uri("http://127.0.0.1:8000/oidc/tokens?a=b#c", uri));
+        assert!(is_same_uri("HTTP://127.0.0.1:8000/oidc/tokens", uri));
+        assert!(!is_same_uri("http://127.0.0.1:8000/oidc/userinfo", uri));
+        assert!(!is_same_uri("/oidc/tokens", uri));
+    }
+}
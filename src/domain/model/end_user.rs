@@ -2,6 +2,7 @@ use chrono::prelude::*;
 use std::ops::IndexMut;
 
 use domain::error::domain as ed;
+use domain::model::Address;
 use util::{generate_random_id, hash_str};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -31,6 +32,8 @@ pub struct EndUser {
     pub locale: Option<String>,
     pub phone_number: Option<String>,
     pub phone_number_verified: bool,
+    #[serde(default)]
+    pub address: Option<Address>,
     pub accepted_clients: Vec<AcceptedClient>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -116,6 +119,7 @@ impl EndUser {
             zoneinfo: None,
             locale: None,
             phone_number: None,
+            address: None,
         }
     }
 }
@@ -136,6 +140,7 @@ pub struct EndUserBuilder {
     zoneinfo: Option<String>,
     locale: Option<String>,
     phone_number: Option<String>,
+    address: Option<Address>,
 }
 
 impl EndUserBuilder {
@@ -211,6 +216,12 @@ impl EndUserBuilder {
             ..self
         }
     }
+    pub fn address(self, address: &Option<Address>) -> Self {
+        EndUserBuilder {
+            address: address.clone(),
+            ..self
+        }
+    }
     pub fn build(self) -> EndUser {
         let created_at = Utc::now();
         EndUser {
@@ -232,6 +243,7 @@ impl EndUserBuilder {
             locale: self.locale,
             phone_number: self.phone_number,
             phone_number_verified: false,
+            address: self.address,
             accepted_clients: Vec::new(),
             created_at,
             updated_at: created_at,
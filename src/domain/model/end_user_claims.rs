@@ -1,6 +1,6 @@
-use chrono::prelude::*;
+use std::collections::HashMap;
 
-use domain::model::EndUser;
+use domain::model::{ClaimRequest, EndUser, StandardClaims};
 
 /// `EndUserClaims` is the type represents claims of an end user.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -10,60 +10,56 @@ pub struct EndUserClaims {
     pub aud: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub auth_time: Option<i64>,
-    pub name: String,
-    pub email: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub given_name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub family_name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub middle_name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub nickname: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub profile: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub picture: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub website: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub gender: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub birthdate: Option<NaiveDate>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub zoneinfo: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub locale: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub phone_number: Option<String>,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub standard_claims: StandardClaims,
 }
 
 impl EndUserClaims {
     /// Generates EndUserClaims from EndUser.
-    pub fn from_end_user(issuer: &str, end_user: &EndUser, client_id: &String) -> Self {
+    /// Only the claims which `scope` allows or `requested` by the `claims` parameter are included.
+    /// `auth_time` is the one of the session which authorized the token,
+    /// not the last login of the end user on any device.
+    pub fn from_end_user(
+        issuer: &str,
+        end_user: &EndUser,
+        client_id: &String,
+        auth_time: &Option<i64>,
+        scope: &Vec<String>,
+        requested: Option<&HashMap<String, Option<ClaimRequest>>>,
+    ) -> Self {
         EndUserClaims {
             iss: issuer.to_string(),
             sub: end_user.id.clone(),
             aud: client_id.clone(),
-            auth_time: end_user.authenticated_at.as_ref().map(|t| t.timestamp()),
-            name: end_user.name.clone(),
-            email: end_user.email.clone(),
-            given_name: end_user.given_name.clone(),
-            family_name: end_user.family_name.clone(),
-            middle_name: end_user.middle_name.clone(),
-            nickname: end_user.nickname.clone(),
-            profile: end_user.profile.clone(),
-            picture: end_user.picture.clone(),
-            website: end_user.website.clone(),
-            gender: end_user.gender.clone(),
-            birthdate: end_user.birthdate.clone(),
-            zoneinfo: end_user.zoneinfo.clone(),
-            locale: end_user.locale.clone(),
-            phone_number: end_user.phone_number.clone(),
-            created_at: end_user.created_at.clone(),
-            updated_at: end_user.updated_at.clone(),
+            auth_time: auth_time.clone(),
+            standard_claims: StandardClaims::from_end_user(end_user, scope)
+                .requested(end_user, requested),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_use_auth_time_of_session() {
+        let mut end_user = EndUser::builder(
+            &"name".to_string(),
+            &"password".to_string(),
+            &"name@example.com".to_string(),
+        ).build();
+        // Logged in on another device
+        end_user.update_authenticated_timestamp();
+
+        let claims = EndUserClaims::from_end_user(
+            "https://op.example.com",
+            &end_user,
+            &"client".to_string(),
+            &Some(1_000_000),
+            &vec!["openid".to_string()],
+            None,
+        );
+        assert_eq!(claims.auth_time, Some(1_000_000));
+    }
+}
@@ -1,10 +1,11 @@
 use chrono::prelude::*;
+use std::collections::HashMap;
 use time::Duration;
 
 use config::AppConfig;
 use domain::error::domain as ed;
-use domain::model::{EndUser, ResponseType};
-use util::generate_random_id;
+use domain::model::{ClaimRequest, ClaimsRequest, EndUser, ResponseMode, ResponseType};
+use util::{encode_base64url, generate_random_id, hash_sha256};
 
 /// `GrantType` is the type that represents `grant_type` in the context of
 /// OAuth2 and OpenID Connect.
@@ -14,6 +15,9 @@ pub enum GrantType {
     ClientCredentials,
     Password,
     AuthorizationCode,
+    DeviceCode,
+    TokenExchange,
+    JwtBearer,
 }
 
 impl GrantType {
@@ -23,9 +27,39 @@ impl GrantType {
             "client_credentials" => GrantType::ClientCredentials,
             "password" => GrantType::Password,
             "authorization_code" => GrantType::AuthorizationCode,
+            "urn:ietf:params:oauth:grant-type:device_code" => GrantType::DeviceCode,
+            "urn:ietf:params:oauth:grant-type:token-exchange" => GrantType::TokenExchange,
+            "urn:ietf:params:oauth:grant-type:jwt-bearer" => GrantType::JwtBearer,
             _ => GrantType::Undefined(grant_type.to_string()),
         }
     }
+
+    /// Returns the value of `grant_type` that represents this `GrantType`.
+    pub fn to_str(&self) -> &str {
+        match self {
+            &GrantType::Undefined(ref raw) => raw,
+            &GrantType::RefreshToken => "refresh_token",
+            &GrantType::ClientCredentials => "client_credentials",
+            &GrantType::Password => "password",
+            &GrantType::AuthorizationCode => "authorization_code",
+            &GrantType::DeviceCode => "urn:ietf:params:oauth:grant-type:device_code",
+            &GrantType::TokenExchange => "urn:ietf:params:oauth:grant-type:token-exchange",
+            &GrantType::JwtBearer => "urn:ietf:params:oauth:grant-type:jwt-bearer",
+        }
+    }
+
+    /// Returns all `GrantType`s supported by this server.
+    pub fn supported() -> Vec<GrantType> {
+        vec![
+            GrantType::AuthorizationCode,
+            GrantType::RefreshToken,
+            GrantType::ClientCredentials,
+            GrantType::Password,
+            GrantType::DeviceCode,
+            GrantType::TokenExchange,
+            GrantType::JwtBearer,
+        ]
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -38,6 +72,47 @@ pub enum GrantStatus {
     Expired,
 }
 
+/// `CodeChallengeMethod` is the type that represents `code_challenge_method` of PKCE (RFC 7636).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum CodeChallengeMethod {
+    #[serde(rename = "plain")]
+    Plain,
+    #[serde(rename = "S256")]
+    S256,
+}
+
+impl CodeChallengeMethod {
+    pub fn new(method: &str) -> Result<Self, ed::Error> {
+        match method {
+            "plain" => Ok(CodeChallengeMethod::Plain),
+            "S256" => Ok(CodeChallengeMethod::S256),
+            _ => Err(ed::ErrorKind::InvalidRequest(format!(
+                "Unsupported code_challenge_method: {}",
+                method
+            )).into()),
+        }
+    }
+
+    /// Returns true if the code_verifier matches the code_challenge.
+    pub fn verify(&self, code_challenge: &str, code_verifier: &str) -> bool {
+        match *self {
+            CodeChallengeMethod::Plain => code_challenge == code_verifier,
+            CodeChallengeMethod::S256 => {
+                code_challenge == encode_base64url(&hash_sha256(code_verifier.as_bytes()))
+            }
+        }
+    }
+}
+
+/// Returns true if the value is valid as code_verifier or code_challenge.
+/// It MUST be 43 to 128 characters of the unreserved characters.
+pub fn is_valid_pkce_value(value: &str) -> bool {
+    value.len() >= 43 && value.len() <= 128
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_' || c == '~')
+}
+
 /// `Grant` is the type that has a grant code in the context of
 /// OAuth2 and OpenID Connect.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -53,6 +128,24 @@ pub struct Grant {
     pub state: Option<String>,
     pub nonce: Option<String>,
     pub response_type: ResponseType,
+    #[serde(default)]
+    pub response_mode: Option<ResponseMode>,
+    #[serde(default)]
+    pub code_challenge: Option<String>,
+    #[serde(default)]
+    pub code_challenge_method: Option<CodeChallengeMethod>,
+    /// The session ID of the end user who authorized this grant.
+    #[serde(default)]
+    pub sid: Option<String>,
+    /// The time when the end user authenticated in the session.
+    #[serde(default)]
+    pub auth_time: Option<i64>,
+    #[serde(default)]
+    pub acr: Option<String>,
+    #[serde(default)]
+    pub amr: Option<Vec<String>>,
+    #[serde(default)]
+    pub claims: Option<ClaimsRequest>,
     pub created_at: DateTime<Utc>,
     pub status: GrantStatus,
     pub is_deleted: bool,
@@ -91,6 +184,35 @@ impl Grant {
         }
     }
 
+    /// Verifies the code_verifier if this grant has the code_challenge.
+    pub fn verify_code_verifier(&self, code_verifier: &Option<String>) -> Result<(), ed::Error> {
+        let code_challenge = match self.code_challenge {
+            Some(ref c) => c,
+            None => return Ok(()),
+        };
+        let code_verifier = match *code_verifier {
+            Some(ref v) if is_valid_pkce_value(v) => v,
+            Some(_) => {
+                return Err(
+                    ed::ErrorKind::InvalidGrant("Invalid code_verifier.".to_string()).into(),
+                )
+            }
+            None => {
+                return Err(
+                    ed::ErrorKind::InvalidGrant("code_verifier is required.".to_string()).into(),
+                )
+            }
+        };
+        let method = self.code_challenge_method
+            .clone()
+            .unwrap_or(CodeChallengeMethod::Plain);
+        if method.verify(code_challenge, code_verifier) {
+            Ok(())
+        } else {
+            Err(ed::ErrorKind::InvalidGrant("code_verifier does not match.".to_string()).into())
+        }
+    }
+
     pub fn expires_at(&self) -> DateTime<Utc> {
         self.created_at.clone() + Duration::seconds(self.expires_in)
     }
@@ -100,6 +222,11 @@ impl Grant {
             || self.status == GrantStatus::Expired)
     }
 
+    /// Returns the individual claims requested in the id token.
+    pub fn id_token_claims(&self) -> Option<&HashMap<String, Option<ClaimRequest>>> {
+        self.claims.as_ref().and_then(|c| c.id_token.as_ref())
+    }
+
     pub fn builder(
         end_user_id: &String,
         client_id: &String,
@@ -123,10 +250,18 @@ pub struct GrantBuilder {
     resource_id: String,
     redirect_uri: String,
     response_type: ResponseType,
+    response_mode: Option<ResponseMode>,
     expires_in: i64,
     scope: Vec<String>,
     state: Option<String>,
     nonce: Option<String>,
+    code_challenge: Option<String>,
+    code_challenge_method: Option<CodeChallengeMethod>,
+    sid: Option<String>,
+    auth_time: Option<i64>,
+    acr: Option<String>,
+    amr: Option<Vec<String>>,
+    claims: Option<ClaimsRequest>,
 }
 
 impl GrantBuilder {
@@ -143,10 +278,18 @@ impl GrantBuilder {
             resource_id: resource_id.clone(),
             redirect_uri: redirect_uri.clone(),
             response_type: response_type.clone(),
+            response_mode: None,
             expires_in: AppConfig::default_grant_max_age_sec(),
             scope: Vec::new(),
             state: None,
             nonce: None,
+            code_challenge: None,
+            code_challenge_method: None,
+            sid: None,
+            auth_time: None,
+            acr: None,
+            amr: None,
+            claims: None,
         }
     }
 
@@ -175,6 +318,60 @@ impl GrantBuilder {
         }
     }
 
+    pub fn response_mode(self, response_mode: &ResponseMode) -> Self {
+        GrantBuilder {
+            response_mode: Some(response_mode.clone()),
+            ..self
+        }
+    }
+
+    pub fn code_challenge(
+        self,
+        code_challenge: &Option<String>,
+        code_challenge_method: &Option<CodeChallengeMethod>,
+    ) -> Self {
+        GrantBuilder {
+            code_challenge: code_challenge.clone(),
+            code_challenge_method: code_challenge_method.clone(),
+            ..self
+        }
+    }
+
+    pub fn sid(self, sid: &Option<String>) -> Self {
+        GrantBuilder {
+            sid: sid.clone(),
+            ..self
+        }
+    }
+
+    pub fn auth_time(self, auth_time: &Option<i64>) -> Self {
+        GrantBuilder {
+            auth_time: auth_time.clone(),
+            ..self
+        }
+    }
+
+    pub fn acr(self, acr: &Option<String>) -> Self {
+        GrantBuilder {
+            acr: acr.clone(),
+            ..self
+        }
+    }
+
+    pub fn amr(self, amr: &Option<Vec<String>>) -> Self {
+        GrantBuilder {
+            amr: amr.clone(),
+            ..self
+        }
+    }
+
+    pub fn claims(self, claims: &Option<ClaimsRequest>) -> Self {
+        GrantBuilder {
+            claims: claims.clone(),
+            ..self
+        }
+    }
+
     pub fn build(self) -> Grant {
         Grant {
             id: generate_random_id(32usize),
@@ -188,9 +385,35 @@ impl GrantBuilder {
             state: self.state,
             nonce: self.nonce,
             response_type: self.response_type,
+            response_mode: self.response_mode,
+            code_challenge: self.code_challenge,
+            code_challenge_method: self.code_challenge_method,
+            sid: self.sid,
+            auth_time: self.auth_time,
+            acr: self.acr,
+            amr: self.amr,
+            claims: self.claims,
             created_at: Utc::now(),
             status: GrantStatus::Created,
             is_deleted: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_verify_code_verifier() {
+        // RFC 7636 Appendix B
+        let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+        let challenge = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+        assert!(CodeChallengeMethod::S256.verify(challenge, verifier));
+        assert!(!CodeChallengeMethod::S256.verify(verifier, verifier));
+        assert!(CodeChallengeMethod::Plain.verify(verifier, verifier));
+        assert!(is_valid_pkce_value(verifier));
+        assert!(!is_valid_pkce_value("too_short"));
+    }
+}
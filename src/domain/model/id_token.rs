@@ -1,10 +1,11 @@
 use chrono::prelude::*;
 use jsonwebtoken::{decode, encode, Algorithm, Header, Validation};
+use std::collections::HashMap;
 
 use config::AppConfig;
-use domain::model::{Client, EndUser};
+use domain::model::{ClaimRequest, Client, EndUser, SigningKey, StandardClaims};
 use domain::error::domain as ed;
-use util::generate_random_id;
+use util::{decode_jwt_header, generate_random_id};
 use self::ed::ResultExt;
 
 /// `IdToken` is the type contains `id_token` in the context of
@@ -21,47 +22,60 @@ pub struct IdToken {
 
 impl IdToken {
     /// Returns `true` if the IdToken is valid.
-    pub fn is_valid(&self, key: &Vec<u8>) -> bool {
-        if let Ok(decoded) =
-            decode::<IdTokenClaims>(&self.token, key.as_ref(), &Validation::default())
-        {
-            !(decoded.claims.exp < Utc::now().timestamp() || self.is_deleted)
+    /// `keys` are the keys of an authorization server which can be used for verification.
+    pub fn is_valid(&self, keys: &Vec<SigningKey>) -> bool {
+        if let Ok(claims) = self.decode_claims(keys) {
+            !(claims.exp < Utc::now().timestamp() || self.is_deleted)
         } else {
             false
         }
     }
 
-    /// Extracts IdTokenClaims from IdToken using a public key of an authorization server.
-    pub fn extract_claims(&self, key: &Vec<u8>) -> Result<IdTokenClaims, ed::Error> {
-        decode::<IdTokenClaims>(&self.token, key.as_ref(), &Validation::default())
-            .map(|data| data.claims)
-            .chain_err(|| {
-                ed::ErrorKind::ServerError(
-                    "Unexpected error occurred when decoding the id token.".to_string(),
-                )
-            })
+    /// Extracts IdTokenClaims from IdToken using keys of an authorization server.
+    /// The key is selected by `kid` of the JWT header.
+    pub fn extract_claims(&self, keys: &Vec<SigningKey>) -> Result<IdTokenClaims, ed::Error> {
+        self.decode_claims(keys).chain_err(|| {
+            ed::ErrorKind::ServerError(
+                "Unexpected error occurred when decoding the id token.".to_string(),
+            )
+        })
     }
 
     /// Refreshes a token of the IdToken.
+    /// The standard claims are released again from the latest end user
+    /// by `scope` and the `requested` individual claims.
     pub fn update(
         self,
         client: &Client,
         end_user: &EndUser,
-        public_key: &Vec<u8>,
-        private_key: &Vec<u8>,
+        scope: &Vec<String>,
+        requested: Option<&HashMap<String, Option<ClaimRequest>>>,
+        keys: &Vec<SigningKey>,
+        signing_key: &SigningKey,
     ) -> Result<Self, ed::Error> {
-        let claims = self.extract_claims(public_key.as_ref())?;
-        let mut new_id_token =
-            IdTokenClaims::from_end_user(&AppConfig::issuer(), &end_user, &client.id)
-                .nonce(&claims.nonce)
-                .acr(&claims.acr)
-                .amr(&claims.amr)
-                .azp(&claims.azp)
-                .publish(private_key.as_ref())?;
+        let claims = self.extract_claims(keys)?;
+        let mut new_id_token = IdTokenClaims::from_end_user(
+            &AppConfig::issuer(),
+            &end_user,
+            &client.id,
+            &claims.auth_time,
+        ).nonce(&claims.nonce)
+            .acr(&claims.acr)
+            .amr(&claims.amr)
+            .azp(&claims.azp)
+            .sid(&claims.sid)
+            .standard_claims(
+                StandardClaims::from_end_user(end_user, scope).requested(end_user, requested),
+            )
+            .publish(signing_key)?;
         new_id_token.id = self.id;
         new_id_token.is_deleted = self.is_deleted;
         Ok(new_id_token)
     }
+
+    fn decode_claims(&self, keys: &Vec<SigningKey>) -> Result<IdTokenClaims, ed::Error> {
+        IdTokenClaims::decode(&self.token, keys, true)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -73,9 +87,15 @@ pub struct IdTokenClaims {
     pub iat: i64,
     pub auth_time: Option<i64>,
     pub nonce: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub acr: Option<String>,
-    pub amr: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amr: Option<Vec<String>>,
     pub azp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    #[serde(flatten)]
+    pub standard_claims: StandardClaims,
 }
 
 impl IdTokenClaims {
@@ -92,10 +112,18 @@ impl IdTokenClaims {
             acr: None,
             amr: None,
             azp: None,
+            sid: None,
+            standard_claims: StandardClaims::default(),
         }
     }
 
-    pub fn from_end_user(issuer: &str, end_user: &EndUser, client_id: &String) -> Self {
+    /// `auth_time` is the one of the session or the grant, not the last login of the end user.
+    pub fn from_end_user(
+        issuer: &str,
+        end_user: &EndUser,
+        client_id: &String,
+        auth_time: &Option<i64>,
+    ) -> Self {
         let now = Utc::now();
         IdTokenClaims {
             iss: issuer.to_string(),
@@ -103,11 +131,13 @@ impl IdTokenClaims {
             aud: client_id.clone(),
             exp: now.timestamp() + AppConfig::default_id_token_max_age_sec(),
             iat: now.timestamp(),
-            auth_time: end_user.authenticated_at.as_ref().map(|t| t.timestamp()),
+            auth_time: auth_time.clone(),
             nonce: None,
             acr: None,
             amr: None,
             azp: None,
+            sid: None,
+            standard_claims: StandardClaims::default(),
         }
     }
 
@@ -140,7 +170,7 @@ impl IdTokenClaims {
         }
     }
 
-    pub fn amr(self, amr: &Option<String>) -> Self {
+    pub fn amr(self, amr: &Option<Vec<String>>) -> Self {
         IdTokenClaims {
             amr: amr.clone(),
             ..self
@@ -154,10 +184,57 @@ impl IdTokenClaims {
         }
     }
 
-    pub fn publish(self, key: &Vec<u8>) -> Result<IdToken, ed::Error> {
+    pub fn sid(self, sid: &Option<String>) -> Self {
+        IdTokenClaims {
+            sid: sid.clone(),
+            ..self
+        }
+    }
+
+    pub fn standard_claims(self, standard_claims: StandardClaims) -> Self {
+        IdTokenClaims {
+            standard_claims,
+            ..self
+        }
+    }
+
+    /// Decodes and verifies an id token issued by this server.
+    /// The key is selected by `kid` of the JWT header.
+    /// Tokens issued before the key ring have no `kid`. They are signed with the configured
+    /// key pair, which is registered as the first key of the ring.
+    /// `validate_exp` can be false to accept an expired token such as `id_token_hint`.
+    pub fn decode(
+        token: &str,
+        keys: &Vec<SigningKey>,
+        validate_exp: bool,
+    ) -> Result<IdTokenClaims, ed::Error> {
+        let header = decode_jwt_header(token).ok_or::<ed::Error>(
+            ed::ErrorKind::InvalidToken("Invalid id token.".to_string()).into(),
+        )?;
+        let key = match header.kid {
+            Some(ref kid) => keys.iter().find(|k| &k.id == kid).ok_or::<ed::Error>(
+                ed::ErrorKind::InvalidToken(format!("Unknown kid. kid => {}", kid)).into(),
+            )?,
+            None => keys.iter().min_by_key(|k| k.created_at).ok_or::<ed::Error>(
+                ed::ErrorKind::InvalidToken("No legacy key.".to_string()).into(),
+            )?,
+        };
+        let validation = Validation {
+            algorithms: Some(vec![Algorithm::RS256]),
+            validate_exp,
+            ..Validation::default()
+        };
+        decode::<IdTokenClaims>(token, key.public_key_der()?.as_ref(), &validation)
+            .map(|data| data.claims)
+            .chain_err(|| ed::ErrorKind::InvalidToken("Invalid id token.".to_string()))
+    }
+
+    pub fn publish(self, signing_key: &SigningKey) -> Result<IdToken, ed::Error> {
         let expires_at = DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp(self.exp, 0), Utc);
         let mut header = Header::default();
         header.alg = Algorithm::RS256;
+        header.kid = Some(signing_key.id.clone());
+        let key = signing_key.private_key_der()?;
         let id_token: String = encode(&header, &self, key.as_ref()).chain_err(|| {
             ed::ErrorKind::ServerError("Encoding claims to JWT failed.".to_string())
         })?;
@@ -0,0 +1,168 @@
+use serde_json;
+
+use domain::error::domain as ed;
+use util::{decode_base64url, encode_base64url, encode_der, encode_der_unsigned_integer,
+           hash_sha256, DerReader, DER_TAG_BIT_STRING, DER_TAG_INTEGER, DER_TAG_SEQUENCE};
+
+/// `Jwk` is the type represents a JSON Web Key of an RSA public key.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Jwk {
+    pub kty: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    #[serde(rename = "use", skip_serializing_if = "Option::is_none")]
+    pub key_use: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+    pub n: String,
+    pub e: String,
+}
+
+impl Jwk {
+    /// Generates a Jwk for signature from a DER encoded RSA public key.
+    /// Both of `SubjectPublicKeyInfo` and PKCS#1 `RSAPublicKey` are accepted.
+    /// `kid` is the JWK thumbprint of the key.
+    pub fn from_rsa_public_key_der(der: &[u8]) -> Result<Self, ed::Error> {
+        let (n, e) = parse_rsa_public_key_der(der).ok_or::<ed::Error>(
+            ed::ErrorKind::ServerError("Parsing the RSA public key failed.".to_string()).into(),
+        )?;
+        let mut jwk = Jwk {
+            kty: "RSA".to_string(),
+            kid: None,
+            key_use: Some("sig".to_string()),
+            alg: Some("RS256".to_string()),
+            n: encode_base64url(n),
+            e: encode_base64url(e),
+        };
+        jwk.kid = Some(jwk.thumbprint());
+        Ok(jwk)
+    }
+
+    /// Returns the PKCS#1 `RSAPublicKey` DER to verify signatures with this key.
+    pub fn to_rsa_public_key_der(&self) -> Result<Vec<u8>, ed::Error> {
+        let invalid = || -> ed::Error {
+            ed::ErrorKind::InvalidClientMetadata("Invalid RSA key in jwks.".to_string()).into()
+        };
+        if self.kty != "RSA" {
+            return Err(invalid());
+        }
+        let n = decode_base64url(&self.n).ok_or_else(&invalid)?;
+        let e = decode_base64url(&self.e).ok_or_else(&invalid)?;
+        let mut content = encode_der_unsigned_integer(&n);
+        content.extend(encode_der_unsigned_integer(&e));
+        Ok(encode_der(DER_TAG_SEQUENCE, &content))
+    }
+
+    /// Returns the JWK thumbprint (RFC 7638) of this key.
+    pub fn thumbprint(&self) -> String {
+        // Required members in lexicographic order without whitespace
+        let json = format!(
+            "{{\"e\":{},\"kty\":{},\"n\":{}}}",
+            serde_json::to_string(&self.e).unwrap(),
+            serde_json::to_string(&self.kty).unwrap(),
+            serde_json::to_string(&self.n).unwrap()
+        );
+        encode_base64url(&hash_sha256(json.as_bytes()))
+    }
+}
+
+/// `JwkSet` is the type represents a JWK Set.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+impl JwkSet {
+    /// Returns the key whose `kid` matches.
+    pub fn find_by_kid(&self, kid: &str) -> Option<&Jwk> {
+        self.keys
+            .iter()
+            .find(|k| k.kid.as_ref().map_or(false, |v| v == kid))
+    }
+}
+
+/// Returns the modulus and the exponent of a DER encoded RSA public key.
+fn parse_rsa_public_key_der(der: &[u8]) -> Option<(&[u8], &[u8])> {
+    let seq = DerReader::new(der).read_expected(DER_TAG_SEQUENCE)?;
+    let mut reader = DerReader::new(seq);
+    match reader.read()? {
+        // SubjectPublicKeyInfo: SEQUENCE { AlgorithmIdentifier, BIT STRING { RSAPublicKey } }
+        (DER_TAG_SEQUENCE, _) => {
+            let bits = reader.read_expected(DER_TAG_BIT_STRING)?;
+            if bits.is_empty() {
+                return None;
+            }
+            // Skip the number of unused bits
+            parse_rsa_public_key_der(&bits[1..])
+        }
+        // RSAPublicKey: SEQUENCE { INTEGER n, INTEGER e }
+        (DER_TAG_INTEGER, n) => {
+            let e = reader.read_expected(DER_TAG_INTEGER)?;
+            Some((trim_integer(n), trim_integer(e)))
+        }
+        _ => None,
+    }
+}
+
+/// Removes leading zeros of a DER INTEGER to get unsigned big-endian bytes.
+fn trim_integer(value: &[u8]) -> &[u8] {
+    if value.is_empty() {
+        return value;
+    }
+    let pos = value
+        .iter()
+        .position(|b| *b != 0)
+        .unwrap_or(value.len() - 1);
+    &value[pos..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_rsa_public_key_der() {
+        // RSAPublicKey { n: 0x00c501, e: 0x010001 }
+        let pkcs1 = vec![
+            0x30, 0x0a, 0x02, 0x03, 0x00, 0xc5, 0x01, 0x02, 0x03, 0x01, 0x00, 0x01
+        ];
+        // SubjectPublicKeyInfo wrapping the RSAPublicKey above
+        let mut spki = vec![
+            0x30, 0x1e, 0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01,
+            0x01, 0x05, 0x00, 0x03, 0x0d, 0x00,
+        ];
+        spki.extend(pkcs1.iter());
+
+        let jwk1 = Jwk::from_rsa_public_key_der(&pkcs1).unwrap();
+        let jwk2 = Jwk::from_rsa_public_key_der(&spki).unwrap();
+
+        assert_eq!(jwk1.n, "xQE");
+        assert_eq!(jwk1.e, "AQAB");
+        assert_eq!(jwk1.n, jwk2.n);
+        assert_eq!(jwk1.kid, jwk2.kid);
+        assert_eq!(jwk1.to_rsa_public_key_der().unwrap(), pkcs1);
+        assert!(Jwk::from_rsa_public_key_der(&pkcs1[..5]).is_err());
+    }
+
+    #[test]
+    fn should_get_thumbprint() {
+        // RFC 7638 section 3.1
+        let jwk = Jwk {
+            kty: "RSA".to_string(),
+            kid: None,
+            key_use: None,
+            alg: Some("RS256".to_string()),
+            n: "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_\
+                BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_\
+                FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-\
+                bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw"
+                .to_string(),
+            e: "AQAB".to_string(),
+        };
+
+        assert_eq!(
+            jwk.thumbprint(),
+            "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs".to_string()
+        );
+    }
+}
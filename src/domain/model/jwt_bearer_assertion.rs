@@ -0,0 +1,190 @@
+use jsonwebtoken::{decode, Algorithm, Validation};
+use serde_json::Value;
+
+use domain::error::domain as ed;
+use domain::model::TrustedIssuer;
+use util::decode_jwt_header;
+
+/// `JwtBearerAssertion` is the type that represents claims of a JWT
+/// presented as an authorization grant (RFC 7523).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JwtBearerAssertion {
+    pub iss: String,
+    pub sub: String,
+    pub aud: Value,
+    pub exp: i64,
+    pub jti: String,
+    #[serde(default)]
+    pub iat: Option<i64>,
+    #[serde(default)]
+    pub nbf: Option<i64>,
+}
+
+impl JwtBearerAssertion {
+    /// Decodes and verifies an assertion with the keys of the issuer trusted by the client.
+    /// The key is selected by `kid` of the JWT header, or the only key is used without `kid`.
+    pub fn decode(token: &str, trusted_issuers: &Vec<TrustedIssuer>) -> Result<Self, ed::Error> {
+        let header = decode_jwt_header(token).ok_or::<ed::Error>(
+            ed::ErrorKind::InvalidGrant("Invalid JWT header.".to_string()).into(),
+        )?;
+
+        // The issuer is read before the verification to select the keys.
+        let unverified = Validation {
+            validate_signature: false,
+            validate_exp: false,
+            validate_iat: false,
+            validate_nbf: false,
+            ..Validation::default()
+        };
+        let iss = decode::<JwtBearerAssertion>(token, &[], &unverified)
+            .map(|data| data.claims.iss)
+            .map_err(|e| ed::ErrorKind::InvalidGrant(format!("Invalid assertion: {}", e)))?;
+        let trusted = trusted_issuers
+            .iter()
+            .find(|t| t.issuer == iss)
+            .ok_or::<ed::Error>(
+                ed::ErrorKind::InvalidGrant(format!("Untrusted issuer: {}", iss)).into(),
+            )?;
+
+        let jwk = match header.kid {
+            Some(ref kid) => trusted.jwks.find_by_kid(kid),
+            None if trusted.jwks.keys.len() == 1 => trusted.jwks.keys.first(),
+            None => None,
+        }.ok_or::<ed::Error>(
+            ed::ErrorKind::InvalidGrant("No key to verify the signature.".to_string()).into(),
+        )?;
+        let validation = Validation {
+            algorithms: Some(vec![Algorithm::RS256]),
+            iss: Some(trusted.issuer.clone()),
+            ..Validation::default()
+        };
+        decode::<JwtBearerAssertion>(token, &jwk.to_rsa_public_key_der()?, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| ed::ErrorKind::InvalidGrant(format!("Verification failed: {}", e)).into())
+    }
+
+    /// Returns the end user which the trusted issuer identifies by `sub`.
+    pub fn end_user_id(&self, trusted_issuers: &Vec<TrustedIssuer>) -> Option<String> {
+        trusted_issuers
+            .iter()
+            .find(|t| t.issuer == self.iss)?
+            .subjects
+            .iter()
+            .find(|s| s.sub == self.sub)
+            .map(|s| s.end_user_id.clone())
+    }
+
+    /// Returns true if `aud` includes any of the audiences.
+    pub fn has_audience(&self, audiences: &Vec<String>) -> bool {
+        match self.aud {
+            Value::String(ref aud) => audiences.contains(aud),
+            Value::Array(ref auds) => auds.iter().any(|a| {
+                a.as_str()
+                    .map_or(false, |a| audiences.iter().any(|v| v == a))
+            }),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::prelude::*;
+    use jsonwebtoken::{encode, Header};
+    use domain::model::{test_jwks, TrustedSubject, OTHER_PRIVATE_KEY, TEST_PRIVATE_KEY};
+
+    fn assertion(aud: Value) -> JwtBearerAssertion {
+        JwtBearerAssertion {
+            iss: "partner".to_string(),
+            sub: "end_user".to_string(),
+            aud,
+            exp: 0,
+            jti: "jti".to_string(),
+            iat: None,
+            nbf: None,
+        }
+    }
+
+    #[test]
+    fn should_check_audience() {
+        let audiences = vec![
+            "jongleur".to_string(),
+            "http://127.0.0.1:8000/oidc/tokens".to_string(),
+        ];
+
+        let value = |s: &str| Value::String(s.to_string());
+
+        assert!(assertion(value("jongleur")).has_audience(&audiences));
+        assert!(
+            assertion(Value::Array(vec![
+                value("other"),
+                value("http://127.0.0.1:8000/oidc/tokens"),
+            ])).has_audience(&audiences)
+        );
+        assert!(!assertion(value("other")).has_audience(&audiences));
+        assert!(!assertion(Value::Null).has_audience(&audiences));
+    }
+
+    fn trusted_issuers() -> Vec<TrustedIssuer> {
+        vec![
+            TrustedIssuer {
+                issuer: "https://partner.example.com".to_string(),
+                jwks: test_jwks(),
+                subjects: vec![
+                    TrustedSubject {
+                        sub: "alice".to_string(),
+                        end_user_id: "end_user".to_string(),
+                    },
+                ],
+            },
+        ]
+    }
+
+    fn sign(assertion: &JwtBearerAssertion, key: &[u8]) -> String {
+        let mut header = Header::default();
+        header.alg = Algorithm::RS256;
+        encode(&header, assertion, key).unwrap()
+    }
+
+    #[test]
+    fn should_verify_signed_assertion() {
+        let assertion = JwtBearerAssertion {
+            iss: "https://partner.example.com".to_string(),
+            sub: "alice".to_string(),
+            exp: Utc::now().timestamp() + 60,
+            ..assertion(Value::String("jongleur".to_string()))
+        };
+        let decoded =
+            JwtBearerAssertion::decode(&sign(&assertion, TEST_PRIVATE_KEY), &trusted_issuers())
+                .unwrap();
+        assert_eq!(decoded.end_user_id(&trusted_issuers()), Some("end_user".to_string()));
+
+        // Unknown subject
+        let other = JwtBearerAssertion {
+            sub: "bob".to_string(),
+            ..assertion.clone()
+        };
+        assert_eq!(other.end_user_id(&trusted_issuers()), None);
+
+        // Signed by another key
+        let token = sign(&assertion, OTHER_PRIVATE_KEY);
+        assert!(JwtBearerAssertion::decode(&token, &trusted_issuers()).is_err());
+
+        // Issued by another issuer
+        let other = JwtBearerAssertion {
+            iss: "https://other.example.com".to_string(),
+            ..assertion.clone()
+        };
+        let token = sign(&other, TEST_PRIVATE_KEY);
+        assert!(JwtBearerAssertion::decode(&token, &trusted_issuers()).is_err());
+
+        // Expired
+        let expired = JwtBearerAssertion {
+            exp: Utc::now().timestamp() - 60,
+            ..assertion.clone()
+        };
+        let token = sign(&expired, TEST_PRIVATE_KEY);
+        assert!(JwtBearerAssertion::decode(&token, &trusted_issuers()).is_err());
+    }
+}
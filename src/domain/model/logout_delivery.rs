@@ -0,0 +1,126 @@
+use chrono::Duration;
+use chrono::prelude::*;
+
+use util::generate_random_id;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum LogoutDeliveryStatus {
+    #[serde(rename = "pending")]
+    Pending,
+    #[serde(rename = "delivered")]
+    Delivered,
+    #[serde(rename = "failed")]
+    Failed,
+}
+
+/// `LogoutDelivery` records the delivery of a logout token
+/// to the `backchannel_logout_uri` of a client.
+/// Pending deliveries are sent in the background, and failed ones are retried
+/// with exponential backoff.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogoutDelivery {
+    pub id: String,
+    pub client_id: String,
+    pub end_user_id: String,
+    pub sid: Option<String>,
+    pub backchannel_logout_uri: String,
+    pub jti: String,
+    pub logout_token: String,
+    pub status: LogoutDeliveryStatus,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub is_deleted: bool,
+}
+
+impl LogoutDelivery {
+    pub fn new(
+        client_id: &String,
+        end_user_id: &String,
+        sid: &Option<String>,
+        backchannel_logout_uri: &String,
+        jti: &String,
+        logout_token: &String,
+    ) -> Self {
+        let created_at = Utc::now();
+        LogoutDelivery {
+            id: generate_random_id(32usize),
+            client_id: client_id.clone(),
+            end_user_id: end_user_id.clone(),
+            sid: sid.clone(),
+            backchannel_logout_uri: backchannel_logout_uri.clone(),
+            jti: jti.clone(),
+            logout_token: logout_token.clone(),
+            status: LogoutDeliveryStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: created_at,
+            created_at,
+            updated_at: created_at,
+            is_deleted: false,
+        }
+    }
+
+    /// Records that the client accepted the logout token.
+    pub fn succeed(&mut self) {
+        self.attempts += 1;
+        self.status = LogoutDeliveryStatus::Delivered;
+        self.last_error = None;
+        self.updated_at = Utc::now();
+    }
+
+    /// Records a failed attempt. The status stays pending while it can be retried,
+    /// and the interval before the next attempt doubles on every failure.
+    pub fn fail(&mut self, error: &str, max_attempts: i64, retry_interval_sec: i64) {
+        let now = Utc::now();
+        self.attempts += 1;
+        self.last_error = Some(error.to_string());
+        if self.attempts >= max_attempts {
+            self.status = LogoutDeliveryStatus::Failed;
+        } else {
+            let backoff = retry_interval_sec << (self.attempts - 1).min(16);
+            self.next_attempt_at = now + Duration::seconds(backoff);
+        }
+        self.updated_at = now;
+    }
+
+    /// Returns true if the logout token should be sent again at the time.
+    pub fn can_retry(&self, max_attempts: i64, now: &DateTime<Utc>) -> bool {
+        self.status == LogoutDeliveryStatus::Pending && self.attempts < max_attempts
+            && &self.next_attempt_at <= now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_back_off_failed_deliveries() {
+        let mut delivery = LogoutDelivery::new(
+            &"client".to_string(),
+            &"end_user".to_string(),
+            &Some("sid".to_string()),
+            &"https://rp.example.com/logout".to_string(),
+            &"jti".to_string(),
+            &"token".to_string(),
+        );
+        let now = Utc::now();
+        assert!(delivery.can_retry(3, &now));
+
+        delivery.fail("refused", 3, 10);
+        assert_eq!(delivery.status, LogoutDeliveryStatus::Pending);
+        assert!(!delivery.can_retry(3, &now));
+        assert!(delivery.can_retry(3, &(now + Duration::seconds(11))));
+
+        delivery.fail("refused", 3, 10);
+        assert!(!delivery.can_retry(3, &(now + Duration::seconds(11))));
+        assert!(delivery.can_retry(3, &(now + Duration::seconds(21))));
+
+        delivery.fail("refused", 3, 10);
+        assert_eq!(delivery.status, LogoutDeliveryStatus::Failed);
+        assert!(!delivery.can_retry(3, &(now + Duration::days(1))));
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use chrono::prelude::*;
+use jsonwebtoken::{encode, Algorithm, Header};
+
+use domain::error::domain as ed;
+use domain::model::SigningKey;
+use util::generate_random_id;
+use self::ed::ResultExt;
+
+/// `LogoutEvents` is the `events` claim of a logout token.
+/// It has only the back-channel logout event with an empty object.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogoutEvents {
+    #[serde(rename = "http://schemas.openid.net/event/backchannel-logout")]
+    pub backchannel_logout: HashMap<String, String>,
+}
+
+/// `LogoutTokenClaims` is the claims of a logout token of
+/// OpenID Connect Back-Channel Logout.
+/// A logout token MUST NOT contain `nonce`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LogoutTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub aud: String,
+    pub iat: i64,
+    pub jti: String,
+    pub events: LogoutEvents,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+}
+
+impl LogoutTokenClaims {
+    pub fn new(
+        issuer: &str,
+        end_user_id: &String,
+        client_id: &String,
+        sid: &Option<String>,
+    ) -> Self {
+        LogoutTokenClaims {
+            iss: issuer.to_string(),
+            sub: end_user_id.clone(),
+            aud: client_id.clone(),
+            iat: Utc::now().timestamp(),
+            jti: generate_random_id(32usize),
+            events: LogoutEvents {
+                backchannel_logout: HashMap::new(),
+            },
+            sid: sid.clone(),
+        }
+    }
+
+    /// Signs the claims with the key and returns the logout token.
+    pub fn publish(&self, signing_key: &SigningKey) -> Result<String, ed::Error> {
+        let mut header = Header::default();
+        header.alg = Algorithm::RS256;
+        header.kid = Some(signing_key.id.clone());
+        let key = signing_key.private_key_der()?;
+        encode(&header, self, key.as_ref()).chain_err(|| {
+            ed::ErrorKind::ServerError("Encoding claims to JWT failed.".to_string())
+        })
+    }
+}
@@ -1,21 +1,69 @@
 mod access_token;
 mod admin;
+mod authentication;
+mod authorization_response;
+mod claims_request;
 mod client;
+mod client_assertion;
+mod client_certificate;
+mod client_metadata;
+mod consumed_assertion;
+mod device_authorization;
+mod dpop_proof;
 mod end_user;
 mod end_user_claims;
 mod grant;
 mod id_token;
+mod jwk;
+mod jwt_bearer_assertion;
+mod logout_delivery;
+mod logout_token;
+mod prompt;
+mod provider_metadata;
+mod pushed_authorization_request;
 mod refresh_token;
+mod request_object;
 mod resource;
+mod response_mode;
 mod response_type;
+mod signing_key;
+mod standard_claims;
+#[cfg(test)]
+mod test_client;
+#[cfg(test)]
+mod test_key;
 
 pub use self::access_token::*;
 pub use self::admin::*;
+pub use self::authentication::*;
+pub use self::authorization_response::*;
+pub use self::claims_request::*;
 pub use self::client::*;
+pub use self::client_assertion::*;
+pub use self::client_certificate::*;
+pub use self::client_metadata::*;
+pub use self::consumed_assertion::*;
+pub use self::device_authorization::*;
+pub use self::dpop_proof::*;
 pub use self::end_user::*;
 pub use self::end_user_claims::*;
 pub use self::grant::*;
 pub use self::id_token::*;
+pub use self::jwk::*;
+pub use self::jwt_bearer_assertion::*;
+pub use self::logout_delivery::*;
+pub use self::logout_token::*;
+pub use self::prompt::*;
+pub use self::provider_metadata::*;
+pub use self::pushed_authorization_request::*;
 pub use self::refresh_token::*;
+pub use self::request_object::*;
 pub use self::resource::*;
+pub use self::response_mode::*;
 pub use self::response_type::*;
+pub use self::signing_key::*;
+pub use self::standard_claims::*;
+#[cfg(test)]
+pub use self::test_client::*;
+#[cfg(test)]
+pub use self::test_key::*;
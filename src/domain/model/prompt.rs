@@ -0,0 +1,60 @@
+use domain::error::domain as ed;
+
+/// `Prompt` is the type that represents a value of `prompt` in the context of OpenID Connect.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Prompt {
+    None,
+    Login,
+    Consent,
+    SelectAccount,
+}
+
+impl Prompt {
+    pub fn new(prompt: &str) -> Result<Self, ed::Error> {
+        match prompt {
+            "none" => Ok(Prompt::None),
+            "login" => Ok(Prompt::Login),
+            "consent" => Ok(Prompt::Consent),
+            "select_account" => Ok(Prompt::SelectAccount),
+            _ => {
+                Err(ed::ErrorKind::InvalidRequest(format!("Unsupported prompt: {}", prompt)).into())
+            }
+        }
+    }
+
+    /// Returns `Prompt`s created from the space-delimited value of prompt.
+    /// `none` MUST NOT be combined with any other value.
+    pub fn from_str(prompt: &Option<String>) -> Result<Vec<Self>, ed::Error> {
+        let prompt = match *prompt {
+            Some(ref p) => p,
+            None => return Ok(Vec::new()),
+        };
+        let mut prompts = Vec::new();
+        for value in prompt.split(' ').filter(|v| !v.is_empty()) {
+            let p = Prompt::new(value)?;
+            if !prompts.contains(&p) {
+                prompts.push(p);
+            }
+        }
+        if prompts.contains(&Prompt::None) && prompts.len() > 1 {
+            return Err(ed::ErrorKind::InvalidRequest(
+                "prompt=none cannot be combined with other values.".to_string(),
+            ).into());
+        }
+        Ok(prompts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_prompt() {
+        let prompts = Prompt::from_str(&Some("login consent".to_string())).unwrap();
+        assert_eq!(prompts, vec![Prompt::Login, Prompt::Consent]);
+        assert!(Prompt::from_str(&None).unwrap().is_empty());
+        assert!(Prompt::from_str(&Some("none login".to_string())).is_err());
+        assert!(Prompt::from_str(&Some("unknown".to_string())).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use domain::model::{AuthenticationLevel, GrantType, ResponseMode, ResponseType, StandardClaims,
+                    TOKEN_ENDPOINT_AUTH_METHOD_BASIC, TOKEN_ENDPOINT_AUTH_METHOD_NONE,
+                    TOKEN_ENDPOINT_AUTH_METHOD_POST, TOKEN_ENDPOINT_AUTH_METHOD_PRIVATE_KEY_JWT,
+                    TOKEN_ENDPOINT_AUTH_METHOD_SECRET_JWT,
+                    TOKEN_ENDPOINT_AUTH_METHOD_SELF_SIGNED_TLS_CLIENT_AUTH,
+                    TOKEN_ENDPOINT_AUTH_METHOD_TLS_CLIENT_AUTH};
+
+/// `ProviderMetadata` is the type represents the OpenID Provider Metadata
+/// published at `/.well-known/openid-configuration`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+    pub introspection_endpoint: String,
+    pub revocation_endpoint: String,
+    pub jwks_uri: String,
+    pub registration_endpoint: String,
+    pub end_session_endpoint: String,
+    pub pushed_authorization_request_endpoint: String,
+    pub device_authorization_endpoint: String,
+    pub scopes_supported: Vec<String>,
+    pub response_types_supported: Vec<String>,
+    pub response_modes_supported: Vec<String>,
+    pub grant_types_supported: Vec<String>,
+    pub subject_types_supported: Vec<String>,
+    pub id_token_signing_alg_values_supported: Vec<String>,
+    pub authorization_signing_alg_values_supported: Vec<String>,
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+    pub token_endpoint_auth_signing_alg_values_supported: Vec<String>,
+    pub code_challenge_methods_supported: Vec<String>,
+    pub dpop_signing_alg_values_supported: Vec<String>,
+    pub tls_client_certificate_bound_access_tokens: bool,
+    pub acr_values_supported: Vec<String>,
+    pub claims_supported: Vec<String>,
+    pub claims_parameter_supported: bool,
+    pub request_parameter_supported: bool,
+    pub request_uri_parameter_supported: bool,
+    pub require_request_uri_registration: bool,
+    pub request_object_signing_alg_values_supported: Vec<String>,
+    pub require_pushed_authorization_requests: bool,
+    pub backchannel_logout_supported: bool,
+    pub backchannel_logout_session_supported: bool,
+    pub frontchannel_logout_supported: bool,
+    pub frontchannel_logout_session_supported: bool,
+}
+
+impl ProviderMetadata {
+    /// Generates ProviderMetadata of this server.
+    /// `endpoint` is the base URL where the `oidc` routes are mounted.
+    pub fn new(issuer: &str, endpoint: &str) -> Self {
+        let oidc_endpoint = format!("{}/oidc", endpoint.trim_right_matches('/'));
+        ProviderMetadata {
+            issuer: issuer.to_string(),
+            authorization_endpoint: format!("{}/authorize", oidc_endpoint),
+            token_endpoint: format!("{}/tokens", oidc_endpoint),
+            userinfo_endpoint: format!("{}/userinfo", oidc_endpoint),
+            introspection_endpoint: format!("{}/introspect", oidc_endpoint),
+            revocation_endpoint: format!("{}/revoke", oidc_endpoint),
+            jwks_uri: format!("{}/jwks", oidc_endpoint),
+            registration_endpoint: format!("{}/register", oidc_endpoint),
+            end_session_endpoint: format!("{}/end_session", oidc_endpoint),
+            pushed_authorization_request_endpoint: format!("{}/par", oidc_endpoint),
+            device_authorization_endpoint: format!("{}/device_authorization", oidc_endpoint),
+            scopes_supported: [vec!["openid".to_string()], StandardClaims::supported_scope()]
+                .concat(),
+            response_types_supported: ResponseType::supported()
+                .iter()
+                .map(|rt| rt.to_space_delimited())
+                .collect(),
+            response_modes_supported: ResponseMode::supported(),
+            grant_types_supported: GrantType::supported()
+                .iter()
+                .map(|gt| gt.to_str().to_string())
+                .collect(),
+            subject_types_supported: vec!["public".to_string()],
+            id_token_signing_alg_values_supported: vec!["RS256".to_string()],
+            authorization_signing_alg_values_supported: vec!["RS256".to_string()],
+            token_endpoint_auth_methods_supported: vec![
+                TOKEN_ENDPOINT_AUTH_METHOD_BASIC.to_string(),
+                TOKEN_ENDPOINT_AUTH_METHOD_POST.to_string(),
+                TOKEN_ENDPOINT_AUTH_METHOD_SECRET_JWT.to_string(),
+                TOKEN_ENDPOINT_AUTH_METHOD_PRIVATE_KEY_JWT.to_string(),
+                TOKEN_ENDPOINT_AUTH_METHOD_TLS_CLIENT_AUTH.to_string(),
+                TOKEN_ENDPOINT_AUTH_METHOD_SELF_SIGNED_TLS_CLIENT_AUTH.to_string(),
+                TOKEN_ENDPOINT_AUTH_METHOD_NONE.to_string(),
+            ],
+            token_endpoint_auth_signing_alg_values_supported: vec![
+                "RS256".to_string(),
+                "HS256".to_string(),
+            ],
+            code_challenge_methods_supported: vec!["S256".to_string(), "plain".to_string()],
+            dpop_signing_alg_values_supported: vec!["RS256".to_string()],
+            tls_client_certificate_bound_access_tokens: true,
+            acr_values_supported: AuthenticationLevel::supported()
+                .iter()
+                .map(|l| l.to_str().to_string())
+                .collect(),
+            claims_supported: [
+                "iss",
+                "sub",
+                "aud",
+                "exp",
+                "iat",
+                "auth_time",
+                "nonce",
+                "acr",
+                "amr",
+                "sid",
+                "name",
+                "email",
+                "email_verified",
+                "given_name",
+                "family_name",
+                "middle_name",
+                "nickname",
+                "profile",
+                "picture",
+                "website",
+                "gender",
+                "birthdate",
+                "zoneinfo",
+                "locale",
+                "phone_number",
+                "phone_number_verified",
+                "address",
+                "updated_at",
+            ].iter()
+                .map(|c| c.to_string())
+                .collect(),
+            claims_parameter_supported: true,
+            request_parameter_supported: true,
+            request_uri_parameter_supported: true,
+            require_request_uri_registration: false,
+            request_object_signing_alg_values_supported: vec!["RS256".to_string()],
+            require_pushed_authorization_requests: false,
+            backchannel_logout_supported: true,
+            backchannel_logout_session_supported: true,
+            frontchannel_logout_supported: true,
+            frontchannel_logout_session_supported: true,
+        }
+    }
+}
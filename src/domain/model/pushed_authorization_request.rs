@@ -0,0 +1,72 @@
+use chrono::prelude::*;
+use time::Duration;
+
+use config::AppConfig;
+use util::generate_random_id;
+
+/// The prefix of `request_uri` which refers to a pushed authorization request.
+pub const PUSHED_REQUEST_URI_PREFIX: &str = "urn:ietf:params:oauth:request_uri:";
+
+/// `PushedAuthorizationRequest` is the type that represents the authorization parameters
+/// pushed by a client in advance (RFC 9126).
+/// It is referred by `request_uri` once before it expires.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PushedAuthorizationRequest {
+    pub id: String,
+    pub request_uri: String,
+    pub client_id: String,
+    pub response_type: String,
+    pub redirect_uri: String,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub prompt: Option<String>,
+    pub max_age: Option<String>,
+    pub acr_values: Option<String>,
+    pub claims: Option<String>,
+    #[serde(default)]
+    pub response_mode: Option<String>,
+    pub expires_in: i64,
+    pub created_at: DateTime<Utc>,
+    pub is_deleted: bool,
+}
+
+impl PushedAuthorizationRequest {
+    pub fn new(client_id: &String, response_type: &String, redirect_uri: &String) -> Self {
+        PushedAuthorizationRequest {
+            id: generate_random_id(32usize),
+            request_uri: format!("{}{}", PUSHED_REQUEST_URI_PREFIX, generate_random_id(32usize)),
+            client_id: client_id.clone(),
+            response_type: response_type.clone(),
+            redirect_uri: redirect_uri.clone(),
+            scope: None,
+            state: None,
+            nonce: None,
+            code_challenge: None,
+            code_challenge_method: None,
+            prompt: None,
+            max_age: None,
+            acr_values: None,
+            claims: None,
+            response_mode: None,
+            expires_in: AppConfig::pushed_authorization_request_max_age_sec(),
+            created_at: Utc::now(),
+            is_deleted: false,
+        }
+    }
+
+    /// Returns true if the `request_uri` refers to a pushed authorization request.
+    pub fn is_pushed_request_uri(request_uri: &str) -> bool {
+        request_uri.starts_with(PUSHED_REQUEST_URI_PREFIX)
+    }
+
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.created_at.clone() + Duration::seconds(self.expires_in)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !(self.expires_at().timestamp() < Utc::now().timestamp() || self.is_deleted)
+    }
+}
@@ -2,6 +2,7 @@ use chrono::prelude::*;
 use time::Duration;
 
 use config::AppConfig;
+use domain::model::Confirmation;
 use util::generate_random_id;
 
 /// `RefreshToken` is the type that contains a `refresh_token`
@@ -9,27 +10,101 @@ use util::generate_random_id;
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RefreshToken {
     pub token: String,
+    /// The client which the refresh token is issued to. Tokens issued by older versions lack it.
+    #[serde(default)]
+    pub client_id: Option<String>,
     pub access_token_id: String,
     pub id_token_id: Option<String>,
+    /// The JWK thumbprint of the DPoP proof key which the refresh token is bound to.
+    #[serde(default)]
+    pub jkt: Option<String>,
+    /// The thumbprint of the client certificate which the refresh token is bound to.
+    #[serde(default)]
+    pub x5t_s256: Option<String>,
     pub created_at: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
     pub is_deleted: bool,
 }
 
 impl RefreshToken {
-    pub fn new(access_token_id: &String, id_token_id: &Option<String>) -> RefreshToken {
+    pub fn new(
+        client_id: &String,
+        access_token_id: &String,
+        id_token_id: &Option<String>,
+    ) -> RefreshToken {
         let now = Utc::now();
         RefreshToken {
             token: generate_random_id(64usize),
+            client_id: Some(client_id.clone()),
             access_token_id: access_token_id.clone(),
             id_token_id: id_token_id.clone(),
+            jkt: None,
+            x5t_s256: None,
             created_at: now,
             expires_at: now + Duration::seconds(AppConfig::default_refresh_token_max_age_sec()),
             is_deleted: false,
         }
     }
 
+    /// Binds the refresh token to the keys of the confirmation if given.
+    pub fn bind(self, cnf: &Option<Confirmation>) -> Self {
+        let cnf = cnf.clone().unwrap_or_default();
+        RefreshToken {
+            jkt: cnf.jkt,
+            x5t_s256: cnf.x5t_s256,
+            ..self
+        }
+    }
+
     pub fn is_valid(&self) -> bool {
         !(self.expires_at.timestamp() < Utc::now().timestamp() || self.is_deleted)
     }
+
+    /// Returns true if the refresh token is issued to the client.
+    /// Tokens issued by older versions belong to the client of the linked access token,
+    /// and they belong to no one if the access token is gone.
+    pub fn is_issued_to(&self, client_id: &String, linked_client_id: Option<&String>) -> bool {
+        match self.client_id {
+            Some(ref id) => id == client_id,
+            None => linked_client_id == Some(client_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refresh_token(client_id: Option<&str>) -> RefreshToken {
+        let now = Utc::now();
+        RefreshToken {
+            token: "token".to_string(),
+            client_id: client_id.map(|id| id.to_string()),
+            access_token_id: "access_token".to_string(),
+            id_token_id: None,
+            jkt: None,
+            x5t_s256: None,
+            created_at: now,
+            expires_at: now + Duration::seconds(60),
+            is_deleted: false,
+        }
+    }
+
+    #[test]
+    fn should_check_client_of_refresh_token() {
+        let client = "client".to_string();
+        let other = "other".to_string();
+
+        let token = refresh_token(Some("client"));
+        assert!(token.is_issued_to(&client, None));
+        assert!(token.is_issued_to(&client, Some(&other)));
+        assert!(!token.is_issued_to(&other, None));
+        assert!(!token.is_issued_to(&other, Some(&other)));
+
+        // Issued by an older version
+        let token = refresh_token(None);
+        assert!(token.is_issued_to(&client, Some(&client)));
+        assert!(!token.is_issued_to(&other, Some(&client)));
+        assert!(!token.is_issued_to(&client, None));
+    }
 }
@@ -0,0 +1,207 @@
+use chrono::prelude::*;
+use jsonwebtoken::{decode, Algorithm, Validation};
+use serde_json::Value;
+
+use domain::error::domain as ed;
+use domain::model::Client;
+use util::decode_jwt_header;
+
+/// `RequestObject` is the type that represents claims of a request object,
+/// the authorization request passed as a JWT signed by the client.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct RequestObject {
+    pub iss: Option<String>,
+    pub aud: Option<Value>,
+    pub exp: Option<i64>,
+    pub client_id: Option<String>,
+    pub response_type: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub prompt: Option<String>,
+    pub max_age: Option<i64>,
+    pub acr_values: Option<String>,
+    pub claims: Option<Value>,
+    pub response_mode: Option<String>,
+}
+
+impl RequestObject {
+    /// Decodes and verifies a request object with the `jwks` of the client.
+    /// The key is selected by `kid` of the JWT header, or the only key is used without `kid`.
+    pub fn decode(token: &str, client: &Client) -> Result<Self, ed::Error> {
+        let header = decode_jwt_header(token).ok_or::<ed::Error>(
+            ed::ErrorKind::InvalidRequestObject("Invalid JWT header.".to_string()).into(),
+        )?;
+        let jwks = client.metadata.jwks.as_ref().ok_or::<ed::Error>(
+            ed::ErrorKind::InvalidRequestObject("The client has no jwks.".to_string()).into(),
+        )?;
+        let jwk = match header.kid {
+            Some(ref kid) => jwks.find_by_kid(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        }.ok_or::<ed::Error>(
+            ed::ErrorKind::InvalidRequestObject("No key to verify the signature.".to_string())
+                .into(),
+        )?;
+        let validation = Validation {
+            algorithms: Some(vec![Algorithm::RS256]),
+            iss: Some(client.id.clone()),
+            ..Validation::default()
+        };
+        let object = decode::<RequestObject>(token, &jwk.to_rsa_public_key_der()?, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| {
+                ed::ErrorKind::InvalidRequestObject(format!("Verification failed: {}", e))
+            })?;
+
+        if object.iss.as_ref() != Some(&client.id) {
+            return Err(
+                ed::ErrorKind::InvalidRequestObject("iss does not match.".to_string()).into(),
+            );
+        }
+        if object.client_id.as_ref().map_or(false, |id| id != &client.id) {
+            return Err(
+                ed::ErrorKind::InvalidRequestObject("client_id does not match.".to_string()).into(),
+            );
+        }
+        Ok(object)
+    }
+
+    /// Returns true if `aud` includes the issuer.
+    pub fn has_audience(&self, issuer: &str) -> bool {
+        match self.aud {
+            Some(Value::String(ref aud)) => aud == issuer,
+            Some(Value::Array(ref auds)) => auds.iter().any(|a| a.as_str() == Some(issuer)),
+            _ => false,
+        }
+    }
+
+    /// Checks `aud` and `exp`, which a request object is required to have,
+    /// so that it is not replayed to another server or long after it is signed.
+    pub fn validate(&self, issuer: &str, max_age_sec: i64) -> Result<(), ed::Error> {
+        if !self.has_audience(issuer) {
+            return Err(ed::ErrorKind::InvalidRequestObject(
+                "aud does not include the issuer.".to_string(),
+            ).into());
+        }
+        let now = Utc::now().timestamp();
+        match self.exp {
+            Some(exp) if exp <= now => Err(
+                ed::ErrorKind::InvalidRequestObject("The request object has expired.".to_string())
+                    .into(),
+            ),
+            Some(exp) if exp - now > max_age_sec => Err(ed::ErrorKind::InvalidRequestObject(
+                "The request object expires too late.".to_string(),
+            ).into()),
+            Some(_) => Ok(()),
+            None => Err(ed::ErrorKind::InvalidRequestObject("exp is required.".to_string()).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, Header};
+    use domain::model::{test_client, test_jwks, ClientMetadata, OTHER_PRIVATE_KEY,
+                        TEST_PRIVATE_KEY};
+
+    fn client() -> Client {
+        let metadata = ClientMetadata {
+            jwks: Some(test_jwks()),
+            ..ClientMetadata::default()
+        };
+        test_client("client", metadata)
+    }
+
+    fn sign(object: &RequestObject, key: &[u8]) -> String {
+        let mut header = Header::default();
+        header.alg = Algorithm::RS256;
+        encode(&header, object, key).unwrap()
+    }
+
+    fn request_object() -> RequestObject {
+        RequestObject {
+            iss: Some("client".to_string()),
+            aud: Some(Value::String("https://op.example.com".to_string())),
+            exp: Some(Utc::now().timestamp() + 60),
+            client_id: Some("client".to_string()),
+            scope: Some("openid".to_string()),
+            ..RequestObject::default()
+        }
+    }
+
+    #[test]
+    fn should_verify_signed_request_object() {
+        let object = request_object();
+        let decoded = RequestObject::decode(&sign(&object, TEST_PRIVATE_KEY), &client()).unwrap();
+        assert_eq!(decoded.scope, Some("openid".to_string()));
+        assert!(decoded.validate("https://op.example.com", 300).is_ok());
+        assert!(decoded.validate("https://attacker.example.com", 300).is_err());
+
+        // Signed by another key
+        assert!(RequestObject::decode(&sign(&object, OTHER_PRIVATE_KEY), &client()).is_err());
+
+        // Issued by another client
+        let other = RequestObject {
+            iss: Some("other".to_string()),
+            ..object.clone()
+        };
+        assert!(RequestObject::decode(&sign(&other, TEST_PRIVATE_KEY), &client()).is_err());
+        let other = RequestObject {
+            client_id: Some("other".to_string()),
+            ..object.clone()
+        };
+        assert!(RequestObject::decode(&sign(&other, TEST_PRIVATE_KEY), &client()).is_err());
+    }
+
+    #[test]
+    fn should_reject_request_object_without_iss() {
+        let object = RequestObject {
+            iss: None,
+            ..request_object()
+        };
+        let e = RequestObject::decode(&sign(&object, TEST_PRIVATE_KEY), &client()).unwrap_err();
+        assert_eq!(e.description(), "invalid_request_object");
+    }
+
+    #[test]
+    fn should_reject_request_object_without_aud() {
+        let object = RequestObject {
+            aud: None,
+            ..request_object()
+        };
+        let e = object.validate("https://op.example.com", 300).unwrap_err();
+        assert_eq!(e.description(), "invalid_request_object");
+    }
+
+    #[test]
+    fn should_reject_request_object_without_exp() {
+        let object = RequestObject {
+            exp: None,
+            ..request_object()
+        };
+        let e = object.validate("https://op.example.com", 300).unwrap_err();
+        assert_eq!(e.description(), "invalid_request_object");
+    }
+
+    #[test]
+    fn should_reject_request_object_expiring_too_late() {
+        let object = RequestObject {
+            exp: Some(Utc::now().timestamp() + 3600),
+            ..request_object()
+        };
+        assert!(object.validate("https://op.example.com", 300).is_err());
+        assert!(object.validate("https://op.example.com", 7200).is_ok());
+
+        let object = RequestObject {
+            exp: Some(Utc::now().timestamp() - 1),
+            ..request_object()
+        };
+        assert!(object.validate("https://op.example.com", 300).is_err());
+    }
+}
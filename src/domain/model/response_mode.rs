@@ -0,0 +1,168 @@
+use domain::error::domain as ed;
+use domain::model::{FlowType, ResponseType};
+
+/// `ResponseMode` is the type that represents `response_mode`,
+/// how the authorization response is returned to the redirect_uri.
+/// The `.jwt` modes return the response as a signed JWT (JARM).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum ResponseMode {
+    #[serde(rename = "query")]
+    Query,
+    #[serde(rename = "fragment")]
+    Fragment,
+    #[serde(rename = "form_post")]
+    FormPost,
+    #[serde(rename = "query.jwt")]
+    QueryJwt,
+    #[serde(rename = "fragment.jwt")]
+    FragmentJwt,
+    #[serde(rename = "form_post.jwt")]
+    FormPostJwt,
+}
+
+impl ResponseMode {
+    pub fn new(response_mode: &str) -> Option<Self> {
+        match response_mode {
+            "query" => Some(ResponseMode::Query),
+            "fragment" => Some(ResponseMode::Fragment),
+            "form_post" => Some(ResponseMode::FormPost),
+            "query.jwt" => Some(ResponseMode::QueryJwt),
+            "fragment.jwt" => Some(ResponseMode::FragmentJwt),
+            "form_post.jwt" => Some(ResponseMode::FormPostJwt),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(&self) -> &str {
+        match *self {
+            ResponseMode::Query => "query",
+            ResponseMode::Fragment => "fragment",
+            ResponseMode::FormPost => "form_post",
+            ResponseMode::QueryJwt => "query.jwt",
+            ResponseMode::FragmentJwt => "fragment.jwt",
+            ResponseMode::FormPostJwt => "form_post.jwt",
+        }
+    }
+
+    /// Returns the values of `response_mode` supported by this server.
+    /// `jwt` is the shortcut of the default `.jwt` mode of the flow.
+    pub fn supported() -> Vec<String> {
+        [
+            "query",
+            "fragment",
+            "form_post",
+            "query.jwt",
+            "fragment.jwt",
+            "form_post.jwt",
+            "jwt",
+        ].iter()
+            .map(|m| m.to_string())
+            .collect()
+    }
+
+    /// Returns the default mode of the flow.
+    /// Tokens are returned in the fragment so that they are not sent to the server.
+    pub fn default_for(flow_type: &FlowType) -> Self {
+        match *flow_type {
+            FlowType::Implicit | FlowType::Hybrid => ResponseMode::Fragment,
+            _ => ResponseMode::Query,
+        }
+    }
+
+    /// Returns the mode requested for the response_type.
+    /// `query` is not allowed if the response contains tokens,
+    /// because the response JWT is not encrypted.
+    pub fn from_str(
+        response_mode: &Option<String>,
+        response_type: &String,
+    ) -> Result<Self, ed::Error> {
+        let flow_type = ResponseType::from_str(response_type)
+            .map(|rt| rt.flow_type())
+            .unwrap_or(FlowType::Undefined);
+        let mode = match *response_mode {
+            Some(ref m) if m == "jwt" => ResponseMode::default_for(&flow_type).to_jwt(),
+            Some(ref m) => ResponseMode::new(m).ok_or::<ed::Error>(
+                ed::ErrorKind::InvalidRequest(format!("Unsupported response_mode: {}", m)).into(),
+            )?,
+            None => return Ok(ResponseMode::default_for(&flow_type)),
+        };
+        let is_query = mode.base() == ResponseMode::Query;
+        match flow_type {
+            FlowType::Implicit | FlowType::Hybrid if is_query => {
+                Err(ed::ErrorKind::InvalidRequest(format!(
+                    "response_mode={} is not allowed for the response_type.",
+                    mode.to_str()
+                )).into())
+            }
+            _ => Ok(mode),
+        }
+    }
+
+    /// Returns true if the response is returned as a JWT.
+    pub fn is_jwt(&self) -> bool {
+        *self != self.base()
+    }
+
+    /// Returns the mode which delivers the response.
+    pub fn base(&self) -> Self {
+        match *self {
+            ResponseMode::Query | ResponseMode::QueryJwt => ResponseMode::Query,
+            ResponseMode::Fragment | ResponseMode::FragmentJwt => ResponseMode::Fragment,
+            ResponseMode::FormPost | ResponseMode::FormPostJwt => ResponseMode::FormPost,
+        }
+    }
+
+    fn to_jwt(&self) -> Self {
+        match self.base() {
+            ResponseMode::Query => ResponseMode::QueryJwt,
+            ResponseMode::Fragment => ResponseMode::FragmentJwt,
+            _ => ResponseMode::FormPostJwt,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_default_by_flow_type() {
+        let code = "code".to_string();
+        let hybrid = "code id_token".to_string();
+        assert_eq!(
+            ResponseMode::from_str(&None, &code).unwrap(),
+            ResponseMode::Query
+        );
+        assert_eq!(
+            ResponseMode::from_str(&None, &hybrid).unwrap(),
+            ResponseMode::Fragment
+        );
+        assert_eq!(
+            ResponseMode::from_str(&Some("form_post".to_string()), &hybrid).unwrap(),
+            ResponseMode::FormPost
+        );
+        assert!(ResponseMode::from_str(&Some("query".to_string()), &hybrid).is_err());
+        assert!(ResponseMode::from_str(&Some("unknown".to_string()), &code).is_err());
+    }
+
+    #[test]
+    fn should_resolve_jwt_modes() {
+        let code = "code".to_string();
+        let hybrid = "code id_token".to_string();
+        let jwt = Some("jwt".to_string());
+        assert_eq!(
+            ResponseMode::from_str(&jwt, &code).unwrap(),
+            ResponseMode::QueryJwt
+        );
+        assert_eq!(
+            ResponseMode::from_str(&jwt, &hybrid).unwrap(),
+            ResponseMode::FragmentJwt
+        );
+        assert!(ResponseMode::from_str(&Some("query.jwt".to_string()), &hybrid).is_err());
+
+        let mode = ResponseMode::from_str(&Some("form_post.jwt".to_string()), &code).unwrap();
+        assert!(mode.is_jwt());
+        assert_eq!(mode.base(), ResponseMode::FormPost);
+        assert!(!ResponseMode::Fragment.is_jwt());
+    }
+}
@@ -65,6 +65,31 @@ impl ResponseType {
         self.response_type.iter().any(|x| x == &"id_token")
     }
 
+    /// Returns the space-delimited value of response_type.
+    pub fn to_space_delimited(&self) -> String {
+        self.response_type.join(" ")
+    }
+
+    /// Returns all combinations of response_type which have a defined `FlowType`.
+    pub fn supported() -> Vec<ResponseType> {
+        let values = ["code", "id_token", "token"];
+        (1..(1 << values.len()))
+            .map(|bits: usize| {
+                let response_type: Vec<String> = values
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| bits & (1 << i) != 0)
+                    .map(|(_, v)| v.to_string())
+                    .collect();
+                Self::new(&response_type)
+            })
+            .filter(|rt| match rt.flow_type() {
+                FlowType::Undefined => false,
+                _ => true,
+            })
+            .collect()
+    }
+
     pub fn new(response_type: &Vec<String>) -> Self {
         ResponseType {
             response_type: response_type.clone(),
@@ -0,0 +1,138 @@
+use base64;
+use chrono::prelude::*;
+use time::Duration;
+
+use config::AppConfig;
+use domain::error::domain as ed;
+use domain::model::Jwk;
+use util::{decrypt_aes256gcm, encrypt_aes256gcm};
+use self::ed::ResultExt;
+
+/// Encrypted private keys are stored with this prefix.
+/// Keys stored in plaintext by older versions don't have it.
+const ENCRYPTED_PRIVATE_KEY_PREFIX: &str = "aes256gcm:";
+
+lazy_static! {
+    static ref PRIVATE_KEY_ENCRYPTION_KEY: Vec<u8> = AppConfig::signing_key_encryption_key();
+}
+
+/// `SigningKeyStatus` is the state of a key in the key ring.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum SigningKeyStatus {
+    /// Published for verification, but not used for signing yet.
+    #[serde(rename = "next")]
+    Next,
+    /// Used for signing and published for verification.
+    #[serde(rename = "active")]
+    Active,
+    /// Published for verification until it expires.
+    #[serde(rename = "retired")]
+    Retired,
+}
+
+/// `SigningKey` is an RSA key pair to sign JWTs issued by this server.
+/// The `id` is used as `kid` of the JWT header.
+/// The private key is encrypted by `SIGNING_KEY_ENCRYPTION_KEY` at rest.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SigningKey {
+    pub id: String,
+    pub private_key: String,
+    pub public_key: String,
+    pub status: SigningKeyStatus,
+    pub created_at: DateTime<Utc>,
+    pub activated_at: Option<DateTime<Utc>>,
+    pub retired_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub is_deleted: bool,
+}
+
+impl SigningKey {
+    /// Creates a new key as the next key from DER encoded private and public keys.
+    pub fn new(private_key: &Vec<u8>, public_key: &Vec<u8>) -> Result<Self, ed::Error> {
+        let jwk = Jwk::from_rsa_public_key_der(public_key)?;
+        Ok(SigningKey {
+            id: jwk.kid.unwrap(),
+            private_key: encrypt_private_key(private_key)?,
+            public_key: base64::encode(public_key),
+            status: SigningKeyStatus::Next,
+            created_at: Utc::now(),
+            activated_at: None,
+            retired_at: None,
+            expires_at: None,
+            is_deleted: false,
+        })
+    }
+
+    /// Returns the DER encoded private key.
+    pub fn private_key_der(&self) -> Result<Vec<u8>, ed::Error> {
+        let broken = || -> ed::Error {
+            ed::ErrorKind::ServerError(format!("Broken private key. ID => {}", self.id)).into()
+        };
+        if !self.is_encrypted() {
+            return base64::decode(&self.private_key).map_err(|_| broken());
+        }
+        base64::decode(&self.private_key[ENCRYPTED_PRIVATE_KEY_PREFIX.len()..])
+            .ok()
+            .and_then(|sealed| decrypt_aes256gcm(&PRIVATE_KEY_ENCRYPTION_KEY, &sealed))
+            .ok_or_else(broken)
+    }
+
+    /// Returns true if the private key is encrypted at rest.
+    pub fn is_encrypted(&self) -> bool {
+        self.private_key.starts_with(ENCRYPTED_PRIVATE_KEY_PREFIX)
+    }
+
+    /// Encrypts the private key stored in plaintext by older versions.
+    pub fn encrypt(self) -> Result<Self, ed::Error> {
+        if self.is_encrypted() {
+            return Ok(self);
+        }
+        let private_key = encrypt_private_key(&self.private_key_der()?)?;
+        Ok(SigningKey {
+            private_key,
+            ..self
+        })
+    }
+
+    /// Returns the DER encoded public key.
+    pub fn public_key_der(&self) -> Result<Vec<u8>, ed::Error> {
+        base64::decode(&self.public_key).chain_err(|| {
+            ed::ErrorKind::ServerError(format!("Broken public key. ID => {}", self.id))
+        })
+    }
+
+    /// Returns the public key as a JWK.
+    pub fn to_jwk(&self) -> Result<Jwk, ed::Error> {
+        Jwk::from_rsa_public_key_der(&self.public_key_der()?)
+    }
+
+    /// Starts signing with this key.
+    pub fn activate(&mut self) {
+        self.status = SigningKeyStatus::Active;
+        self.activated_at = Some(Utc::now());
+    }
+
+    /// Stops signing with this key. It is still published for `grace_sec`
+    /// so that tokens signed with this key can be verified until they expire.
+    pub fn retire(&mut self, grace_sec: i64) {
+        let now = Utc::now();
+        self.status = SigningKeyStatus::Retired;
+        self.retired_at = Some(now);
+        self.expires_at = Some(now + Duration::seconds(grace_sec));
+    }
+
+    /// Returns true if this key can be used for verification.
+    pub fn is_valid(&self) -> bool {
+        !(self.is_deleted
+            || self.expires_at
+                .as_ref()
+                .map_or(false, |t| t.timestamp() < Utc::now().timestamp()))
+    }
+}
+
+// Private functions
+fn encrypt_private_key(der: &[u8]) -> Result<String, ed::Error> {
+    encrypt_aes256gcm(&PRIVATE_KEY_ENCRYPTION_KEY, der)
+        .map(|sealed| format!("{}{}", ENCRYPTED_PRIVATE_KEY_PREFIX, base64::encode(&sealed)))
+        .ok_or(ed::ErrorKind::ServerError("Encrypting the private key failed.".to_string()).into())
+}
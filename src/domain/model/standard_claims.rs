@@ -0,0 +1,195 @@
+use chrono::prelude::*;
+use std::collections::HashMap;
+
+use domain::model::{ClaimRequest, EndUser};
+
+/// `Address` is the type that represents the postal address of an end user.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Address {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub street_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locality: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+}
+
+/// `StandardClaims` is the type that represents the standard claims of an end user.
+/// Only the claims which the granted scope allows are released.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct StandardClaims {
+    // profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub given_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub family_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub middle_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nickname: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub picture: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub website: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gender: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub birthdate: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zoneinfo: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<i64>,
+    // email
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_verified: Option<bool>,
+    // address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<Address>,
+    // phone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone_number: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone_number_verified: Option<bool>,
+}
+
+impl StandardClaims {
+    /// Returns the claims of the end user which `profile`, `email`, `address`
+    /// and `phone` in the scope allow.
+    pub fn from_end_user(end_user: &EndUser, scope: &Vec<String>) -> Self {
+        let mut claims = StandardClaims::default();
+        for name in scope.iter().flat_map(|s| StandardClaims::claim_names(s)) {
+            claims.release(end_user, name);
+        }
+        claims
+    }
+
+    /// Releases the individual claims requested by the `claims` parameter in addition.
+    pub fn requested(
+        mut self,
+        end_user: &EndUser,
+        requested: Option<&HashMap<String, Option<ClaimRequest>>>,
+    ) -> Self {
+        if let Some(requested) = requested {
+            for name in requested.keys() {
+                self.release(end_user, name);
+            }
+        }
+        self
+    }
+
+    /// Returns the scope which releases the standard claims.
+    pub fn supported_scope() -> Vec<String> {
+        ["profile", "email", "address", "phone"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    fn claim_names(scope: &str) -> Vec<&'static str> {
+        match scope {
+            "profile" => vec![
+                "name",
+                "given_name",
+                "family_name",
+                "middle_name",
+                "nickname",
+                "profile",
+                "picture",
+                "website",
+                "gender",
+                "birthdate",
+                "zoneinfo",
+                "locale",
+                "updated_at",
+            ],
+            "email" => vec!["email", "email_verified"],
+            "address" => vec!["address"],
+            "phone" => vec!["phone_number", "phone_number_verified"],
+            _ => Vec::new(),
+        }
+    }
+
+    fn release(&mut self, end_user: &EndUser, name: &str) {
+        match name {
+            "name" => self.name = Some(end_user.name.clone()),
+            "given_name" => self.given_name = end_user.given_name.clone(),
+            "family_name" => self.family_name = end_user.family_name.clone(),
+            "middle_name" => self.middle_name = end_user.middle_name.clone(),
+            "nickname" => self.nickname = end_user.nickname.clone(),
+            "profile" => self.profile = end_user.profile.clone(),
+            "picture" => self.picture = end_user.picture.clone(),
+            "website" => self.website = end_user.website.clone(),
+            "gender" => self.gender = end_user.gender.clone(),
+            "birthdate" => self.birthdate = end_user.birthdate.clone(),
+            "zoneinfo" => self.zoneinfo = end_user.zoneinfo.clone(),
+            "locale" => self.locale = end_user.locale.clone(),
+            "updated_at" => self.updated_at = Some(end_user.updated_at.timestamp()),
+            "email" => self.email = Some(end_user.email.clone()),
+            "email_verified" => self.email_verified = Some(end_user.email_verified),
+            "address" => self.address = end_user.address.clone(),
+            "phone_number" => self.phone_number = end_user.phone_number.clone(),
+            "phone_number_verified" => {
+                if end_user.phone_number.is_some() {
+                    self.phone_number_verified = Some(end_user.phone_number_verified);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_release_claims_by_scope() {
+        let end_user = EndUser::builder(
+            &"name".to_string(),
+            &"password".to_string(),
+            &"name@example.com".to_string(),
+        ).phone_number(&Some("+81-90-0000-0000".to_string()))
+            .build();
+
+        let claims = StandardClaims::from_end_user(&end_user, &vec!["openid".to_string()]);
+        assert!(claims.name.is_none());
+        assert!(claims.email.is_none());
+        assert!(claims.phone_number.is_none());
+
+        let claims = StandardClaims::from_end_user(&end_user, &vec!["email".to_string()]);
+        assert!(claims.name.is_none());
+        assert_eq!(claims.email, Some("name@example.com".to_string()));
+        assert_eq!(claims.email_verified, Some(false));
+        assert!(claims.phone_number.is_none());
+
+        let claims = StandardClaims::from_end_user(
+            &end_user,
+            &vec!["profile".to_string(), "phone".to_string()],
+        );
+        assert_eq!(claims.name, Some("name".to_string()));
+        assert!(claims.email.is_none());
+        assert_eq!(claims.phone_number_verified, Some(false));
+
+        let mut requested = HashMap::new();
+        requested.insert("name".to_string(), None);
+        let claims = StandardClaims::from_end_user(&end_user, &vec!["email".to_string()])
+            .requested(&end_user, Some(&requested));
+        assert_eq!(claims.name, Some("name".to_string()));
+        assert!(claims.given_name.is_none());
+        assert_eq!(claims.email, Some("name@example.com".to_string()));
+    }
+}
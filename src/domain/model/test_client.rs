@@ -0,0 +1,16 @@
+//! Clients for testing the services.
+use domain::model::{Client, ClientMetadata};
+
+/// Returns a client whose ID is `id` with the secret `password` and the resource `resource`.
+/// The website is `https://<id>.example.com`.
+pub fn test_client(id: &str, metadata: ClientMetadata) -> Client {
+    let mut client = Client::builder(
+        &id.to_string(),
+        &"password".to_string(),
+        &format!("https://{}.example.com", id),
+        &"resource".to_string(),
+    ).metadata(&metadata)
+        .build();
+    client.id = id.to_string();
+    client
+}
@@ -0,0 +1,41 @@
+//! RSA keys for testing the signatures of JWTs.
+use base64;
+use chrono::prelude::*;
+
+use domain::model::{Jwk, JwkSet, SigningKey, SigningKeyStatus};
+
+/// PKCS#1 DER encoded private and public keys.
+pub const TEST_PRIVATE_KEY: &'static [u8] = include_bytes!("../../../res/test/rsa_key.der");
+pub const TEST_PUBLIC_KEY: &'static [u8] = include_bytes!("../../../res/test/rsa_key.pub.der");
+/// Another key pair whose signatures the test key does not verify.
+pub const OTHER_PRIVATE_KEY: &'static [u8] =
+    include_bytes!("../../../res/test/other_rsa_key.der");
+pub const OTHER_PUBLIC_KEY: &'static [u8] =
+    include_bytes!("../../../res/test/other_rsa_key.pub.der");
+
+/// Returns the active signing key of the test key.
+/// The private key is stored in plaintext, so it is not encrypted by the configured key.
+pub fn test_signing_key() -> SigningKey {
+    let jwk = test_jwk();
+    SigningKey {
+        id: jwk.kid.unwrap(),
+        private_key: base64::encode(TEST_PRIVATE_KEY),
+        public_key: base64::encode(TEST_PUBLIC_KEY),
+        status: SigningKeyStatus::Active,
+        created_at: Utc::now(),
+        activated_at: Some(Utc::now()),
+        retired_at: None,
+        expires_at: None,
+        is_deleted: false,
+    }
+}
+
+pub fn test_jwk() -> Jwk {
+    Jwk::from_rsa_public_key_der(TEST_PUBLIC_KEY).unwrap()
+}
+
+pub fn test_jwks() -> JwkSet {
+    JwkSet {
+        keys: vec![test_jwk()],
+    }
+}
@@ -0,0 +1,13 @@
+use domain::error::domain as ed;
+use domain::model::ConsumedAssertion;
+
+pub trait ConsumedAssertionRepository {
+    /// Records the assertion unless the same `issuer` and `jti` have been already recorded.
+    /// Returns false if the assertion is replayed.
+    fn add_if_absent(&self, model: &ConsumedAssertion) -> Result<bool, ed::Error>;
+}
+
+pub trait ConsumedAssertionRepositoryComponent {
+    type ConsumedAssertionRepository: ConsumedAssertionRepository;
+    fn consumed_assertion_repository(&self) -> &Self::ConsumedAssertionRepository;
+}
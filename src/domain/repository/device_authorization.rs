@@ -0,0 +1,31 @@
+use domain::error::domain as ed;
+use domain::model::{DeviceAuthorization, DeviceAuthorizationStatus};
+
+pub trait DeviceAuthorizationRepository {
+    fn find_by_device_code(
+        &self,
+        device_code: &String,
+    ) -> Result<Option<DeviceAuthorization>, ed::Error>;
+    fn find_by_user_code(
+        &self,
+        user_code: &String,
+    ) -> Result<Option<DeviceAuthorization>, ed::Error>;
+    /// Changes the status only if it is `current`, and returns the request before the change.
+    /// Only one of concurrent callers gets the request.
+    fn find_by_id_and_change_status(
+        &self,
+        id: &String,
+        current: &DeviceAuthorizationStatus,
+        status: &DeviceAuthorizationStatus,
+    ) -> Result<Option<DeviceAuthorization>, ed::Error>;
+    /// Records only the polling, so that it never overwrites the status.
+    fn update_polling(&self, model: &DeviceAuthorization) -> Result<(), ed::Error>;
+    fn add(&self, model: &DeviceAuthorization) -> Result<(), ed::Error>;
+    fn update(&self, model: &DeviceAuthorization) -> Result<(), ed::Error>;
+    fn remove(&self, model: DeviceAuthorization) -> Result<(), ed::Error>;
+}
+
+pub trait DeviceAuthorizationRepositoryComponent {
+    type DeviceAuthorizationRepository: DeviceAuthorizationRepository;
+    fn device_authorization_repository(&self) -> &Self::DeviceAuthorizationRepository;
+}
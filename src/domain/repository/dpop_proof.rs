@@ -0,0 +1,13 @@
+use domain::error::domain as ed;
+
+/// `DpopProofRepository` remembers `jti` of DPoP proofs to reject replayed proofs.
+pub trait DpopProofRepository {
+    /// Records `jti` for `expires_in` seconds.
+    /// Returns false if the same `jti` has been already recorded.
+    fn add_if_absent(&self, jti: &String, expires_in: i64) -> Result<bool, ed::Error>;
+}
+
+pub trait DpopProofRepositoryComponent {
+    type DpopProofRepository: DpopProofRepository;
+    fn dpop_proof_repository(&self) -> &Self::DpopProofRepository;
+}
@@ -14,6 +14,7 @@ pub trait GrantRepository {
         code: &String,
         status: &GrantStatus,
     ) -> Result<Option<Grant>, ed::Error>;
+    fn find_by_sid(&self, sid: &String) -> Result<Vec<Grant>, ed::Error>;
     fn find_all(&self) -> Result<Vec<Grant>, ed::Error>;
     fn add(&self, model: &Grant) -> Result<(), ed::Error>;
     fn update(&self, model: &Grant) -> Result<(), ed::Error>;
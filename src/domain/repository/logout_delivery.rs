@@ -0,0 +1,20 @@
+use domain::error::domain as ed;
+use domain::model::{LogoutDelivery, LogoutDeliveryStatus};
+
+pub trait LogoutDeliveryRepository {
+    fn find_by_id(&self, id: &String) -> Result<Option<LogoutDelivery>, ed::Error>;
+    fn find_by_sid(&self, sid: &String) -> Result<Vec<LogoutDelivery>, ed::Error>;
+    fn find_by_status(
+        &self,
+        status: &LogoutDeliveryStatus,
+    ) -> Result<Vec<LogoutDelivery>, ed::Error>;
+    fn find_all(&self) -> Result<Vec<LogoutDelivery>, ed::Error>;
+    fn add(&self, model: &LogoutDelivery) -> Result<(), ed::Error>;
+    fn update(&self, model: &LogoutDelivery) -> Result<(), ed::Error>;
+    fn remove(&self, model: LogoutDelivery) -> Result<(), ed::Error>;
+}
+
+pub trait LogoutDeliveryRepositoryComponent {
+    type LogoutDeliveryRepository: LogoutDeliveryRepository;
+    fn logout_delivery_repository(&self) -> &Self::LogoutDeliveryRepository;
+}
@@ -0,0 +1,24 @@
+use domain::error::domain as ed;
+use domain::model::PushedAuthorizationRequest;
+
+pub trait PushedAuthorizationRequestRepository {
+    fn find_by_request_uri(
+        &self,
+        request_uri: &String,
+    ) -> Result<Option<PushedAuthorizationRequest>, ed::Error>;
+    /// Marks the request as used and returns it only if it has not been used yet,
+    /// so that only one of concurrent authorization requests can use it.
+    fn consume(
+        &self,
+        request_uri: &String,
+    ) -> Result<Option<PushedAuthorizationRequest>, ed::Error>;
+    fn add(&self, model: &PushedAuthorizationRequest) -> Result<(), ed::Error>;
+    fn remove(&self, model: PushedAuthorizationRequest) -> Result<(), ed::Error>;
+}
+
+pub trait PushedAuthorizationRequestRepositoryComponent {
+    type PushedAuthorizationRequestRepository: PushedAuthorizationRequestRepository;
+    fn pushed_authorization_request_repository(
+        &self,
+    ) -> &Self::PushedAuthorizationRequestRepository;
+}
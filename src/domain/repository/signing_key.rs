@@ -0,0 +1,16 @@
+use domain::error::domain as ed;
+use domain::model::{SigningKey, SigningKeyStatus};
+
+pub trait SigningKeyRepository {
+    fn find_by_id(&self, id: &String) -> Result<Option<SigningKey>, ed::Error>;
+    fn find_by_status(&self, status: &SigningKeyStatus) -> Result<Vec<SigningKey>, ed::Error>;
+    fn find_all(&self) -> Result<Vec<SigningKey>, ed::Error>;
+    fn add(&self, model: &SigningKey) -> Result<(), ed::Error>;
+    fn update(&self, model: &SigningKey) -> Result<(), ed::Error>;
+    fn remove(&self, model: SigningKey) -> Result<(), ed::Error>;
+}
+
+pub trait SigningKeyRepositoryComponent {
+    type SigningKeyRepository: SigningKeyRepository;
+    fn signing_key_repository(&self) -> &Self::SigningKeyRepository;
+}
@@ -0,0 +1,16 @@
+use domain::error::domain as ed;
+
+/// `UserCodeAttemptRepository` counts wrong `user_code`s typed by each end user
+/// to limit guessing of the codes of Device Authorization Grant.
+pub trait UserCodeAttemptRepository {
+    /// Returns the number of wrong `user_code`s the end user has typed recently.
+    fn count(&self, end_user_id: &String) -> Result<i64, ed::Error>;
+    /// Records a wrong `user_code`. The records are forgotten `expires_in` seconds
+    /// after the last one.
+    fn add(&self, end_user_id: &String, expires_in: i64) -> Result<(), ed::Error>;
+}
+
+pub trait UserCodeAttemptRepositoryComponent {
+    type UserCodeAttemptRepository: UserCodeAttemptRepository;
+    fn user_code_attempt_repository(&self) -> &Self::UserCodeAttemptRepository;
+}
@@ -1,17 +1,27 @@
+use chrono::prelude::*;
+
 use config::AppConfig;
 use constant;
 use domain::error::domain as ed;
 use self::ed::ErrorKind as ek;
-use domain::model::{AccessToken, Client, EndUser, FlowType, Grant, GrantStatus, IdTokenClaims,
-                    RefreshToken, Resource, ResponseType};
+use domain::model::{is_valid_pkce_value, AccessToken, AuthenticationLevel, AuthenticationMethod,
+                    AuthorizationResponseClaims, ClaimsRequest, Client, CodeChallengeMethod,
+                    Confirmation,
+                    EndUser, FlowType, Grant, GrantStatus, IdTokenClaims, Prompt,
+                    PushedAuthorizationRequest, RefreshToken, Resource, ResponseMode,
+                    ResponseType, StandardClaims, TOKEN_ENDPOINT_AUTH_METHOD_NONE};
 use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent, ClientRepository,
                          ClientRepositoryComponent, EndUserRepository, EndUserRepositoryComponent,
                          GrantRepository, GrantRepositoryComponent, IdTokenRepository,
-                         IdTokenRepositoryComponent, RefreshTokenRepository,
+                         IdTokenRepositoryComponent,
+                         PushedAuthorizationRequestRepositoryComponent, RefreshTokenRepository,
                          RefreshTokenRepositoryComponent, ResourceRepository,
                          ResourceRepositoryComponent};
-use domain::service::{AuthorizeRet, KeyService, KeyServiceComponent, TokensRet};
+use domain::service::{consume_pushed_authorization_request, resolve_pushed_authorization_request,
+                      resolve_request_object, AuthorizeRet, KeyService, KeyServiceComponent,
+                      RequestObjectFetcherComponent, TokensRet};
 
+#[derive(Clone)]
 pub struct AuthorizeCmd {
     pub end_user_id: Option<String>,
     pub client_id: String,
@@ -20,6 +30,22 @@ pub struct AuthorizeCmd {
     pub scope: Option<String>,
     pub state: Option<String>,
     pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub prompt: Option<String>,
+    pub max_age: Option<String>,
+    pub acr_values: Option<String>,
+    pub claims: Option<String>,
+    pub response_mode: Option<String>,
+    /// The request object passed by value.
+    pub request: Option<String>,
+    /// The request object passed by reference.
+    pub request_uri: Option<String>,
+    pub sid: Option<String>,
+    /// The time when the end user authenticated in the session.
+    pub auth_time: Option<i64>,
+    /// The space-delimited authentication methods used in the session.
+    pub amr: Option<String>,
 }
 
 pub struct AcceptClientCmd {
@@ -29,9 +55,10 @@ pub struct AcceptClientCmd {
 }
 
 pub struct AcceptGrantCmd {
-    pub client_id: Option<String>,
-    pub client_secret: Option<String>,
     pub code: Option<String>,
+    pub code_verifier: Option<String>,
+    /// The confirmation of the DPoP proof key which the tokens are bound to.
+    pub cnf: Option<Confirmation>,
 }
 
 /// `AuthorizeService` provides functions for Authorization Code Flow.
@@ -41,9 +68,11 @@ pub trait AuthorizeService
     + EndUserRepositoryComponent
     + GrantRepositoryComponent
     + IdTokenRepositoryComponent
+    + PushedAuthorizationRequestRepositoryComponent
     + RefreshTokenRepositoryComponent
     + ResourceRepositoryComponent
-    + KeyServiceComponent {
+    + KeyServiceComponent
+    + RequestObjectFetcherComponent {
     /// Execute Authorization Code flow.
     fn authorize(&self, cmd: &AuthorizeCmd) -> AuthorizeRet {
         let access_token_repo = self.access_token_repository();
@@ -51,33 +80,10 @@ pub trait AuthorizeService
         let end_user_repo = self.end_user_repository();
         let grant_repo = self.grant_repository();
         let id_token_repo = self.id_token_repository();
+        let pushed_repo = self.pushed_authorization_request_repository();
         let resource_repo = self.resource_repository();
         let key_service = self.key_service();
 
-        // Check login
-        let end_user = match cmd.end_user_id {
-            Some(ref id) => match end_user_repo.find_by_id(id) {
-                Ok(ret) => match ret {
-                    Some(end_user) => end_user,
-                    None => {
-                        return AuthorizeRet::error(
-                            ek::EntityNotFound(format!("End user not found. ID => {}", id)).into(),
-                            None,
-                            None,
-                        );
-                    }
-                },
-                Err(e) => return AuthorizeRet::error(e, None, None),
-            },
-            None => {
-                return AuthorizeRet::error(
-                    ek::RequireLogin("Login required.".to_string()).into(),
-                    None,
-                    None,
-                );
-            }
-        };
-
         // Find client
         // We dunno whether the redirect_uri is valid yet.
         let client = match client_repo.find_by_id(&cmd.client_id).and_then(|v| {
@@ -87,6 +93,47 @@ pub trait AuthorizeService
             Err(e) => return AuthorizeRet::error(e, None, cmd.state.clone()),
         };
 
+        // Replace the request with the pushed one.
+        // Clients which require PAR are not allowed to pass the parameters in the query.
+        let pushed;
+        let pushed_request_uri = match cmd.request_uri {
+            Some(ref uri) if PushedAuthorizationRequest::is_pushed_request_uri(uri) => {
+                Some(uri.clone())
+            }
+            _ => None,
+        };
+        let cmd = match pushed_request_uri {
+            Some(ref uri) => {
+                pushed = match resolve_pushed_authorization_request(cmd, uri, pushed_repo) {
+                    Ok(c) => c,
+                    Err(e) => return AuthorizeRet::error(e, None, cmd.state.clone()),
+                };
+                &pushed
+            }
+            _ if client.metadata.require_pushed_authorization_requests => {
+                return AuthorizeRet::error(
+                    ek::InvalidRequest("Pushed authorization request is required.".to_string())
+                        .into(),
+                    None,
+                    cmd.state.clone(),
+                )
+            }
+            _ => cmd,
+        };
+
+        // Resolve the request object before the redirect_uri is validated,
+        // because the request object may override the redirect_uri.
+        let resolved;
+        let cmd = if cmd.request.is_some() || cmd.request_uri.is_some() {
+            resolved = match resolve_request_object(cmd, &client, self.request_object_fetcher()) {
+                Ok(c) => c,
+                Err(e) => return AuthorizeRet::error(e, None, cmd.state.clone()),
+            };
+            &resolved
+        } else {
+            cmd
+        };
+
         // First of all, we should check whether the redirect_uri is valid or not.
         // If the redirect_uri is invalid, we MUST NOT send any data to that uri.
         let redirect_uri = match validate_redirect_uri(&cmd.redirect_uri, &client) {
@@ -96,20 +143,86 @@ pub trait AuthorizeService
 
         // Now we know that the redirect_uri is valid.
         // From now, any errors are wrapped into the AuthorizationCodeRet.
+        let response_mode = match ResponseMode::from_str(&cmd.response_mode, &cmd.response_type) {
+            Ok(m) => m,
+            Err(e) => {
+                let default_mode = ResponseMode::from_str(&None, &cmd.response_type)
+                    .unwrap_or(ResponseMode::Query);
+                return AuthorizeRet::error(e, Some(redirect_uri), cmd.state.clone())
+                    .response_mode(&default_mode);
+            }
+        };
+        // JARM signs the response for the client
+        let respond = |ret: AuthorizeRet| {
+            secure_response(ret.response_mode(&response_mode), &cmd.client_id, key_service)
+        };
+        let prompt = match Prompt::from_str(&cmd.prompt) {
+            Ok(p) => p,
+            Err(e) => {
+                return respond(AuthorizeRet::error(e, Some(redirect_uri), cmd.state.clone()))
+            }
+        };
+        let max_age = match parse_max_age(&cmd.max_age) {
+            Ok(m) => m,
+            Err(e) => {
+                return respond(AuthorizeRet::error(e, Some(redirect_uri), cmd.state.clone()))
+            }
+        };
+        let claims = match ClaimsRequest::from_str(&cmd.claims) {
+            Ok(c) => c,
+            Err(e) => {
+                return respond(AuthorizeRet::error(e, Some(redirect_uri), cmd.state.clone()))
+            }
+        };
+
+        if let Err(e) = check_acr_supported(&cmd.acr_values, &claims) {
+            return respond(AuthorizeRet::error(e, Some(redirect_uri), cmd.state.clone()));
+        }
+
+        // Check login
+        // The SPA shows the login page and retries this request if the login is required,
+        // but it MUST NOT interact with the end user if prompt=none.
+        let end_user = match check_login(cmd, &prompt, &max_age, end_user_repo) {
+            Ok(end_user) => end_user,
+            Err(e) => {
+                let require_login = match *e.kind() {
+                    ek::RequireLogin(_) | ek::EntityNotFound(_) => true,
+                    _ => false,
+                };
+                if require_login && prompt.contains(&Prompt::None) {
+                    return respond(AuthorizeRet::error(
+                        ek::LoginRequired(e.to_string()).into(),
+                        Some(redirect_uri),
+                        cmd.state.clone(),
+                    ));
+                }
+                return AuthorizeRet::error(e, None, None);
+            }
+        };
+
         let ret = execute_authorization_code_flow(
             cmd,
+            &prompt,
+            &claims,
             client,
             end_user,
             &redirect_uri,
+            &response_mode,
             access_token_repo,
             grant_repo,
             id_token_repo,
             resource_repo,
             key_service,
         );
+        // The pushed request is consumed after the grant is issued,
+        // and the response is discarded if another request has used it meanwhile.
+        let ret = ret.and_then(|r| match pushed_request_uri {
+            Some(ref uri) => consume_pushed_authorization_request(uri, pushed_repo).map(|_| r),
+            None => Ok(r),
+        });
         match ret {
-            Ok(r) => r,
-            Err(e) => AuthorizeRet::error(e, Some(redirect_uri), cmd.state.clone()),
+            Ok(r) => respond(r),
+            Err(e) => respond(AuthorizeRet::error(e, Some(redirect_uri), cmd.state.clone())),
         }
     }
 
@@ -127,6 +240,15 @@ pub trait AuthorizeService
             Ok(g) => g,
             Err(e) => return AuthorizeRet::error(e, None, None),
         };
+        // The grant keeps the response_mode while the end user reviews the client.
+        let response_mode = grant
+            .response_mode
+            .clone()
+            .unwrap_or_else(|| ResponseMode::default_for(&grant.response_type.flow_type()));
+        let client_id = grant.client_id.clone();
+        let respond = |ret: AuthorizeRet| {
+            secure_response(ret.response_mode(&response_mode), &client_id, key_service)
+        };
 
         let ret = execute_accepting_client(
             cmd,
@@ -138,15 +260,14 @@ pub trait AuthorizeService
             key_service,
         );
         match ret {
-            Ok(r) => r,
-            Err(e) => AuthorizeRet::error(e, Some(grant.redirect_uri), grant.state),
+            Ok(r) => respond(r),
+            Err(e) => respond(AuthorizeRet::error(e, Some(grant.redirect_uri), grant.state)),
         }
     }
 
     /// Check the grant code and
-    fn accept_grant(&self, cmd: &AcceptGrantCmd) -> TokensRet {
+    fn accept_grant(&self, client: &Client, cmd: &AcceptGrantCmd) -> TokensRet {
         let access_token_repo = self.access_token_repository();
-        let client_repo = self.client_repository();
         let end_user_repo = self.end_user_repository();
         let grant_repo = self.grant_repository();
         let id_token_repo = self.id_token_repository();
@@ -155,8 +276,8 @@ pub trait AuthorizeService
 
         let ret = execute_accepting_grant(
             cmd,
+            client,
             access_token_repo,
-            client_repo,
             end_user_repo,
             grant_repo,
             id_token_repo,
@@ -182,20 +303,53 @@ impl<
         + EndUserRepositoryComponent
         + GrantRepositoryComponent
         + IdTokenRepositoryComponent
+        + PushedAuthorizationRequestRepositoryComponent
         + RefreshTokenRepositoryComponent
         + ResourceRepositoryComponent
-        + KeyServiceComponent,
+        + KeyServiceComponent
+        + RequestObjectFetcherComponent,
 > AuthorizeService for T
 {
 }
 
+/// Check login and the action of the end user, then add the new accepted client to the end user.
+pub fn accept_client_by_end_user(
+    end_user_id: &Option<String>,
+    action: &String,
+    client_id: &String,
+    scope: &Vec<String>,
+    end_user_repo: &EndUserRepository,
+) -> Result<EndUser, ed::Error> {
+    // Check login
+    let mut end_user = match *end_user_id {
+        Some(ref id) => end_user_repo.find_by_id(id).and_then(|v| {
+            v.ok_or(ek::EntityNotFound(format!("End user not found. ID => {}", id)).into())
+        })?,
+        None => {
+            return Err(ek::RequireLogin("Login required.".to_string()).into());
+        }
+    };
+
+    if action != constant::ACTION_ACCEPT {
+        return Err(ek::AccessDenied("The user rejected the request.".to_string()).into());
+    }
+
+    // Update end_user
+    end_user.add_accepted_client(client_id, scope);
+    end_user_repo.update(&end_user)?;
+    Ok(end_user)
+}
+
 // Private functions
 /// Execute Authorization Code flow.
 fn execute_authorization_code_flow(
     cmd: &AuthorizeCmd,
+    prompt: &Vec<Prompt>,
+    claims: &Option<ClaimsRequest>,
     client: Client,
     end_user: EndUser,
     redirect_uri: &String,
+    response_mode: &ResponseMode,
     access_token_repo: &AccessTokenRepository,
     grant_repo: &GrantRepository,
     id_token_repo: &IdTokenRepository,
@@ -207,12 +361,31 @@ fn execute_authorization_code_flow(
         .and_then(|v| v.ok_or(ek::EntityNotFound(format!("ID => {}", client.resource_id)).into()))?;
 
     // Validation
-    let response_type = validate_response_type(&cmd.response_type)?;
+    let response_type = validate_response_type(&cmd.response_type, &client)?;
     let scope = cmd.scope
         .as_ref()
         .map_or(Vec::new(), |v| validate_scope_str(v, &resource));
+    let code_challenge_method =
+        validate_code_challenge(&cmd.code_challenge, &cmd.code_challenge_method, &client)?;
+
+    // prompt=consent forces the acceptance even if the end user has accepted the scope.
+    let require_acceptance = prompt.contains(&Prompt::Consent)
+        || end_user.require_acceptance(&scope, &cmd.client_id);
+    if require_acceptance && prompt.contains(&Prompt::None) {
+        let accepted = end_user
+            .accepted_clients
+            .iter()
+            .any(|c| c.client_id == cmd.client_id);
+        // The first authorization requires the end user to review the client itself.
+        return if accepted {
+            Err(ek::ConsentRequired("New scope requires acceptance.".to_string()).into())
+        } else {
+            Err(ek::InteractionRequired("The client has not been accepted.".to_string()).into())
+        };
+    }
 
     // Generate Grant
+    let methods = AuthenticationMethod::from_str(&cmd.amr);
     let grant = Grant::builder(
         &end_user.id,
         &cmd.client_id,
@@ -221,12 +394,19 @@ fn execute_authorization_code_flow(
         redirect_uri,
     ).state(&cmd.state)
         .nonce(&cmd.nonce)
+        .response_mode(response_mode)
         .scope(&scope)
+        .code_challenge(&cmd.code_challenge, &code_challenge_method)
+        .sid(&cmd.sid)
+        .auth_time(&cmd.auth_time)
+        .acr(&Some(AuthenticationLevel::from_methods(&methods).to_str().to_string()))
+        .amr(&Some(AuthenticationMethod::to_amr(&methods)))
+        .claims(claims)
         .build();
     grant_repo.add(&grant)?;
 
     // Require new acceptance?
-    if end_user.require_acceptance(&scope, &cmd.client_id) {
+    if require_acceptance {
         // Return and request new acceptance
         let ret = AuthorizeRet::require_acceptance(grant.id, resource.convert_scope(&scope));
         Ok(ret)
@@ -243,6 +423,94 @@ fn execute_authorization_code_flow(
     }
 }
 
+/// Parses the max_age parameter in seconds.
+fn parse_max_age(max_age: &Option<String>) -> Result<Option<i64>, ed::Error> {
+    match *max_age {
+        Some(ref m) => match m.parse::<i64>() {
+            Ok(v) if v >= 0 => Ok(Some(v)),
+            _ => Err(ek::InvalidRequest(format!("Invalid max_age. max_age => {}", m)).into()),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Find the logged in end user.
+/// prompt=login and prompt=select_account require the end user to have logged in just now,
+/// and max_age requires the end user to have logged in within that many seconds.
+fn check_login(
+    cmd: &AuthorizeCmd,
+    prompt: &Vec<Prompt>,
+    max_age: &Option<i64>,
+    end_user_repo: &EndUserRepository,
+) -> Result<EndUser, ed::Error> {
+    let id = match cmd.end_user_id {
+        Some(ref id) => id,
+        None => return Err(ek::RequireLogin("Login required.".to_string()).into()),
+    };
+    let end_user = end_user_repo.find_by_id(id).and_then(|v| {
+        v.ok_or(ek::EntityNotFound(format!("End user not found. ID => {}", id)).into())
+    })?;
+    check_auth_time(
+        &cmd.auth_time,
+        Utc::now().timestamp(),
+        prompt,
+        max_age,
+        AppConfig::prompt_login_grace_sec(),
+    )?;
+    Ok(end_user)
+}
+
+/// Rejects acr_values and the essential acr claim which no login of this server achieves,
+/// instead of asking the SPA for a login again and again.
+/// Every login achieves all the supported levels, so none of the values may be unknown.
+fn check_acr_supported(
+    acr_values: &Option<String>,
+    claims: &Option<ClaimsRequest>,
+) -> Result<(), ed::Error> {
+    let requested = vec![
+        acr_values.clone(),
+        claims.as_ref().and_then(|c| c.essential_acr_values()),
+    ];
+    let unsupported = requested.into_iter().any(|r| {
+        r.is_some() && AuthenticationLevel::from_acr_values(&r).is_none()
+    });
+    if unsupported {
+        return Err(ek::UnmetAuthenticationRequirements(
+            "The requested authentication level is not supported.".to_string(),
+        ).into());
+    }
+    Ok(())
+}
+
+/// Checks the authentication time of the session.
+/// A login within the grace period counts as "just now" for prompt=login,
+/// so that the SPA can retry this request after the login.
+/// max_age=0 is equivalent to prompt=login, and other max_age values are never extended.
+fn check_auth_time(
+    auth_time: &Option<i64>,
+    now: i64,
+    prompt: &Vec<Prompt>,
+    max_age: &Option<i64>,
+    grace: i64,
+) -> Result<(), ed::Error> {
+    let reauthentication = prompt.contains(&Prompt::Login)
+        || prompt.contains(&Prompt::SelectAccount) || *max_age == Some(0);
+    if reauthentication {
+        let logged_in_just_now = auth_time.map_or(false, |t| now - t <= grace);
+        if !logged_in_just_now {
+            return Err(ek::RequireLogin("Re-authentication required.".to_string()).into());
+        }
+    }
+    if let Some(max_age) = *max_age {
+        let logged_in_recently =
+            auth_time.map_or(false, |t| max_age == 0 || now - t <= max_age);
+        if !logged_in_recently {
+            return Err(ek::RequireLogin("Authentication is too old.".to_string()).into());
+        }
+    }
+    Ok(())
+}
+
 /// Check parameters and add the new accepted client to the end user.
 fn execute_accepting_client(
     cmd: &AcceptClientCmd,
@@ -253,30 +521,22 @@ fn execute_accepting_client(
     id_token_repo: &IdTokenRepository,
     key_service: &KeyService,
 ) -> Result<AuthorizeRet, ed::Error> {
-    // Check login
-    let mut end_user = match cmd.end_user_id {
-        Some(ref id) => end_user_repo.find_by_id(id).and_then(|v| {
-            v.ok_or(ek::EntityNotFound(format!("End user not found. ID => {}", id)).into())
-        })?,
-        None => {
-            return Err(ek::RequireLogin("Login required.".to_string()).into());
+    if let Some(ref id) = cmd.end_user_id {
+        if id != &grant.end_user_id {
+            return Err(ek::AccessDenied(format!(
+                "The granted user does not match. EndUser => {}",
+                id
+            )).into());
         }
-    };
-
-    if cmd.action != constant::ACTION_ACCEPT {
-        return Err(ek::AccessDenied("The user rejected the request.".to_string()).into());
     }
 
-    if end_user.id != grant.end_user_id {
-        return Err(ek::AccessDenied(format!(
-            "The granted user does not match. EndUser => {}",
-            end_user.id
-        )).into());
-    }
-
-    // Update end_user
-    end_user.add_accepted_client(&grant.client_id, &grant.scope);
-    end_user_repo.update(&end_user)?;
+    let end_user = accept_client_by_end_user(
+        &cmd.end_user_id,
+        &cmd.action,
+        &grant.client_id,
+        &grant.scope,
+        end_user_repo,
+    )?;
 
     // Generate tokens
     generate_code_or_tokens(
@@ -333,12 +593,17 @@ fn generate_code_or_tokens(
     };
 
     // Access token
+    let auth_time = grant_auth_time(&grant, end_user);
     let access_token = if grant.response_type.has_token() {
         let access_token = AccessToken::builder(&grant.client_id, &grant.resource_id)
             .end_user_id(&Some(grant.end_user_id.clone()))
             .state(&grant.state)
             .scope(&grant.scope)
             .nonce(&grant.nonce)
+            .acr(&grant.acr)
+            .amr(&grant.amr)
+            .auth_time(&auth_time)
+            .claims(&grant.claims)
             .build();
         access_token_repo.add(&access_token)?;
         Some(access_token)
@@ -348,10 +613,20 @@ fn generate_code_or_tokens(
 
     // Id token
     let id_token = if grant.response_type.has_id_token() {
-        let id_token =
-            IdTokenClaims::from_end_user(&AppConfig::issuer(), end_user, &grant.client_id)
-                .nonce(&grant.nonce)
-                .publish(key_service.jwt_private_key())?;
+        let id_token = IdTokenClaims::from_end_user(
+            &AppConfig::issuer(),
+            end_user,
+            &grant.client_id,
+            &auth_time,
+        ).nonce(&grant.nonce)
+            .sid(&grant.sid)
+            .acr(&grant.acr)
+            .amr(&grant.amr)
+            .standard_claims(
+                StandardClaims::from_end_user(end_user, &grant.scope)
+                    .requested(end_user, grant.id_token_claims()),
+            )
+            .publish(&key_service.active_signing_key()?)?;
         id_token_repo.add(&id_token)?;
         Some(id_token)
     } else {
@@ -360,6 +635,7 @@ fn generate_code_or_tokens(
 
     // Result
     AuthorizeRet::builder(grant.redirect_uri.clone(), grant.state.clone())
+        .client_id(&grant.client_id)
         .code(code)
         .access_token(access_token)
         .id_token(id_token)
@@ -370,30 +646,14 @@ fn generate_code_or_tokens(
 /// Check the grant code and return tokens
 fn execute_accepting_grant(
     cmd: &AcceptGrantCmd,
+    client: &Client,
     access_token_repo: &AccessTokenRepository,
-    client_repo: &ClientRepository,
     end_user_repo: &EndUserRepository,
     grant_repo: &GrantRepository,
     id_token_repo: &IdTokenRepository,
     refresh_token_repo: &RefreshTokenRepository,
     key_service: &KeyService,
 ) -> Result<TokensRet, ed::Error> {
-    // Authorize the client
-    let (client_id, client_secret) = cmd.client_id
-        .as_ref()
-        .and_then(|id| cmd.client_secret.as_ref().map(|secret| (id, secret)))
-        .ok_or(ek::UnauthorizedClient("Invalid credentials.".to_string()))?;
-
-    let client = client_repo.find_by_id(&client_id).and_then(|c| {
-        c.ok_or(ek::UnauthorizedClient(format!("Client not found. ID => {}", client_id)).into())
-    })?;
-
-    if !client.is_authenticated_by_secret(&client_secret) {
-        return Err(
-            ek::UnauthorizedClient(format!("Client not authorized. ID => {}", client_id)).into(),
-        );
-    }
-
     // Fetch grant
     let grant = match cmd.code {
         Some(ref c) => grant_repo
@@ -404,6 +664,23 @@ fn execute_accepting_grant(
         None => return Err(ek::InvalidGrant("Invalid grant code.".to_string()).into()),
     };
 
+    // The grant MUST be issued to the authenticated client.
+    if grant.client_id != client.id {
+        return Err(ek::InvalidGrant(format!(
+            "The grant was not issued to the client. ID => {}",
+            client.id
+        )).into());
+    }
+
+    // PKCE
+    // Clients authenticated without credentials MUST use PKCE.
+    if client.token_endpoint_auth_method() == TOKEN_ENDPOINT_AUTH_METHOD_NONE
+        && grant.code_challenge.is_none()
+    {
+        return Err(ek::InvalidGrant("Public clients must use PKCE.".to_string()).into());
+    }
+    grant.verify_code_verifier(&cmd.code_verifier)?;
+
     // EndUser
     let end_user = end_user_repo.find_by_id(&grant.end_user_id).and_then(|v| {
         v.ok_or(ek::InvalidGrant(format!("EndUser not found. ID => {}", grant.end_user_id)).into())
@@ -432,20 +709,37 @@ fn execute_accepting_grant(
 
     // Access token is necessary
     // TODO: Should we re-check the scope here?
+    let cnf = client.confirmation(&cmd.cnf)?;
+    let auth_time = grant_auth_time(&grant, &end_user);
     let access_token = AccessToken::builder(&grant.client_id, &grant.resource_id)
         .end_user_id(&Some(grant.end_user_id.clone()))
         .state(&grant.state)
         .scope(&grant.scope)
         .nonce(&grant.nonce)
+        .acr(&grant.acr)
+        .amr(&grant.amr)
+        .auth_time(&auth_time)
+        .claims(&grant.claims)
+        .cnf(&cnf)
         .build();
     access_token_repo.add(&access_token)?;
 
     // Id token
     let id_token = if grant.response_type.has_id_token() {
-        let id_token =
-            IdTokenClaims::from_end_user(&AppConfig::issuer(), &end_user, &grant.client_id)
-                .nonce(&grant.nonce)
-                .publish(key_service.jwt_private_key())?;
+        let id_token = IdTokenClaims::from_end_user(
+            &AppConfig::issuer(),
+            &end_user,
+            &grant.client_id,
+            &auth_time,
+        ).nonce(&grant.nonce)
+            .sid(&grant.sid)
+            .acr(&grant.acr)
+            .amr(&grant.amr)
+            .standard_claims(
+                StandardClaims::from_end_user(&end_user, &grant.scope)
+                    .requested(&end_user, grant.id_token_claims()),
+            )
+            .publish(&key_service.active_signing_key()?)?;
         id_token_repo.add(&id_token)?;
         Some(id_token)
     } else {
@@ -453,8 +747,17 @@ fn execute_accepting_grant(
     };
 
     // Refresh token
-    let refresh_token =
-        RefreshToken::new(&access_token.id, &id_token.as_ref().map(|v| v.id.clone()));
+    // Refresh tokens of public clients are bound to the same keys as well.
+    let refresh_cnf = if client.token_endpoint_auth_method() == TOKEN_ENDPOINT_AUTH_METHOD_NONE {
+        cnf
+    } else {
+        None
+    };
+    let refresh_token = RefreshToken::new(
+        &access_token.client_id,
+        &access_token.id,
+        &id_token.as_ref().map(|v| v.id.clone()),
+    ).bind(&refresh_cnf);
     refresh_token_repo.add(&refresh_token)?;
 
     Ok(TokensRet::builder(&access_token)
@@ -463,12 +766,88 @@ fn execute_accepting_grant(
         .build())
 }
 
+/// Returns the auth_time of the session which authorized the grant.
+/// Grants issued before the session tracked it fall back to the end user's last login.
+fn grant_auth_time(grant: &Grant, end_user: &EndUser) -> Option<i64> {
+    grant
+        .auth_time
+        .or(end_user.authenticated_at.as_ref().map(|t| t.timestamp()))
+}
+
+/// Returns the response signed as a JWT if the response_mode requires (JARM).
+/// The response is not returned to the redirect_uri if it cannot be signed.
+fn secure_response(
+    ret: AuthorizeRet,
+    client_id: &String,
+    key_service: &KeyService,
+) -> AuthorizeRet {
+    if !ret.response_mode.is_jwt() || ret.redirect_uri.is_none() {
+        return ret;
+    }
+    let response = key_service.active_signing_key().and_then(|key| {
+        AuthorizationResponseClaims::new(
+            &AppConfig::issuer(),
+            client_id,
+            &ret.kind,
+            AppConfig::authorization_response_max_age_sec(),
+        )?
+            .publish(&key)
+    });
+    match response {
+        Ok(r) => ret.jwt(r),
+        Err(e) => AuthorizeRet::error(e, None, None),
+    }
+}
+
 /// Validate the string that represents response_type, then return `ResponseType` if the string is valid.
-fn validate_response_type(response_type: &String) -> Result<ResponseType, ed::Error> {
+/// The client MUST have registered the response_type.
+fn validate_response_type(
+    response_type: &String,
+    client: &Client,
+) -> Result<ResponseType, ed::Error> {
     let response_type = ResponseType::from_str(response_type)?;
-    match response_type.flow_type() {
-        FlowType::Undefined => Err(ek::InvalidRequest("Invalid response_type.".to_string()).into()),
-        _ => Ok(response_type),
+    if let FlowType::Undefined = response_type.flow_type() {
+        return Err(ek::InvalidRequest("Invalid response_type.".to_string()).into());
+    }
+    if !client.allows_response_type(&response_type) {
+        return Err(ek::UnauthorizedClient(format!(
+            "The client is not allowed to use response_type {}.",
+            response_type.to_space_delimited()
+        )).into());
+    }
+    Ok(response_type)
+}
+
+/// Validate code_challenge and code_challenge_method of PKCE with the Client,
+/// then return `CodeChallengeMethod` if the code_challenge is given.
+fn validate_code_challenge(
+    code_challenge: &Option<String>,
+    code_challenge_method: &Option<String>,
+    client: &Client,
+) -> Result<Option<CodeChallengeMethod>, ed::Error> {
+    match *code_challenge {
+        Some(ref c) => {
+            if !is_valid_pkce_value(c) {
+                return Err(ek::InvalidRequest("Invalid code_challenge.".to_string()).into());
+            }
+            // Defaults to "plain" if not present in the request.
+            let method = match *code_challenge_method {
+                Some(ref m) => CodeChallengeMethod::new(m)?,
+                None => CodeChallengeMethod::Plain,
+            };
+            Ok(Some(method))
+        }
+        None => {
+            if client.require_pkce {
+                return Err(ek::InvalidRequest("code_challenge is required.".to_string()).into());
+            }
+            if code_challenge_method.is_some() {
+                return Err(ek::InvalidRequest(
+                    "code_challenge_method requires code_challenge.".to_string(),
+                ).into());
+            }
+            Ok(None)
+        }
     }
 }
 
@@ -486,3 +865,58 @@ fn validate_scope_str(scope: &String, resource: &Resource) -> Vec<String> {
     let scope = scope.split(" ").map(|s| s.to_string()).collect();
     resource.filter_scope(&scope)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use domain::model::{test_client, ClientMetadata};
+
+    #[test]
+    fn should_apply_grace_only_to_reauthentication() {
+        let now = 1_000_000;
+        let auth_time = Some(now - 30);
+        let login = vec![Prompt::Login];
+
+        // prompt=login and max_age=0 accept a login within the grace period.
+        assert!(check_auth_time(&auth_time, now, &login, &None, 60).is_ok());
+        assert!(check_auth_time(&auth_time, now, &vec![], &Some(0), 60).is_ok());
+        assert!(check_auth_time(&Some(now - 61), now, &login, &None, 60).is_err());
+        assert!(check_auth_time(&None, now, &login, &None, 60).is_err());
+
+        // The grace period does not extend max_age.
+        assert!(check_auth_time(&auth_time, now, &vec![], &Some(10), 60).is_err());
+        assert!(check_auth_time(&auth_time, now, &login, &Some(10), 60).is_err());
+        assert!(check_auth_time(&auth_time, now, &vec![], &Some(30), 60).is_ok());
+        assert!(check_auth_time(&auth_time, now, &vec![], &None, 60).is_ok());
+    }
+
+    #[test]
+    fn should_reject_unsupported_acr() {
+        assert!(check_acr_supported(&Some("1".to_string()), &None).is_ok());
+        assert!(check_acr_supported(&Some("2 1".to_string()), &None).is_ok());
+        assert!(check_acr_supported(&None, &None).is_ok());
+
+        let err = check_acr_supported(&Some("2".to_string()), &None).unwrap_err();
+        assert_eq!(err.description(), "unmet_authentication_requirements");
+
+        let claims = ClaimsRequest::from_str(&Some(
+            r#"{"id_token": {"acr": {"essential": true, "values": ["2"]}}}"#.to_string(),
+        )).unwrap();
+        assert!(check_acr_supported(&None, &claims).is_err());
+    }
+
+    #[test]
+    fn should_reject_unregistered_response_type() {
+        let metadata = ClientMetadata {
+            response_types: vec!["code".to_string()],
+            ..ClientMetadata::default()
+        };
+        let client = test_client("client", metadata);
+
+        assert!(validate_response_type(&"code".to_string(), &client).is_ok());
+        let err = validate_response_type(&"code id_token".to_string(), &client).unwrap_err();
+        assert_eq!(err.description(), "unauthorized_client");
+        assert!(validate_response_type(&"none".to_string(), &client).is_err());
+    }
+}
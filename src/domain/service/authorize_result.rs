@@ -1,4 +1,4 @@
-use domain::model::{AccessToken, IdToken, Scope};
+use domain::model::{AccessToken, IdToken, ResponseMode, Scope};
 use domain::error::domain as ed;
 
 #[derive(Serialize)]
@@ -65,11 +65,22 @@ pub enum AuthorizeRetKind {
         #[serde(skip_serializing)]
         _cause: ed::Error,
     },
+    /// The response signed as a JWT by JARM.
+    /// `_cause` is kept if the signed response is an error.
+    Jwt {
+        response: String,
+        #[serde(skip_serializing)]
+        _cause: Option<ed::Error>,
+    },
 }
 
 pub struct AuthorizeRet {
     pub redirect_uri: Option<String>,
     pub kind: AuthorizeRetKind,
+    /// The client which has been issued the code or tokens.
+    pub client_id: Option<String>,
+    /// How the result is returned to the redirect_uri.
+    pub response_mode: ResponseMode,
 }
 
 impl AuthorizeRet {
@@ -77,6 +88,8 @@ impl AuthorizeRet {
         AuthorizeRet {
             redirect_uri: None,
             kind: AuthorizeRetKind::RequireAcceptance { grant_id, scope },
+            client_id: None,
+            response_mode: ResponseMode::Query,
         }
     }
 
@@ -89,6 +102,36 @@ impl AuthorizeRet {
                 state,
                 _cause: error,
             },
+            client_id: None,
+            response_mode: ResponseMode::Query,
+        }
+    }
+
+    /// Returns the result which is returned by the response_mode.
+    pub fn response_mode(self, response_mode: &ResponseMode) -> Self {
+        AuthorizeRet {
+            response_mode: response_mode.clone(),
+            ..self
+        }
+    }
+
+    /// Returns the result which replaces the parameters with the signed response.
+    pub fn jwt(self, response: String) -> Self {
+        let AuthorizeRet {
+            redirect_uri,
+            kind,
+            client_id,
+            response_mode,
+        } = self;
+        let _cause = match kind {
+            AuthorizeRetKind::Error { _cause, .. } => Some(_cause),
+            _ => None,
+        };
+        AuthorizeRet {
+            redirect_uri,
+            kind: AuthorizeRetKind::Jwt { response, _cause },
+            client_id,
+            response_mode,
         }
     }
 
@@ -104,6 +147,7 @@ pub struct AuthorizeRetBuilder {
     access_token: Option<String>,
     expires_in: i64,
     id_token: Option<String>,
+    client_id: Option<String>,
     kind: u32,
 }
 
@@ -116,6 +160,7 @@ impl AuthorizeRetBuilder {
             access_token: None,
             expires_in: -1i64,
             id_token: None,
+            client_id: None,
             kind: 0b000,
         }
     }
@@ -154,6 +199,13 @@ impl AuthorizeRetBuilder {
         }
     }
 
+    pub fn client_id(self, client_id: &String) -> Self {
+        AuthorizeRetBuilder {
+            client_id: Some(client_id.clone()),
+            ..self
+        }
+    }
+
     pub fn build(self) -> Option<AuthorizeRet> {
         let kind = match self.kind {
             0b001 => AuthorizeRetKind::Code {
@@ -202,9 +254,17 @@ impl AuthorizeRetBuilder {
             _ => return None,
         };
 
+        // Tokens are returned in the fragment by default
+        let response_mode = if self.kind == 0b001 {
+            ResponseMode::Query
+        } else {
+            ResponseMode::Fragment
+        };
         Some(AuthorizeRet {
             redirect_uri: Some(self.redirect_uri),
             kind,
+            client_id: self.client_id,
+            response_mode,
         })
     }
 }
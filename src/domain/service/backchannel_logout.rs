@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+
+use chrono::prelude::*;
+
+use config::AppConfig;
+use domain::error::domain as ed;
+use domain::model::{Client, GrantStatus, LogoutDelivery, LogoutDeliveryStatus,
+                    LogoutTokenClaims, SigningKey};
+use domain::repository::{ClientRepository, ClientRepositoryComponent, GrantRepository,
+                         GrantRepositoryComponent, LogoutDeliveryRepository,
+                         LogoutDeliveryRepositoryComponent};
+use domain::service::{KeyService, KeyServiceComponent};
+
+/// `LogoutTokenSender` delivers a logout token to the `backchannel_logout_uri` of a client.
+pub trait LogoutTokenSender {
+    fn send(&self, backchannel_logout_uri: &str, logout_token: &str) -> Result<(), ed::Error>;
+}
+
+pub trait LogoutTokenSenderComponent {
+    type LogoutTokenSender: LogoutTokenSender;
+    fn logout_token_sender(&self) -> &Self::LogoutTokenSender;
+}
+
+/// `BackchannelLogoutService` provides functions for OpenID Connect Back-Channel Logout.
+pub trait BackchannelLogoutService
+    : ClientRepositoryComponent
+    + GrantRepositoryComponent
+    + LogoutDeliveryRepositoryComponent
+    + LogoutTokenSenderComponent
+    + KeyServiceComponent {
+    /// Queues logout tokens for the clients which have been issued tokens in the session.
+    /// The tokens are sent later by `deliver_pending_logout_tokens`
+    /// so that the logout does not wait for the clients.
+    fn notify_logout(
+        &self,
+        sid: &String,
+        end_user_id: &String,
+    ) -> Result<Vec<LogoutDelivery>, ed::Error> {
+        let client_repo = self.client_repository();
+        let grant_repo = self.grant_repository();
+        let delivery_repo = self.logout_delivery_repository();
+        let key_service = self.key_service();
+
+        let clients = find_clients_in_session(sid, end_user_id, client_repo, grant_repo)?;
+        if clients.is_empty() {
+            return Ok(Vec::new());
+        }
+        let signing_key = key_service.active_signing_key()?;
+
+        let mut deliveries = Vec::new();
+        for client in clients.iter() {
+            let uri = match client.metadata.backchannel_logout_uri {
+                Some(ref uri) => uri,
+                None => continue,
+            };
+            let delivery =
+                queue_logout_token(&client.id, uri, sid, end_user_id, &signing_key, delivery_repo)?;
+            deliveries.push(delivery);
+        }
+        Ok(deliveries)
+    }
+
+    /// Sends the pending logout tokens which are due, and returns how many were delivered.
+    /// A failed delivery is retried with exponential backoff up to
+    /// `BACKCHANNEL_LOGOUT_MAX_ATTEMPTS` times.
+    fn deliver_pending_logout_tokens(&self) -> Result<usize, ed::Error> {
+        let delivery_repo = self.logout_delivery_repository();
+        let sender = self.logout_token_sender();
+        let max_attempts = AppConfig::backchannel_logout_max_attempts();
+        let retry_interval_sec = AppConfig::backchannel_logout_retry_interval_sec();
+
+        let now = Utc::now();
+        let mut delivered = 0;
+        for mut delivery in delivery_repo.find_by_status(&LogoutDeliveryStatus::Pending)? {
+            if !delivery.can_retry(max_attempts, &now) {
+                continue;
+            }
+            match sender.send(&delivery.backchannel_logout_uri, &delivery.logout_token) {
+                Ok(_) => {
+                    delivery.succeed();
+                    delivered += 1;
+                }
+                Err(e) => delivery.fail(&e.to_string(), max_attempts, retry_interval_sec),
+            }
+            delivery_repo.update(&delivery)?;
+        }
+        Ok(delivered)
+    }
+}
+
+pub trait BackchannelLogoutServiceComponent {
+    type BackchannelLogoutService: BackchannelLogoutService;
+    fn backchannel_logout_service(&self) -> &Self::BackchannelLogoutService;
+}
+
+// Implement
+impl<
+    T: ClientRepositoryComponent
+        + GrantRepositoryComponent
+        + LogoutDeliveryRepositoryComponent
+        + LogoutTokenSenderComponent
+        + KeyServiceComponent,
+> BackchannelLogoutService for T
+{
+}
+
+// Private functions
+/// Returns the clients which have been issued tokens in the session.
+fn find_clients_in_session(
+    sid: &String,
+    end_user_id: &String,
+    client_repo: &ClientRepository,
+    grant_repo: &GrantRepository,
+) -> Result<Vec<Client>, ed::Error> {
+    // Grants which are not activated have issued nothing yet
+    let client_ids: HashSet<String> = grant_repo
+        .find_by_sid(sid)?
+        .into_iter()
+        .filter(|g| &g.end_user_id == end_user_id && g.status != GrantStatus::Created)
+        .map(|g| g.client_id)
+        .collect();
+    let mut clients = Vec::new();
+    for client_id in client_ids.iter() {
+        if let Some(client) = client_repo.find_by_id(client_id)? {
+            clients.push(client);
+        }
+    }
+    Ok(clients)
+}
+
+/// Publishes a logout token for the client and records it as a pending delivery.
+fn queue_logout_token(
+    client_id: &String,
+    backchannel_logout_uri: &String,
+    sid: &String,
+    end_user_id: &String,
+    signing_key: &SigningKey,
+    delivery_repo: &LogoutDeliveryRepository,
+) -> Result<LogoutDelivery, ed::Error> {
+    let claims =
+        LogoutTokenClaims::new(&AppConfig::issuer(), end_user_id, client_id, &Some(sid.clone()));
+    let logout_token = claims.publish(signing_key)?;
+    let delivery = LogoutDelivery::new(
+        client_id,
+        end_user_id,
+        &claims.sid,
+        backchannel_logout_uri,
+        &claims.jti,
+        &logout_token,
+    );
+    delivery_repo.add(&delivery)?;
+    Ok(delivery)
+}
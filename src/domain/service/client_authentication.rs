@@ -0,0 +1,370 @@
+use chrono::prelude::*;
+
+use config::AppConfig;
+use domain::error::domain as ed;
+use self::ed::ErrorKind as ek;
+use domain::model::{Client, ClientAssertion, ClientCertificate, ConsumedAssertion,
+                    CLIENT_ASSERTION_TYPE_JWT_BEARER, TOKEN_ENDPOINT_AUTH_METHOD_BASIC,
+                    TOKEN_ENDPOINT_AUTH_METHOD_NONE, TOKEN_ENDPOINT_AUTH_METHOD_POST,
+                    TOKEN_ENDPOINT_AUTH_METHOD_PRIVATE_KEY_JWT,
+                    TOKEN_ENDPOINT_AUTH_METHOD_SECRET_JWT,
+                    TOKEN_ENDPOINT_AUTH_METHOD_SELF_SIGNED_TLS_CLIENT_AUTH,
+                    TOKEN_ENDPOINT_AUTH_METHOD_TLS_CLIENT_AUTH};
+use domain::repository::{ClientRepository, ClientRepositoryComponent,
+                         ConsumedAssertionRepository, ConsumedAssertionRepositoryComponent};
+
+/// `ClientAuthentication` is the type represents the credentials presented by a client.
+#[derive(Debug, Clone, Default)]
+pub struct ClientAuthentication {
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    /// True if the client_id and the client_secret are sent by HTTP Basic authentication.
+    pub basic: bool,
+    /// True if the form also contains client_secret while HTTP Basic authentication is used.
+    pub basic_with_form_secret: bool,
+    pub client_assertion_type: Option<String>,
+    pub client_assertion: Option<String>,
+    /// The certificate of the mutual TLS connection (RFC 8705).
+    pub client_certificate: Option<ClientCertificate>,
+}
+
+/// `ClientAuthenticationService` authenticates clients at the token endpoint
+/// and the other endpoints which accept the same credentials.
+pub trait ClientAuthenticationService
+    : ClientRepositoryComponent + ConsumedAssertionRepositoryComponent {
+    /// Authenticates the client by `token_endpoint_auth_method` of the client.
+    fn authenticate_client(&self, auth: &ClientAuthentication) -> Result<Client, ed::Error> {
+        // Client assertions are addressed to either the issuer or the token endpoint.
+        let audiences = vec![
+            AppConfig::issuer(),
+            format!("{}/oidc/tokens", AppConfig::endpoint().trim_right_matches('/')),
+        ];
+        process_client_authentication(
+            auth,
+            &audiences,
+            AppConfig::client_assertion_max_age_sec(),
+            self.client_repository(),
+            self.consumed_assertion_repository(),
+        )
+    }
+}
+
+pub trait ClientAuthenticationServiceComponent {
+    type ClientAuthenticationService: ClientAuthenticationService;
+    fn client_authentication_service(&self) -> &Self::ClientAuthenticationService;
+}
+
+// Implement
+impl<T: ClientRepositoryComponent + ConsumedAssertionRepositoryComponent>
+    ClientAuthenticationService for T
+{
+}
+
+// Private functions
+fn process_client_authentication(
+    auth: &ClientAuthentication,
+    audiences: &Vec<String>,
+    assertion_max_age_sec: i64,
+    client_repo: &ClientRepository,
+    consumed_repo: &ConsumedAssertionRepository,
+) -> Result<Client, ed::Error> {
+    // The client MUST NOT use more than one authentication method in each request.
+    let multiple = auth.basic_with_form_secret
+        || (auth.client_secret.is_some() && auth.client_assertion.is_some());
+    if multiple {
+        return Err(ek::InvalidRequest(
+            "Multiple client authentication methods are used.".to_string(),
+        ).into());
+    }
+
+    // client_id may be omitted when the assertion identifies the client.
+    let client_id = match auth.client_assertion {
+        Some(ref assertion) => {
+            let sub = ClientAssertion::peek_subject(assertion)?;
+            if auth.client_id.as_ref().map_or(false, |id| id != &sub) {
+                return Err(ek::UnauthorizedClient(
+                    "client_id does not match the client assertion.".to_string(),
+                ).into());
+            }
+            sub
+        }
+        None => auth.client_id
+            .clone()
+            .ok_or(ek::UnauthorizedClient("Invalid credentials.".to_string()))?,
+    };
+
+    let client = client_repo.find_by_id(&client_id).and_then(|c| {
+        c.ok_or(ek::UnauthorizedClient(format!("Client not found. ID => {}", client_id)).into())
+    })?;
+
+    let authenticated = match client.token_endpoint_auth_method() {
+        TOKEN_ENDPOINT_AUTH_METHOD_BASIC => auth.basic && is_authenticated_by_secret(&client, auth),
+        TOKEN_ENDPOINT_AUTH_METHOD_POST => !auth.basic && is_authenticated_by_secret(&client, auth),
+        TOKEN_ENDPOINT_AUTH_METHOD_SECRET_JWT | TOKEN_ENDPOINT_AUTH_METHOD_PRIVATE_KEY_JWT => {
+            verify_client_assertion(
+                &client,
+                auth,
+                audiences,
+                assertion_max_age_sec,
+                consumed_repo,
+            )?;
+            true
+        }
+        TOKEN_ENDPOINT_AUTH_METHOD_NONE => has_no_credentials(auth),
+        TOKEN_ENDPOINT_AUTH_METHOD_TLS_CLIENT_AUTH => {
+            has_no_credentials(auth) && auth.client_certificate
+                .as_ref()
+                .map_or(false, |cert| cert.is_issued_to(&client.metadata))
+        }
+        TOKEN_ENDPOINT_AUTH_METHOD_SELF_SIGNED_TLS_CLIENT_AUTH => {
+            has_no_credentials(auth) && match (&auth.client_certificate, &client.metadata.jwks) {
+                (&Some(ref cert), &Some(ref jwks)) => cert.has_key_in(jwks),
+                _ => false,
+            }
+        }
+        _ => false,
+    };
+    if !authenticated {
+        return Err(
+            ek::UnauthorizedClient(format!("Client not authorized. ID => {}", client_id)).into(),
+        );
+    }
+    Ok(client)
+}
+
+/// The certificate is not counted since it is also used to bind tokens.
+fn has_no_credentials(auth: &ClientAuthentication) -> bool {
+    !auth.basic && auth.client_secret.is_none() && auth.client_assertion.is_none()
+}
+
+fn is_authenticated_by_secret(client: &Client, auth: &ClientAuthentication) -> bool {
+    auth.client_secret
+        .as_ref()
+        .map_or(false, |secret| client.is_authenticated_by_secret(secret))
+}
+
+/// Verifies the assertion of `client_secret_jwt` or `private_key_jwt` (RFC 7523 section 3).
+/// The assertion expiring later than `max_age_sec` is rejected,
+/// because its `jti` is remembered until it expires.
+fn verify_client_assertion(
+    client: &Client,
+    auth: &ClientAuthentication,
+    audiences: &Vec<String>,
+    max_age_sec: i64,
+    consumed_repo: &ConsumedAssertionRepository,
+) -> Result<(), ed::Error> {
+    let assertion_type_valid = auth.client_assertion_type
+        .as_ref()
+        .map_or(false, |t| t == CLIENT_ASSERTION_TYPE_JWT_BEARER);
+    if !assertion_type_valid {
+        return Err(ek::UnauthorizedClient("Unsupported client_assertion_type.".to_string()).into());
+    }
+    let token = auth.client_assertion
+        .as_ref()
+        .ok_or(ek::UnauthorizedClient("client_assertion is required.".to_string()))?;
+
+    let assertion = if client.token_endpoint_auth_method() == TOKEN_ENDPOINT_AUTH_METHOD_SECRET_JWT
+    {
+        ClientAssertion::decode_with_secret(token, &client.id, &client.client_secret)?
+    } else {
+        let jwks = client
+            .metadata
+            .jwks
+            .as_ref()
+            .ok_or(ek::UnauthorizedClient("The client has no jwks.".to_string()))?;
+        ClientAssertion::decode_with_jwks(token, &client.id, jwks)?
+    };
+
+    if !assertion.has_audience(audiences) {
+        return Err(ek::UnauthorizedClient(
+            "The client assertion is not addressed to us.".to_string(),
+        ).into());
+    }
+    if assertion.exp - Utc::now().timestamp() > max_age_sec {
+        return Err(ek::UnauthorizedClient(
+            "The client assertion expires too late.".to_string(),
+        ).into());
+    }
+
+    // Replay protection
+    let consumed = ConsumedAssertion::new(&client.id, &assertion.jti, assertion.exp);
+    if !consumed_repo.add_if_absent(&consumed)? {
+        return Err(ek::UnauthorizedClient(format!(
+            "The client assertion has been already used. jti => {}",
+            assertion.jti
+        )).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, Algorithm, Header};
+    use serde_json::Value;
+    use std::cell::RefCell;
+
+    use domain::model::{test_client, test_jwks, ClientMetadata, OTHER_PRIVATE_KEY,
+                        TEST_PRIVATE_KEY};
+
+    const AUDIENCE: &str = "https://op.example.com/oidc/tokens";
+
+    struct ClientRepositoryStub {
+        clients: Vec<Client>,
+    }
+
+    impl ClientRepository for ClientRepositoryStub {
+        fn find_by_id(&self, id: &String) -> Result<Option<Client>, ed::Error> {
+            Ok(self.clients.iter().find(|c| &c.id == id).cloned())
+        }
+        fn find_by_name(&self, name: &String) -> Result<Option<Client>, ed::Error> {
+            Ok(self.clients.iter().find(|c| &c.name == name).cloned())
+        }
+        fn find_all(&self) -> Result<Vec<Client>, ed::Error> {
+            Ok(self.clients.clone())
+        }
+        fn add(&self, _model: &Client) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn update(&self, _model: &Client) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn remove(&self, _model: Client) -> Result<(), ed::Error> {
+            Ok(())
+        }
+    }
+
+    struct ConsumedAssertionRepositoryStub {
+        consumed: RefCell<Vec<ConsumedAssertion>>,
+    }
+
+    impl ConsumedAssertionRepository for ConsumedAssertionRepositoryStub {
+        fn add_if_absent(&self, model: &ConsumedAssertion) -> Result<bool, ed::Error> {
+            let mut consumed = self.consumed.borrow_mut();
+            if consumed
+                .iter()
+                .any(|c| c.issuer == model.issuer && c.jti == model.jti)
+            {
+                return Ok(false);
+            }
+            consumed.push(model.clone());
+            Ok(true)
+        }
+    }
+
+    fn client(id: &str, token_endpoint_auth_method: &str) -> Client {
+        let metadata = ClientMetadata {
+            token_endpoint_auth_method: Some(token_endpoint_auth_method.to_string()),
+            jwks: Some(test_jwks()),
+            ..ClientMetadata::default()
+        };
+        test_client(id, metadata)
+    }
+
+    fn client_repo() -> ClientRepositoryStub {
+        ClientRepositoryStub {
+            clients: vec![
+                client("basic", TOKEN_ENDPOINT_AUTH_METHOD_BASIC),
+                client("post", TOKEN_ENDPOINT_AUTH_METHOD_POST),
+                client("jwt", TOKEN_ENDPOINT_AUTH_METHOD_PRIVATE_KEY_JWT),
+            ],
+        }
+    }
+
+    fn consumed_repo() -> ConsumedAssertionRepositoryStub {
+        ConsumedAssertionRepositoryStub {
+            consumed: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn assertion(jti: &str, aud: &str, expires_in: i64, key: &[u8]) -> String {
+        let claims = ClientAssertion {
+            iss: "jwt".to_string(),
+            sub: "jwt".to_string(),
+            aud: Value::String(aud.to_string()),
+            exp: Utc::now().timestamp() + expires_in,
+            jti: jti.to_string(),
+            iat: None,
+        };
+        encode(&Header::new(Algorithm::RS256), &claims, key).unwrap()
+    }
+
+    fn jwt_auth(assertion: String) -> ClientAuthentication {
+        ClientAuthentication {
+            client_assertion_type: Some(CLIENT_ASSERTION_TYPE_JWT_BEARER.to_string()),
+            client_assertion: Some(assertion),
+            ..ClientAuthentication::default()
+        }
+    }
+
+    fn authenticate(
+        auth: &ClientAuthentication,
+        client_repo: &ClientRepositoryStub,
+        consumed_repo: &ConsumedAssertionRepositoryStub,
+    ) -> Result<Client, ed::Error> {
+        let audiences = vec![AUDIENCE.to_string()];
+        process_client_authentication(auth, &audiences, 300, client_repo, consumed_repo)
+    }
+
+    #[test]
+    fn should_authenticate_by_private_key_jwt() {
+        let client_repo = client_repo();
+        let consumed_repo = consumed_repo();
+
+        let auth = jwt_auth(assertion("jti", AUDIENCE, 60, TEST_PRIVATE_KEY));
+        let client = authenticate(&auth, &client_repo, &consumed_repo).unwrap();
+        assert_eq!(client.id, "jwt");
+
+        // Replayed
+        assert!(authenticate(&auth, &client_repo, &consumed_repo).is_err());
+
+        // Signed by another key
+        let auth = jwt_auth(assertion("other_key", AUDIENCE, 60, OTHER_PRIVATE_KEY));
+        assert!(authenticate(&auth, &client_repo, &consumed_repo).is_err());
+
+        // Addressed to another server
+        let aud = "https://attacker.example.com";
+        let auth = jwt_auth(assertion("other_aud", aud, 60, TEST_PRIVATE_KEY));
+        assert!(authenticate(&auth, &client_repo, &consumed_repo).is_err());
+
+        // Expires too late
+        let auth = jwt_auth(assertion("long_lived", AUDIENCE, 3600, TEST_PRIVATE_KEY));
+        assert!(authenticate(&auth, &client_repo, &consumed_repo).is_err());
+    }
+
+    #[test]
+    fn should_reject_other_methods() {
+        let client_repo = client_repo();
+        let consumed_repo = consumed_repo();
+        let secret = |id: &str| {
+            let client = client_repo.find_by_id(&id.to_string()).unwrap().unwrap();
+            client.client_secret
+        };
+        let secret_auth = |id: &str, basic: bool| ClientAuthentication {
+            client_id: Some(id.to_string()),
+            client_secret: Some(secret(id)),
+            basic,
+            ..ClientAuthentication::default()
+        };
+
+        assert!(authenticate(&secret_auth("basic", true), &client_repo, &consumed_repo).is_ok());
+        assert!(authenticate(&secret_auth("basic", false), &client_repo, &consumed_repo).is_err());
+        assert!(authenticate(&secret_auth("post", false), &client_repo, &consumed_repo).is_ok());
+        assert!(authenticate(&secret_auth("post", true), &client_repo, &consumed_repo).is_err());
+        assert!(authenticate(&secret_auth("jwt", false), &client_repo, &consumed_repo).is_err());
+
+        let auth = ClientAuthentication {
+            basic_with_form_secret: true,
+            ..secret_auth("basic", true)
+        };
+        let e = authenticate(&auth, &client_repo, &consumed_repo).unwrap_err();
+        assert_eq!(e.description(), "invalid_request");
+
+        let auth = ClientAuthentication {
+            client_secret: Some(secret("jwt")),
+            ..jwt_auth(assertion("jti", AUDIENCE, 60, TEST_PRIVATE_KEY))
+        };
+        let e = authenticate(&auth, &client_repo, &consumed_repo).unwrap_err();
+        assert_eq!(e.description(), "invalid_request");
+    }
+}
@@ -1,26 +1,24 @@
 use domain::error::domain as ed;
 use self::ed::ErrorKind as ek;
-use domain::model::{AccessToken, Resource};
-use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent, ClientRepository,
-                         ClientRepositoryComponent, ResourceRepository,
-                         ResourceRepositoryComponent};
+use domain::model::{AccessToken, Client, Confirmation, Resource, TOKEN_ENDPOINT_AUTH_METHOD_NONE};
+use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent,
+                         ResourceRepository, ResourceRepositoryComponent};
 use domain::service::TokensRet;
 
 pub struct ClientCredentialsCmd {
-    pub client_id: Option<String>,
-    pub client_secret: Option<String>,
     pub scope: Option<String>,
+    /// The confirmation of the DPoP proof key which the tokens are bound to.
+    pub cnf: Option<Confirmation>,
 }
 
 pub trait ClientCredentialsService
-    : AccessTokenRepositoryComponent + ClientRepositoryComponent + ResourceRepositoryComponent
-    {
-    fn execute_client_credentials(&self, cmd: &ClientCredentialsCmd) -> TokensRet {
+    : AccessTokenRepositoryComponent
+    + ResourceRepositoryComponent {
+    fn execute_client_credentials(&self, client: &Client, cmd: &ClientCredentialsCmd) -> TokensRet {
         let access_token_repo = self.access_token_repository();
-        let client_repo = self.client_repository();
         let resource_repo = self.resource_repository();
 
-        let ret = process_client_credentials(cmd, access_token_repo, client_repo, resource_repo);
+        let ret = process_client_credentials(cmd, client, access_token_repo, resource_repo);
 
         match ret {
             Ok(r) => r,
@@ -35,7 +33,8 @@ pub trait ClientCredentialsServiceComponent {
 }
 
 impl<
-    T: AccessTokenRepositoryComponent + ClientRepositoryComponent + ResourceRepositoryComponent,
+    T: AccessTokenRepositoryComponent
+        + ResourceRepositoryComponent,
 > ClientCredentialsService for T
 {
 }
@@ -43,24 +42,16 @@ impl<
 // Private functions
 fn process_client_credentials(
     cmd: &ClientCredentialsCmd,
+    client: &Client,
     access_token_repo: &AccessTokenRepository,
-    client_repo: &ClientRepository,
     resource_repo: &ResourceRepository,
 ) -> Result<TokensRet, ed::Error> {
-    // Authorize the client
-    let (client_id, client_secret) = cmd.client_id
-        .as_ref()
-        .and_then(|id| cmd.client_secret.as_ref().map(|secret| (id, secret)))
-        .ok_or(ek::UnauthorizedClient("Invalid credentials.".to_string()))?;
-
-    let client = client_repo.find_by_id(&client_id).and_then(|c| {
-        c.ok_or(ek::UnauthorizedClient(format!("Client not found. ID => {}", client_id)).into())
-    })?;
-
-    if !client.is_authenticated_by_secret(&client_secret) {
-        return Err(
-            ek::UnauthorizedClient(format!("Client not authorized. ID => {}", client_id)).into(),
-        );
+    // Only confidential clients are allowed to use this grant.
+    if client.token_endpoint_auth_method() == TOKEN_ENDPOINT_AUTH_METHOD_NONE {
+        return Err(ek::UnauthorizedClient(format!(
+            "Public clients cannot use client_credentials. ID => {}",
+            client.id
+        )).into());
     }
 
     // Resource
@@ -79,8 +70,9 @@ fn process_client_credentials(
         .map_or(Vec::new(), |s| validate_scope_str(&s, &resource));
 
     // Access token
-    let access_token = AccessToken::builder(&client_id, &resource.id)
+    let access_token = AccessToken::builder(&client.id, &resource.id)
         .scope(&scope)
+        .cnf(&client.confirmation(&cmd.cnf)?)
         .build();
     access_token_repo.add(&access_token)?;
 
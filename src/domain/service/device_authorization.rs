@@ -0,0 +1,559 @@
+use chrono::prelude::*;
+
+use config::AppConfig;
+use domain::error::domain as ed;
+use self::ed::ErrorKind as ek;
+use domain::model::{format_user_code, AccessToken, AuthenticationLevel, AuthenticationMethod,
+                    Client, Confirmation, DeviceAuthorization, DeviceAuthorizationStatus,
+                    GrantType, IdTokenClaims, RefreshToken, Scope, StandardClaims,
+                    TOKEN_ENDPOINT_AUTH_METHOD_NONE};
+use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent, ClientRepository,
+                         ClientRepositoryComponent, DeviceAuthorizationRepository,
+                         DeviceAuthorizationRepositoryComponent, EndUserRepository,
+                         EndUserRepositoryComponent, IdTokenRepository,
+                         IdTokenRepositoryComponent, RefreshTokenRepository,
+                         RefreshTokenRepositoryComponent, ResourceRepository,
+                         ResourceRepositoryComponent, UserCodeAttemptRepository,
+                         UserCodeAttemptRepositoryComponent};
+use domain::service::{accept_client_by_end_user, ClientAuthentication,
+                      ClientAuthenticationService, ClientAuthenticationServiceComponent,
+                      KeyService, KeyServiceComponent, TokensRet};
+
+pub struct DeviceAuthorizationCmd {
+    pub client_auth: ClientAuthentication,
+    pub scope: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeviceAuthorizationRet {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: String,
+    pub expires_in: i64,
+    pub interval: i64,
+}
+
+pub struct ReviewDeviceCmd {
+    pub end_user_id: Option<String>,
+    pub user_code: String,
+}
+
+/// The client and the scope shown to the end user before the acceptance.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviewDeviceRet {
+    pub user_code: String,
+    pub client_id: String,
+    pub client_name: String,
+    pub scope: Vec<Scope>,
+}
+
+pub struct AcceptDeviceCmd {
+    pub end_user_id: Option<String>,
+    pub action: String,
+    pub user_code: String,
+    pub sid: Option<String>,
+    pub auth_time: Option<i64>,
+    pub amr: Option<String>,
+}
+
+pub struct DeviceCodeCmd {
+    pub device_code: Option<String>,
+    /// The confirmation of the DPoP proof key which the tokens are bound to.
+    pub cnf: Option<Confirmation>,
+}
+
+/// `DeviceAuthorizationService` provides functions for Device Authorization Grant (RFC 8628).
+pub trait DeviceAuthorizationService
+    : AccessTokenRepositoryComponent
+    + ClientRepositoryComponent
+    + DeviceAuthorizationRepositoryComponent
+    + EndUserRepositoryComponent
+    + IdTokenRepositoryComponent
+    + RefreshTokenRepositoryComponent
+    + ResourceRepositoryComponent
+    + UserCodeAttemptRepositoryComponent
+    + ClientAuthenticationServiceComponent
+    + KeyServiceComponent {
+    /// Issues `device_code` for the device and `user_code` for the end user.
+    fn authorize_device(
+        &self,
+        cmd: &DeviceAuthorizationCmd,
+    ) -> Result<DeviceAuthorizationRet, ed::Error> {
+        let device_repo = self.device_authorization_repository();
+        let resource_repo = self.resource_repository();
+
+        let client = self.client_authentication_service()
+            .authenticate_client(&cmd.client_auth)?;
+        check_device_code_grant(&client)?;
+        let resource = resource_repo
+            .find_by_id(&client.resource_id)
+            .and_then(|v| {
+                v.ok_or(
+                    ek::InvalidRequest(format!("Resource not found. ID => {}", client.resource_id))
+                        .into(),
+                )
+            })?;
+
+        let requested: Vec<String> = cmd.scope
+            .as_ref()
+            .map_or(Vec::new(), |s| s.split(" ").map(|s| s.to_string()).collect());
+        let device = DeviceAuthorization {
+            openid: requested.iter().any(|s| s == "openid"),
+            ..DeviceAuthorization::new(
+                &client.id,
+                &resource.id,
+                &resource.filter_scope(&requested),
+            )
+        };
+        device_repo.add(&device)?;
+
+        let verification_uri = AppConfig::device_verification_uri();
+        Ok(DeviceAuthorizationRet {
+            verification_uri_complete: format!(
+                "{}?user_code={}",
+                verification_uri, device.user_code
+            ),
+            verification_uri,
+            device_code: device.device_code,
+            user_code: device.user_code,
+            expires_in: device.expires_in,
+            interval: device.interval,
+        })
+    }
+
+    /// Returns the request of the device which the end user is going to accept.
+    fn review_device(&self, cmd: &ReviewDeviceCmd) -> Result<ReviewDeviceRet, ed::Error> {
+        let client_repo = self.client_repository();
+        let device_repo = self.device_authorization_repository();
+        let resource_repo = self.resource_repository();
+
+        let end_user_id = match cmd.end_user_id {
+            Some(ref id) => id,
+            None => return Err(ek::RequireLogin("Login required.".to_string()).into()),
+        };
+        let device = find_pending_device(
+            end_user_id,
+            &cmd.user_code,
+            AppConfig::device_user_code_max_attempts(),
+            AppConfig::device_code_max_age_sec(),
+            device_repo,
+            self.user_code_attempt_repository(),
+        )?;
+        let client = client_repo.find_by_id(&device.client_id).and_then(|v| {
+            v.ok_or(
+                ek::EntityNotFound(format!("Client not found. ID => {}", device.client_id)).into(),
+            )
+        })?;
+        let resource = resource_repo.find_by_id(&device.resource_id).and_then(|v| {
+            v.ok_or(ek::EntityNotFound(format!("ID => {}", device.resource_id)).into())
+        })?;
+
+        Ok(ReviewDeviceRet {
+            user_code: device.user_code,
+            client_id: client.id,
+            client_name: client.name,
+            scope: resource.convert_scope(&device.scope),
+        })
+    }
+
+    /// Approves or denies the request of the device in the same way as `accept_client`.
+    fn accept_device(&self, cmd: &AcceptDeviceCmd) -> Result<(), ed::Error> {
+        let device_repo = self.device_authorization_repository();
+        let end_user_repo = self.end_user_repository();
+
+        let end_user_id = match cmd.end_user_id {
+            Some(ref id) => id,
+            None => return Err(ek::RequireLogin("Login required.".to_string()).into()),
+        };
+        let mut device = find_pending_device(
+            end_user_id,
+            &cmd.user_code,
+            AppConfig::device_user_code_max_attempts(),
+            AppConfig::device_code_max_age_sec(),
+            device_repo,
+            self.user_code_attempt_repository(),
+        )?;
+        let end_user = match accept_client_by_end_user(
+            &cmd.end_user_id,
+            &cmd.action,
+            &device.client_id,
+            &device.scope,
+            end_user_repo,
+        ) {
+            Ok(end_user) => end_user,
+            Err(e) => {
+                // The device is told the rejection at the next polling.
+                if let ek::AccessDenied(_) = *e.kind() {
+                    let denied = DeviceAuthorizationStatus::Denied;
+                    change_pending_device(&device, &denied, device_repo)?;
+                }
+                return Err(e);
+            }
+        };
+
+        let methods = AuthenticationMethod::from_str(&cmd.amr);
+        change_pending_device(&device, &DeviceAuthorizationStatus::Approved, device_repo)?;
+        device.status = DeviceAuthorizationStatus::Approved;
+        device.end_user_id = Some(end_user.id.clone());
+        device.sid = cmd.sid.clone();
+        device.auth_time = cmd.auth_time;
+        device.acr = Some(AuthenticationLevel::from_methods(&methods).to_str().to_string());
+        device.amr = Some(AuthenticationMethod::to_amr(&methods));
+        device_repo.update(&device)
+    }
+
+    /// Returns tokens if the end user has approved the request of the device.
+    fn execute_device_code(&self, client: &Client, cmd: &DeviceCodeCmd) -> TokensRet {
+        let ret = process_device_code(
+            cmd,
+            client,
+            self.access_token_repository(),
+            self.device_authorization_repository(),
+            self.end_user_repository(),
+            self.id_token_repository(),
+            self.refresh_token_repository(),
+            self.key_service(),
+        );
+
+        match ret {
+            Ok(r) => r,
+            Err(e) => TokensRet::error(e),
+        }
+    }
+}
+
+pub trait DeviceAuthorizationServiceComponent {
+    type DeviceAuthorizationService: DeviceAuthorizationService;
+    fn device_authorization_service(&self) -> &Self::DeviceAuthorizationService;
+}
+
+// Implement
+impl<
+    T: AccessTokenRepositoryComponent
+        + ClientRepositoryComponent
+        + DeviceAuthorizationRepositoryComponent
+        + EndUserRepositoryComponent
+        + IdTokenRepositoryComponent
+        + RefreshTokenRepositoryComponent
+        + ResourceRepositoryComponent
+        + UserCodeAttemptRepositoryComponent
+        + ClientAuthenticationServiceComponent
+        + KeyServiceComponent,
+> DeviceAuthorizationService for T
+{
+}
+
+// Private functions
+/// The client MUST have registered the device code grant.
+fn check_device_code_grant(client: &Client) -> Result<(), ed::Error> {
+    if !client.allows_grant_type(&GrantType::DeviceCode) {
+        return Err(ek::UnauthorizedClient(format!(
+            "The client is not allowed to use {}.",
+            GrantType::DeviceCode.to_str()
+        )).into());
+    }
+    Ok(())
+}
+
+/// Finds the request of the device waiting for the end user by the typed `user_code`.
+/// The end user who has typed `max_attempts` wrong codes is rejected for `attempts_expire_in`
+/// seconds, because the short `user_code` could be guessed otherwise.
+fn find_pending_device(
+    end_user_id: &String,
+    user_code: &String,
+    max_attempts: i64,
+    attempts_expire_in: i64,
+    device_repo: &DeviceAuthorizationRepository,
+    attempt_repo: &UserCodeAttemptRepository,
+) -> Result<DeviceAuthorization, ed::Error> {
+    if attempt_repo.count(end_user_id)? >= max_attempts {
+        return Err(ek::SlowDown("Too many wrong user_codes. Try again later.".to_string()).into());
+    }
+    let device = format_user_code(user_code)
+        .map_or(Ok(None), |c| device_repo.find_by_user_code(&c))?;
+    let device = match device {
+        Some(d) => d,
+        None => {
+            attempt_repo.add(end_user_id, attempts_expire_in)?;
+            return Err(
+                ek::InvalidRequest(format!("Device not found. Code => {}", user_code)).into(),
+            );
+        }
+    };
+    if !device.is_valid() {
+        return Err(ek::InvalidRequest("The user_code has expired.".to_string()).into());
+    }
+    if device.status != DeviceAuthorizationStatus::Pending {
+        return Err(ek::InvalidRequest("The user_code has been already used.".to_string()).into());
+    }
+    Ok(device)
+}
+
+/// Changes the status of the pending request. Only one of concurrent acceptances succeeds.
+fn change_pending_device(
+    device: &DeviceAuthorization,
+    status: &DeviceAuthorizationStatus,
+    device_repo: &DeviceAuthorizationRepository,
+) -> Result<(), ed::Error> {
+    device_repo
+        .find_by_id_and_change_status(&device.id, &DeviceAuthorizationStatus::Pending, status)?
+        .map(|_| ())
+        .ok_or(ek::InvalidRequest("The user_code has been already used.".to_string()).into())
+}
+
+/// Check the device code and return tokens.
+fn process_device_code(
+    cmd: &DeviceCodeCmd,
+    client: &Client,
+    access_token_repo: &AccessTokenRepository,
+    device_repo: &DeviceAuthorizationRepository,
+    end_user_repo: &EndUserRepository,
+    id_token_repo: &IdTokenRepository,
+    refresh_token_repo: &RefreshTokenRepository,
+    key_service: &KeyService,
+) -> Result<TokensRet, ed::Error> {
+    // Fetch the request of the device
+    let mut device = match cmd.device_code {
+        Some(ref c) => device_repo.find_by_device_code(c).and_then(|v| {
+            v.ok_or(ek::InvalidGrant(format!("Device not found. Code => {}", c)).into())
+        })?,
+        None => return Err(ek::InvalidRequest("device_code is required.".to_string()).into()),
+    };
+
+    check_device_code_grant(client)?;
+
+    // The device code MUST be issued to the authenticated client.
+    if device.client_id != client.id {
+        return Err(ek::InvalidGrant(format!(
+            "The device code was not issued to the client. ID => {}",
+            client.id
+        )).into());
+    }
+
+    if !device.is_valid() {
+        return Err(ek::ExpiredToken("The device code has expired.".to_string()).into());
+    }
+
+    // The polling is recorded even if it is too quick.
+    let polled = device.poll(Utc::now());
+    device_repo.update_polling(&device)?;
+    polled?;
+
+    match device.status {
+        DeviceAuthorizationStatus::Approved => {}
+        DeviceAuthorizationStatus::Pending => {
+            return Err(ek::AuthorizationPending(
+                "The end user has not approved the device yet.".to_string(),
+            ).into())
+        }
+        DeviceAuthorizationStatus::Denied => {
+            return Err(ek::AccessDenied("The user rejected the request.".to_string()).into())
+        }
+        DeviceAuthorizationStatus::Issued => {
+            return Err(
+                ek::InvalidGrant("The device code has been already used.".to_string()).into(),
+            )
+        }
+    }
+    // Only one of concurrent polling requests gets the tokens.
+    device_repo
+        .find_by_id_and_change_status(
+            &device.id,
+            &DeviceAuthorizationStatus::Approved,
+            &DeviceAuthorizationStatus::Issued,
+        )?
+        .ok_or(ek::InvalidGrant("The device code has been already used.".to_string()))?;
+
+    // EndUser
+    let end_user_id = device.end_user_id.clone().unwrap_or_default();
+    let end_user = end_user_repo.find_by_id(&end_user_id).and_then(|v| {
+        v.ok_or(ek::InvalidGrant(format!("EndUser not found. ID => {}", end_user_id)).into())
+    })?;
+
+    // Access token
+    let cnf = client.confirmation(&cmd.cnf)?;
+    let access_token = AccessToken::builder(&device.client_id, &device.resource_id)
+        .end_user_id(&Some(end_user.id.clone()))
+        .scope(&device.scope)
+        .acr(&device.acr)
+        .amr(&device.amr)
+        .auth_time(&device.auth_time)
+        .cnf(&cnf)
+        .build();
+    access_token_repo.add(&access_token)?;
+
+    // Id token
+    let id_token = if device.openid {
+        let id_token = IdTokenClaims::from_end_user(
+            &AppConfig::issuer(),
+            &end_user,
+            &device.client_id,
+            &device.auth_time,
+        ).sid(&device.sid)
+            .acr(&device.acr)
+            .amr(&device.amr)
+            .standard_claims(StandardClaims::from_end_user(&end_user, &device.scope))
+            .publish(&key_service.active_signing_key()?)?;
+        id_token_repo.add(&id_token)?;
+        Some(id_token)
+    } else {
+        None
+    };
+
+    // Refresh token
+    // Refresh tokens of public clients are bound to the same keys as well.
+    let refresh_cnf = if client.token_endpoint_auth_method() == TOKEN_ENDPOINT_AUTH_METHOD_NONE {
+        cnf
+    } else {
+        None
+    };
+    let refresh_token = RefreshToken::new(
+        &access_token.client_id,
+        &access_token.id,
+        &id_token.as_ref().map(|v| v.id.clone()),
+    ).bind(&refresh_cnf);
+    refresh_token_repo.add(&refresh_token)?;
+
+    Ok(TokensRet::builder(&access_token)
+        .id_token(&id_token)
+        .refresh_token(&Some(refresh_token))
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    struct DeviceAuthorizationRepositoryStub {
+        devices: RefCell<Vec<DeviceAuthorization>>,
+    }
+
+    impl DeviceAuthorizationRepository for DeviceAuthorizationRepositoryStub {
+        fn find_by_device_code(
+            &self,
+            device_code: &String,
+        ) -> Result<Option<DeviceAuthorization>, ed::Error> {
+            let devices = self.devices.borrow();
+            Ok(devices.iter().find(|d| &d.device_code == device_code).cloned())
+        }
+        fn find_by_user_code(
+            &self,
+            user_code: &String,
+        ) -> Result<Option<DeviceAuthorization>, ed::Error> {
+            let devices = self.devices.borrow();
+            Ok(devices.iter().find(|d| &d.user_code == user_code).cloned())
+        }
+        fn find_by_id_and_change_status(
+            &self,
+            id: &String,
+            current: &DeviceAuthorizationStatus,
+            status: &DeviceAuthorizationStatus,
+        ) -> Result<Option<DeviceAuthorization>, ed::Error> {
+            let mut devices = self.devices.borrow_mut();
+            match devices
+                .iter_mut()
+                .find(|d| &d.id == id && &d.status == current)
+            {
+                Some(d) => {
+                    let before = d.clone();
+                    d.status = status.clone();
+                    Ok(Some(before))
+                }
+                None => Ok(None),
+            }
+        }
+        fn update_polling(&self, _model: &DeviceAuthorization) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn add(&self, model: &DeviceAuthorization) -> Result<(), ed::Error> {
+            self.devices.borrow_mut().push(model.clone());
+            Ok(())
+        }
+        fn update(&self, _model: &DeviceAuthorization) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn remove(&self, _model: DeviceAuthorization) -> Result<(), ed::Error> {
+            Ok(())
+        }
+    }
+
+    struct UserCodeAttemptRepositoryStub {
+        attempts: RefCell<i64>,
+    }
+
+    impl UserCodeAttemptRepository for UserCodeAttemptRepositoryStub {
+        fn count(&self, _end_user_id: &String) -> Result<i64, ed::Error> {
+            Ok(*self.attempts.borrow())
+        }
+        fn add(&self, _end_user_id: &String, _expires_in: i64) -> Result<(), ed::Error> {
+            *self.attempts.borrow_mut() += 1;
+            Ok(())
+        }
+    }
+
+    fn device() -> DeviceAuthorization {
+        DeviceAuthorization {
+            id: "id".to_string(),
+            device_code: "device_code".to_string(),
+            user_code: "BCDF-GHJK".to_string(),
+            client_id: "client".to_string(),
+            resource_id: "resource".to_string(),
+            scope: Vec::new(),
+            openid: false,
+            status: DeviceAuthorizationStatus::Pending,
+            end_user_id: None,
+            sid: None,
+            auth_time: None,
+            acr: None,
+            amr: None,
+            interval: 5,
+            last_polled_at: None,
+            expires_in: 600,
+            created_at: Utc::now(),
+            is_deleted: false,
+        }
+    }
+
+    #[test]
+    fn should_limit_wrong_user_codes() {
+        let device_repo = DeviceAuthorizationRepositoryStub {
+            devices: RefCell::new(vec![device()]),
+        };
+        let attempt_repo = UserCodeAttemptRepositoryStub {
+            attempts: RefCell::new(0),
+        };
+        let end_user_id = "end_user".to_string();
+        let find = |user_code: &str| {
+            find_pending_device(
+                &end_user_id,
+                &user_code.to_string(),
+                2,
+                600,
+                &device_repo,
+                &attempt_repo,
+            )
+        };
+
+        assert!(find("bcdf-ghjk").is_ok());
+        assert_eq!(find("BCDF-GHJL").unwrap_err().description(), "invalid_request");
+        assert_eq!(find("BCDF").unwrap_err().description(), "invalid_request");
+        assert_eq!(*attempt_repo.attempts.borrow(), 2);
+        // The right code is rejected as well after too many wrong codes.
+        assert_eq!(find("BCDF-GHJK").unwrap_err().description(), "slow_down");
+    }
+
+    #[test]
+    fn should_change_pending_device_once() {
+        let device_repo = DeviceAuthorizationRepositoryStub {
+            devices: RefCell::new(vec![device()]),
+        };
+        let approved = DeviceAuthorizationStatus::Approved;
+        let denied = DeviceAuthorizationStatus::Denied;
+
+        assert!(change_pending_device(&device(), &approved, &device_repo).is_ok());
+        assert!(change_pending_device(&device(), &denied, &device_repo).is_err());
+        assert_eq!(device_repo.devices.borrow()[0].status, approved);
+    }
+}
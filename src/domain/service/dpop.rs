@@ -0,0 +1,57 @@
+use config::AppConfig;
+use domain::error::domain as ed;
+use self::ed::ErrorKind as ek;
+use domain::model::DpopProof;
+use domain::repository::{DpopProofRepository, DpopProofRepositoryComponent};
+
+pub struct DpopProofCmd {
+    /// The value of the DPoP header.
+    pub proof: String,
+    pub htm: String,
+    pub htu: String,
+    /// The access token presented with the proof, if any.
+    pub access_token: Option<String>,
+}
+
+/// `DpopService` verifies DPoP proofs (RFC 9449) presented with requests.
+pub trait DpopService: DpopProofRepositoryComponent {
+    /// Returns the JWK thumbprint of the key which the proof is signed with.
+    fn verify_dpop_proof(&self, cmd: &DpopProofCmd) -> Result<String, ed::Error> {
+        check_dpop_proof(
+            cmd,
+            AppConfig::dpop_proof_max_age_sec(),
+            self.dpop_proof_repository(),
+        )
+    }
+}
+
+pub trait DpopServiceComponent {
+    type DpopService: DpopService;
+    fn dpop_service(&self) -> &Self::DpopService;
+}
+
+// Implement
+impl<T: DpopProofRepositoryComponent> DpopService for T {}
+
+/// Verifies the proof whose `iat` is within `max_age_sec` from now
+/// and returns the JWK thumbprint of the key which the proof is signed with.
+pub fn check_dpop_proof(
+    cmd: &DpopProofCmd,
+    max_age_sec: i64,
+    dpop_proof_repo: &DpopProofRepository,
+) -> Result<String, ed::Error> {
+    let proof = DpopProof::decode(&cmd.proof)?;
+    proof.validate(&cmd.htm, &cmd.htu, cmd.access_token.as_ref(), max_age_sec)?;
+
+    // Replay protection
+    // iat is accepted within the max age in both directions,
+    // so the jti is kept for twice as long.
+    let expires_in = max_age_sec * 2;
+    if !dpop_proof_repo.add_if_absent(&proof.jti, expires_in)? {
+        return Err(ek::InvalidDpopProof(format!(
+            "The proof has been already used. jti => {}",
+            proof.jti
+        )).into());
+    }
+    Ok(proof.jkt)
+}
@@ -0,0 +1,296 @@
+use url::Url;
+
+use config::AppConfig;
+use domain::error::domain as ed;
+use self::ed::ErrorKind as ek;
+use domain::model::IdTokenClaims;
+use domain::repository::{ClientRepository, ClientRepositoryComponent};
+use domain::service::{KeyService, KeyServiceComponent};
+
+pub struct EndSessionCmd {
+    pub end_user_id: Option<String>,
+    pub id_token_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub post_logout_redirect_uri: Option<String>,
+    pub state: Option<String>,
+    /// The session ID for OpenID Connect.
+    pub sid: Option<String>,
+    /// The clients which have been issued tokens in the session.
+    pub client_ids: Vec<String>,
+}
+
+/// `EndSessionRet` is the result of RP-Initiated Logout.
+/// The end user is redirected to `post_logout_redirect_uri` if it is present,
+/// after the user agent loads `frontchannel_logout_uris` in iframes.
+pub struct EndSessionRet {
+    pub post_logout_redirect_uri: Option<String>,
+    pub state: Option<String>,
+    pub frontchannel_logout_uris: Vec<String>,
+}
+
+/// `EndSessionService` provides functions for RP-Initiated Logout.
+pub trait EndSessionService: ClientRepositoryComponent + KeyServiceComponent {
+    /// Validates the logout request.
+    /// The caller terminates the session of the end user if this succeeds.
+    fn end_session(&self, cmd: &EndSessionCmd) -> Result<EndSessionRet, ed::Error> {
+        let client_repo = self.client_repository();
+        let key_service = self.key_service();
+
+        // The id_token_hint may be expired, but it MUST be issued by this server.
+        let claims = match cmd.id_token_hint {
+            Some(ref token) => Some(
+                IdTokenClaims::decode(token, &key_service.verification_keys()?, false)
+                    .map_err(|_| ek::InvalidRequest("Invalid id_token_hint.".to_string()))?,
+            ),
+            None => None,
+        };
+
+        check_hint_subject(&claims, &cmd.end_user_id)?;
+
+        let post_logout_redirect_uri = match cmd.post_logout_redirect_uri {
+            Some(ref uri) => Some(validate_post_logout_redirect_uri(
+                uri,
+                &claims,
+                &cmd.client_id,
+                client_repo,
+            )?),
+            None => None,
+        };
+
+        let frontchannel_logout_uris = build_frontchannel_logout_uris(
+            &cmd.client_ids,
+            &cmd.sid,
+            &AppConfig::issuer(),
+            client_repo,
+        )?;
+
+        Ok(EndSessionRet {
+            post_logout_redirect_uri,
+            state: cmd.state.clone(),
+            frontchannel_logout_uris,
+        })
+    }
+}
+
+pub trait EndSessionServiceComponent {
+    type EndSessionService: EndSessionService;
+    fn end_session_service(&self) -> &Self::EndSessionService;
+}
+
+// Implement
+impl<T: ClientRepositoryComponent + KeyServiceComponent> EndSessionService for T {}
+
+// Private functions
+/// The hint MUST be issued to the logged in end user if the session exists.
+/// The RP often logs out after the session has expired, so the hint is accepted without it.
+fn check_hint_subject(
+    claims: &Option<IdTokenClaims>,
+    end_user_id: &Option<String>,
+) -> Result<(), ed::Error> {
+    match (claims, end_user_id) {
+        (&Some(ref c), &Some(ref id)) if &c.sub != id => Err(ek::InvalidRequest(
+            "id_token_hint does not match the logged in user.".to_string(),
+        ).into()),
+        _ => Ok(()),
+    }
+}
+
+/// Validates the post_logout_redirect_uri with the client identified by
+/// the id_token_hint or client_id, then returns valid post_logout_redirect_uri.
+fn validate_post_logout_redirect_uri(
+    uri: &String,
+    claims: &Option<IdTokenClaims>,
+    client_id: &Option<String>,
+    client_repo: &ClientRepository,
+) -> Result<String, ed::Error> {
+    let client_id = match (claims, client_id) {
+        (&Some(ref c), &Some(ref id)) if &c.aud != id => {
+            return Err(ek::InvalidRequest(
+                "client_id does not match the id_token_hint.".to_string(),
+            ).into());
+        }
+        (&Some(ref c), _) => c.aud.clone(),
+        (&None, &Some(ref id)) => id.clone(),
+        (&None, &None) => {
+            return Err(ek::InvalidRequest(
+                "post_logout_redirect_uri requires id_token_hint or client_id.".to_string(),
+            ).into());
+        }
+    };
+    let client = client_repo.find_by_id(&client_id).and_then(|v| {
+        v.ok_or(ek::InvalidRequest(format!("Client not found. ID => {}", client_id)).into())
+    })?;
+    if !client.validate_post_logout_redirect_uri(uri) {
+        return Err(ek::InvalidRequest("Invalid post_logout_redirect_uri.".to_string()).into());
+    }
+    Ok(uri.clone())
+}
+
+/// Returns the `frontchannel_logout_uri`s of the clients.
+/// `iss` and `sid` parameters are added only for the clients which require them
+/// by `frontchannel_logout_session_required`.
+fn build_frontchannel_logout_uris(
+    client_ids: &Vec<String>,
+    sid: &Option<String>,
+    issuer: &str,
+    client_repo: &ClientRepository,
+) -> Result<Vec<String>, ed::Error> {
+    let mut uris = Vec::new();
+    for client_id in client_ids.iter() {
+        let client = match client_repo.find_by_id(client_id)? {
+            Some(client) => client,
+            None => continue,
+        };
+        if let Some(ref uri) = client.metadata.frontchannel_logout_uri {
+            let mut url = Url::parse(uri).map_err(|_| {
+                ek::ServerError(format!("Invalid frontchannel_logout_uri: {}", uri))
+            })?;
+            let session_required = client.metadata.frontchannel_logout_session_required;
+            if let (true, &Some(ref sid)) = (session_required, sid) {
+                url.query_pairs_mut()
+                    .append_pair("iss", issuer)
+                    .append_pair("sid", sid);
+            }
+            uris.push(url.into_string());
+        }
+    }
+    Ok(uris)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::model::{test_client, Client, ClientMetadata, StandardClaims};
+
+    struct StubClientRepository {
+        clients: Vec<Client>,
+    }
+
+    impl ClientRepository for StubClientRepository {
+        fn find_by_id(&self, id: &String) -> Result<Option<Client>, ed::Error> {
+            Ok(self.clients.iter().find(|c| &c.id == id).cloned())
+        }
+        fn find_by_name(&self, _name: &String) -> Result<Option<Client>, ed::Error> {
+            Ok(None)
+        }
+        fn find_all(&self) -> Result<Vec<Client>, ed::Error> {
+            Ok(self.clients.clone())
+        }
+        fn add(&self, _model: &Client) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn update(&self, _model: &Client) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn remove(&self, _model: Client) -> Result<(), ed::Error> {
+            Ok(())
+        }
+    }
+
+    fn client(id: &str) -> Client {
+        let metadata = ClientMetadata {
+            post_logout_redirect_uris: vec![format!("https://{}.example.com/logout", id)],
+            ..ClientMetadata::default()
+        };
+        test_client(id, metadata)
+    }
+
+    fn claims(aud: &str) -> Option<IdTokenClaims> {
+        Some(IdTokenClaims {
+            iss: "http://127.0.0.1:8000".to_string(),
+            sub: "end_user".to_string(),
+            aud: aud.to_string(),
+            exp: 0,
+            iat: 0,
+            auth_time: None,
+            nonce: None,
+            acr: None,
+            amr: None,
+            azp: None,
+            sid: None,
+            standard_claims: StandardClaims::default(),
+        })
+    }
+
+    #[test]
+    fn should_accept_id_token_hint_without_session() {
+        let end_user = |id: &str| Some(id.to_string());
+
+        assert!(check_hint_subject(&claims("client1"), &None).is_ok());
+        assert!(check_hint_subject(&claims("client1"), &end_user("end_user")).is_ok());
+        assert!(check_hint_subject(&claims("client1"), &end_user("other")).is_err());
+        assert!(check_hint_subject(&None, &end_user("end_user")).is_ok());
+    }
+
+    #[test]
+    fn should_validate_post_logout_redirect_uri() {
+        let repo = StubClientRepository {
+            clients: vec![client("client1"), client("client2")],
+        };
+        let uri = "https://client1.example.com/logout".to_string();
+        let id = |id: &str| Some(id.to_string());
+
+        // The client is identified by the id_token_hint or client_id.
+        let ret = validate_post_logout_redirect_uri(&uri, &claims("client1"), &None, &repo);
+        assert_eq!(ret.unwrap(), uri);
+        assert!(validate_post_logout_redirect_uri(&uri, &None, &id("client1"), &repo).is_ok());
+        assert!(
+            validate_post_logout_redirect_uri(&uri, &claims("client1"), &id("client1"), &repo)
+                .is_ok()
+        );
+
+        assert!(validate_post_logout_redirect_uri(&uri, &None, &None, &repo).is_err());
+        assert!(
+            validate_post_logout_redirect_uri(&uri, &claims("client1"), &id("client2"), &repo)
+                .is_err()
+        );
+        assert!(validate_post_logout_redirect_uri(&uri, &None, &id("client2"), &repo).is_err());
+        assert!(validate_post_logout_redirect_uri(&uri, &None, &id("unknown"), &repo).is_err());
+    }
+
+    #[test]
+    fn should_match_post_logout_redirect_uri_exactly() {
+        let client = client("client1");
+
+        assert!(client.validate_post_logout_redirect_uri(
+            &"https://client1.example.com/logout".to_string()
+        ));
+        assert!(!client.validate_post_logout_redirect_uri(
+            &"https://client1.example.com/logout?next=https://evil.example.com".to_string()
+        ));
+        assert!(!client.validate_post_logout_redirect_uri(
+            &"https://client1.example.com/".to_string()
+        ));
+    }
+
+    #[test]
+    fn should_add_session_to_frontchannel_logout_uri_only_if_required() {
+        let mut client1 = client("client1");
+        client1.metadata.frontchannel_logout_uri =
+            Some("https://client1.example.com/frontchannel_logout".to_string());
+        client1.metadata.frontchannel_logout_session_required = true;
+        let mut client2 = client("client2");
+        client2.metadata.frontchannel_logout_uri =
+            Some("https://client2.example.com/frontchannel_logout".to_string());
+        let repo = StubClientRepository {
+            clients: vec![client1, client2, client("client3")],
+        };
+        let client_ids = vec![
+            "client1".to_string(),
+            "client2".to_string(),
+            "client3".to_string(),
+        ];
+        let issuer = "https://op.example.com";
+
+        let uris =
+            build_frontchannel_logout_uris(&client_ids, &Some("sid".to_string()), issuer, &repo)
+                .unwrap();
+        assert_eq!(uris.len(), 2);
+        assert_eq!(
+            uris[0],
+            "https://client1.example.com/frontchannel_logout\
+             ?iss=https%3A%2F%2Fop.example.com&sid=sid"
+        );
+        assert_eq!(uris[1], "https://client2.example.com/frontchannel_logout");
+    }
+}
@@ -1,43 +1,39 @@
 use domain::error::domain as ed;
 use self::ed::ErrorKind as ek;
-use domain::model::AccessToken;
-use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent, ClientRepository,
-                         ClientRepositoryComponent, EndUserRepository, EndUserRepositoryComponent,
-                         ResourceRepository, ResourceRepositoryComponent};
-use domain::service::IntrospectRet;
+use domain::model::{AccessToken, TOKEN_ENDPOINT_AUTH_METHOD_NONE};
+use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent,
+                         EndUserRepository, EndUserRepositoryComponent, ResourceRepository,
+                         ResourceRepositoryComponent};
+use domain::service::{ClientAuthentication, ClientAuthenticationService,
+                      ClientAuthenticationServiceComponent, IntrospectRet};
 
 pub struct IntrospectCmd {
-    pub client_id: Option<String>,
-    pub client_secret: Option<String>,
+    pub client_auth: ClientAuthentication,
     pub token: String,
     pub token_type_hint: Option<String>,
 }
 
 pub trait IntrospectService
     : AccessTokenRepositoryComponent
-    + ClientRepositoryComponent
     + EndUserRepositoryComponent
-    + ResourceRepositoryComponent {
+    + ResourceRepositoryComponent
+    + ClientAuthenticationServiceComponent {
     fn introspect(&self, cmd: &IntrospectCmd) -> Result<IntrospectRet, ed::Error> {
         let access_token_repo = self.access_token_repository();
-        let client_repo = self.client_repository();
         let end_user_repo = self.end_user_repository();
         let resource_repo = self.resource_repository();
 
         // Authorize the client
-        let (client_id, client_secret) = cmd.client_id
-            .as_ref()
-            .and_then(|id| cmd.client_secret.as_ref().map(|secret| (id, secret)))
-            .ok_or(ek::UnauthorizedClient("Invalid credentials.".to_string()))?;
-        let client = client_repo.find_by_id(&client_id).and_then(|c| {
-            c.ok_or(ek::UnauthorizedClient(format!("Client not found. ID => {}", client_id)).into())
-        })?;
-        if !client.is_authenticated_by_secret(&client_secret) {
+        // Only confidential clients are allowed to introspect tokens.
+        let client = self.client_authentication_service()
+            .authenticate_client(&cmd.client_auth)?;
+        if client.token_endpoint_auth_method() == TOKEN_ENDPOINT_AUTH_METHOD_NONE {
             return Err(ek::UnauthorizedClient(format!(
                 "Client not authorized. ID => {}",
-                client_id
+                client.id
             )).into());
         }
+        let client_id = &client.id;
 
         // Access token
         let access_token = access_token_repo.find_by_token(&cmd.token)?;
@@ -81,6 +77,11 @@ pub trait IntrospectService
             .iat(Some(access_token.created_at.timestamp()))
             .sub(end_user.as_ref().map(|v| v.id.to_string()))
             .aud(Some(client_id.clone()))
+            .acr(access_token.acr.clone())
+            .amr(access_token.amr.clone())
+            .act(access_token.act.clone())
+            .token_type(Some(access_token.token_type().to_string()))
+            .cnf(access_token.cnf.clone())
             .build())
     }
 }
@@ -92,9 +93,9 @@ pub trait IntrospectServiceComponent {
 
 impl<
     T: AccessTokenRepositoryComponent
-        + ClientRepositoryComponent
         + EndUserRepositoryComponent
-        + ResourceRepositoryComponent,
+        + ResourceRepositoryComponent
+        + ClientAuthenticationServiceComponent,
 > IntrospectService for T
 {
 }
@@ -1,4 +1,5 @@
 use config::AppConfig;
+use domain::model::{Actor, Confirmation};
 
 #[derive(Serialize, Deserialize)]
 pub struct IntrospectRet {
@@ -25,6 +26,14 @@ pub struct IntrospectRet {
     iss: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     jti: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    acr: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amr: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    act: Option<Actor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cnf: Option<Confirmation>,
 }
 
 impl IntrospectRet {
@@ -45,6 +54,10 @@ pub struct IntrospectRetBuilder {
     sub: Option<String>,
     aud: Option<String>,
     jti: Option<String>,
+    acr: Option<String>,
+    amr: Option<Vec<String>>,
+    act: Option<Actor>,
+    cnf: Option<Confirmation>,
 }
 
 impl IntrospectRetBuilder {
@@ -61,6 +74,10 @@ impl IntrospectRetBuilder {
             sub: None,
             aud: None,
             jti: None,
+            acr: None,
+            amr: None,
+            act: None,
+            cnf: None,
         }
     }
 
@@ -94,6 +111,18 @@ impl IntrospectRetBuilder {
     pub fn jti(self, jti: Option<String>) -> Self {
         IntrospectRetBuilder { jti, ..self }
     }
+    pub fn acr(self, acr: Option<String>) -> Self {
+        IntrospectRetBuilder { acr, ..self }
+    }
+    pub fn amr(self, amr: Option<Vec<String>>) -> Self {
+        IntrospectRetBuilder { amr, ..self }
+    }
+    pub fn act(self, act: Option<Actor>) -> Self {
+        IntrospectRetBuilder { act, ..self }
+    }
+    pub fn cnf(self, cnf: Option<Confirmation>) -> Self {
+        IntrospectRetBuilder { cnf, ..self }
+    }
     pub fn build(self) -> IntrospectRet {
         IntrospectRet {
             action: self.action,
@@ -108,6 +137,10 @@ impl IntrospectRetBuilder {
             aud: self.aud,
             iss: Some(AppConfig::issuer().to_string()),
             jti: self.jti,
+            acr: self.acr,
+            amr: self.amr,
+            act: self.act,
+            cnf: self.cnf,
         }
     }
 }
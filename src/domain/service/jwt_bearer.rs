@@ -0,0 +1,129 @@
+use config::AppConfig;
+use domain::error::domain as ed;
+use self::ed::ErrorKind as ek;
+use domain::model::{AccessToken, Client, Confirmation, ConsumedAssertion, JwtBearerAssertion};
+use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent,
+                         ConsumedAssertionRepository, ConsumedAssertionRepositoryComponent,
+                         EndUserRepository, EndUserRepositoryComponent, ResourceRepository,
+                         ResourceRepositoryComponent};
+use domain::service::TokensRet;
+
+pub struct JwtBearerCmd {
+    pub assertion: Option<String>,
+    pub scope: Option<String>,
+    /// The confirmation of the DPoP proof key which the tokens are bound to.
+    pub cnf: Option<Confirmation>,
+}
+
+/// `JwtBearerService` provides functions for JWT Bearer Authorization Grant (RFC 7523).
+pub trait JwtBearerService
+    : AccessTokenRepositoryComponent
+    + ConsumedAssertionRepositoryComponent
+    + EndUserRepositoryComponent
+    + ResourceRepositoryComponent {
+    /// Issues an access token for the end user identified by the assertion.
+    fn execute_jwt_bearer(&self, client: &Client, cmd: &JwtBearerCmd) -> TokensRet {
+        let ret = process_jwt_bearer(
+            cmd,
+            client,
+            self.access_token_repository(),
+            self.consumed_assertion_repository(),
+            self.end_user_repository(),
+            self.resource_repository(),
+        );
+
+        match ret {
+            Ok(r) => r,
+            Err(e) => TokensRet::error(e),
+        }
+    }
+}
+
+pub trait JwtBearerServiceComponent {
+    type JwtBearerService: JwtBearerService;
+    fn jwt_bearer_service(&self) -> &Self::JwtBearerService;
+}
+
+// Implement
+impl<
+    T: AccessTokenRepositoryComponent
+        + ConsumedAssertionRepositoryComponent
+        + EndUserRepositoryComponent
+        + ResourceRepositoryComponent,
+> JwtBearerService for T
+{
+}
+
+// Private functions
+fn process_jwt_bearer(
+    cmd: &JwtBearerCmd,
+    client: &Client,
+    access_token_repo: &AccessTokenRepository,
+    consumed_repo: &ConsumedAssertionRepository,
+    end_user_repo: &EndUserRepository,
+    resource_repo: &ResourceRepository,
+) -> Result<TokensRet, ed::Error> {
+    // Verify the assertion with the issuers trusted by the client
+    let assertion = match cmd.assertion {
+        Some(ref a) => JwtBearerAssertion::decode(a, &client.metadata.trusted_issuers)?,
+        None => return Err(ek::InvalidRequest("assertion is required.".to_string()).into()),
+    };
+    // The assertion is addressed to either the issuer or the token endpoint.
+    let audiences = vec![
+        AppConfig::issuer(),
+        format!("{}/oidc/tokens", AppConfig::endpoint().trim_right_matches('/')),
+    ];
+    if !assertion.has_audience(&audiences) {
+        return Err(ek::InvalidGrant("The assertion is not addressed to us.".to_string()).into());
+    }
+
+    // Replay protection
+    let consumed = ConsumedAssertion::new(&assertion.iss, &assertion.jti, assertion.exp);
+    if !consumed_repo.add_if_absent(&consumed)? {
+        return Err(ek::InvalidGrant(format!(
+            "The assertion has been already used. jti => {}",
+            assertion.jti
+        )).into());
+    }
+
+    // EndUser
+    // The subject of the issuer is mapped to the end user by admins.
+    let end_user_id = assertion
+        .end_user_id(&client.metadata.trusted_issuers)
+        .ok_or(ek::InvalidGrant(format!("Unknown subject: {}", assertion.sub)))?;
+    let end_user = end_user_repo.find_by_id(&end_user_id).and_then(|v| {
+        v.ok_or(ek::InvalidGrant(format!("EndUser not found. ID => {}", end_user_id)).into())
+    })?;
+
+    // Resource
+    let resource = resource_repo
+        .find_by_id(&client.resource_id)
+        .and_then(|v| {
+            v.ok_or(
+                ek::InvalidRequest(format!("Resource not found. ID => {}", client.resource_id))
+                    .into(),
+            )
+        })?;
+
+    // Scope
+    let scope = cmd.scope.as_ref().map_or(Vec::new(), |s| {
+        resource.filter_scope(&s.split(" ").map(|s| s.to_string()).collect())
+    });
+
+    // The end user MUST have accepted the client with the scope.
+    if end_user.require_acceptance(&scope, &client.id) {
+        return Err(ek::InvalidGrant(
+            "The end user has not accepted the client.".to_string(),
+        ).into());
+    }
+
+    // Access token
+    let access_token = AccessToken::builder(&client.id, &resource.id)
+        .end_user_id(&Some(end_user.id.clone()))
+        .scope(&scope)
+        .cnf(&client.confirmation(&cmd.cnf)?)
+        .build();
+    access_token_repo.add(&access_token)?;
+
+    Ok(TokensRet::builder(&access_token).build())
+}
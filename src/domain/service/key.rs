@@ -1,7 +1,15 @@
+use chrono::prelude::*;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
+use std::sync::RwLock;
+use jsonwebtoken::{decode, encode, Algorithm, Header, Validation};
 
 use config::AppConfig;
+use domain::error::domain as ed;
+use self::ed::ErrorKind as ek;
+use domain::model::{JwkSet, SigningKey, SigningKeyStatus};
+use domain::repository::{SigningKeyRepository, SigningKeyRepositoryComponent};
 
 lazy_static! {
   static ref PRIVATE_KEY: Vec<u8> = {
@@ -20,25 +28,47 @@ lazy_static! {
     buf
   };
 
-  static ref PUBLIC_KEY_PEM: String = {
-    let filename = AppConfig::jwt_public_key_pem();
-    let mut file = File::open(filename).unwrap();
-    let mut content = String::new();
-    file.read_to_string(&mut content).unwrap();
-    content
-  };
+  /// The active key and the time when it was loaded.
+  static ref ACTIVE_KEY_CACHE: RwLock<Option<(SigningKey, i64)>> = RwLock::new(None);
 }
 
+/// Another instance may rotate the keys, so the active key is reloaded after this period.
+/// The retired key is still published, so tokens signed meanwhile can be verified.
+const ACTIVE_KEY_CACHE_SEC: i64 = 60;
+
+/// `KeyService` manages the key ring to sign and verify JWTs.
+/// Keys move from `next` to `active` to `retired` by rotation.
+/// Retired keys are still published until they expire,
+/// so that tokens signed before the rotation can be verified.
 pub trait KeyService {
-    fn jwt_private_key<'a>(&self) -> &'a Vec<u8> {
-        &PRIVATE_KEY
-    }
-    fn jwt_public_key<'a>(&self) -> &'a Vec<u8> {
-        &PUBLIC_KEY
-    }
-    fn jwt_public_key_pem<'a>(&self) -> &'a String {
-        &PUBLIC_KEY_PEM
+    /// Returns the key currently used for signing.
+    fn active_signing_key(&self) -> Result<SigningKey, ed::Error>;
+
+    /// Returns all keys which can be used for verification.
+    fn verification_keys(&self) -> Result<Vec<SigningKey>, ed::Error>;
+
+    /// Returns all keys which are not deleted, including expired ones.
+    fn signing_keys(&self) -> Result<Vec<SigningKey>, ed::Error>;
+
+    /// Returns the public keys for verification as a JWK Set.
+    fn jwt_public_jwk_set(&self) -> Result<JwkSet, ed::Error> {
+        let keys = self.verification_keys()?;
+        let mut jwks = Vec::new();
+        for key in keys.iter() {
+            jwks.push(key.to_jwk()?);
+        }
+        Ok(JwkSet { keys: jwks })
     }
+
+    /// Registers a new key as the next key.
+    fn register_next_signing_key(
+        &self,
+        private_key: &Vec<u8>,
+        public_key: &Vec<u8>,
+    ) -> Result<SigningKey, ed::Error>;
+
+    /// Activates the next key and retires the active key.
+    fn rotate_signing_keys(&self) -> Result<SigningKey, ed::Error>;
 }
 
 pub trait KeyServiceComponent {
@@ -47,5 +77,120 @@ pub trait KeyServiceComponent {
 }
 
 // Implement
-// TODO: Implement in the infra module
-impl<T> KeyService for T {}
+impl<T: SigningKeyRepositoryComponent> KeyService for T {
+    fn active_signing_key(&self) -> Result<SigningKey, ed::Error> {
+        let now = Utc::now().timestamp();
+        let cached = ACTIVE_KEY_CACHE.read().unwrap().clone();
+        match cached {
+            Some((key, loaded_at)) => if now - loaded_at < ACTIVE_KEY_CACHE_SEC {
+                return Ok(key);
+            },
+            // Keys stored in plaintext by older versions are encrypted once at the first load
+            None => encrypt_stored_private_keys(self.signing_key_repository())?,
+        }
+        let key = load_active_signing_key(self.signing_key_repository())?;
+        *ACTIVE_KEY_CACHE.write().unwrap() = Some((key.clone(), now));
+        Ok(key)
+    }
+
+    fn verification_keys(&self) -> Result<Vec<SigningKey>, ed::Error> {
+        // Make sure that the key ring has been initialized
+        self.active_signing_key()?;
+        self.signing_keys()
+            .map(|keys| keys.into_iter().filter(|k| k.is_valid()).collect())
+    }
+
+    fn signing_keys(&self) -> Result<Vec<SigningKey>, ed::Error> {
+        let repo = self.signing_key_repository();
+        let mut keys = repo.find_all()?;
+        keys.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        Ok(keys)
+    }
+
+    fn register_next_signing_key(
+        &self,
+        private_key: &Vec<u8>,
+        public_key: &Vec<u8>,
+    ) -> Result<SigningKey, ed::Error> {
+        let repo = self.signing_key_repository();
+        let key = SigningKey::new(private_key, public_key)?;
+        validate_key_pair(private_key, public_key)?;
+        if repo.find_by_id(&key.id)?.is_some() {
+            return Err(ek::DuplicatedEntity(format!(
+                "The key has been already registered. ID => {}",
+                key.id
+            )).into());
+        }
+        repo.add(&key)?;
+        Ok(key)
+    }
+
+    fn rotate_signing_keys(&self) -> Result<SigningKey, ed::Error> {
+        let repo = self.signing_key_repository();
+        let mut next_keys = repo.find_by_status(&SigningKeyStatus::Next)?;
+        if next_keys.is_empty() {
+            return Err(ek::InvalidRequest("No next key is registered.".to_string()).into());
+        }
+        // The oldest next key is activated first
+        next_keys.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        let mut next_key = next_keys.remove(0);
+
+        // Retired keys must be published while id tokens signed with them are alive
+        let mut active_keys = repo.find_by_status(&SigningKeyStatus::Active)?;
+        for key in active_keys.iter_mut() {
+            key.retire(AppConfig::default_id_token_max_age_sec());
+            repo.update(key)?;
+        }
+        next_key.activate();
+        repo.update(&next_key)?;
+        *ACTIVE_KEY_CACHE.write().unwrap() = None;
+        Ok(next_key)
+    }
+}
+
+// Private functions
+fn load_active_signing_key(repo: &SigningKeyRepository) -> Result<SigningKey, ed::Error> {
+    let mut keys = repo.find_by_status(&SigningKeyStatus::Active)?;
+    if keys.is_empty() {
+        return bootstrap_signing_key(repo);
+    }
+    keys.sort_by(|a, b| b.activated_at.cmp(&a.activated_at));
+    Ok(keys.remove(0))
+}
+
+fn encrypt_stored_private_keys(repo: &SigningKeyRepository) -> Result<(), ed::Error> {
+    for key in repo.find_all()?.into_iter().filter(|k| !k.is_encrypted()) {
+        repo.update(&key.encrypt()?)?;
+    }
+    Ok(())
+}
+
+/// Registers the key pair in the configuration as the active key
+/// if the key ring is empty.
+fn bootstrap_signing_key(repo: &SigningKeyRepository) -> Result<SigningKey, ed::Error> {
+    if !repo.find_all()?.is_empty() {
+        return Err(ek::ServerError("No active signing key.".to_string()).into());
+    }
+    let mut key = SigningKey::new(&PRIVATE_KEY, &PUBLIC_KEY)?;
+    key.activate();
+    repo.add(&key)?;
+    Ok(key)
+}
+
+/// Checks that the private key and the public key are a pair.
+fn validate_key_pair(private_key: &Vec<u8>, public_key: &Vec<u8>) -> Result<(), ed::Error> {
+    let mut claims = HashMap::new();
+    claims.insert("sub", "key_pair_validation");
+    let token = encode(&Header::new(Algorithm::RS256), &claims, private_key.as_ref())
+        .map_err(|_| ek::InvalidRequest("Invalid private key.".to_string()))?;
+    let validation = Validation {
+        algorithms: Some(vec![Algorithm::RS256]),
+        ..Validation::default()
+    };
+    decode::<HashMap<String, String>>(&token, public_key.as_ref(), &validation)
+        .map(|_| ())
+        .map_err(|_| {
+            ek::InvalidRequest("The private key and the public key are not a pair.".to_string())
+                .into()
+        })
+}
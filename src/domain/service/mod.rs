@@ -1,21 +1,41 @@
 mod authorize;
 mod authorize_result;
+mod backchannel_logout;
+mod client_authentication;
 mod client_credentials;
+mod device_authorization;
+mod dpop;
+mod end_session;
 mod introspect;
 mod introspect_result;
+mod jwt_bearer;
 mod key;
+mod pushed_authorization;
 mod refresh_token;
+mod request_object;
 mod resource_owner_password_credentials;
+mod revocation;
+mod token_exchange;
 mod tokens_result;
 mod userinfo;
 
 pub use self::authorize::*;
 pub use self::authorize_result::*;
+pub use self::backchannel_logout::*;
+pub use self::client_authentication::*;
 pub use self::client_credentials::*;
+pub use self::device_authorization::*;
+pub use self::dpop::*;
+pub use self::end_session::*;
 pub use self::introspect::*;
 pub use self::introspect_result::*;
+pub use self::jwt_bearer::*;
 pub use self::key::*;
+pub use self::pushed_authorization::*;
 pub use self::refresh_token::*;
+pub use self::request_object::*;
 pub use self::resource_owner_password_credentials::*;
+pub use self::revocation::*;
+pub use self::token_exchange::*;
 pub use self::tokens_result::*;
 pub use self::userinfo::*;
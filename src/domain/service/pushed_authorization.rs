@@ -0,0 +1,314 @@
+use domain::error::domain as ed;
+use self::ed::ErrorKind as ek;
+use domain::model::PushedAuthorizationRequest;
+use domain::repository::{PushedAuthorizationRequestRepository,
+                         PushedAuthorizationRequestRepositoryComponent};
+use domain::service::{resolve_request_object, AuthorizeCmd, ClientAuthentication,
+                      ClientAuthenticationService, ClientAuthenticationServiceComponent,
+                      RequestObjectFetcherComponent};
+
+pub struct PushAuthorizationRequestCmd {
+    pub client_auth: ClientAuthentication,
+    pub response_type: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub prompt: Option<String>,
+    pub max_age: Option<String>,
+    pub acr_values: Option<String>,
+    pub claims: Option<String>,
+    pub response_mode: Option<String>,
+    pub request: Option<String>,
+    pub request_uri: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PushAuthorizationRequestRet {
+    pub request_uri: String,
+    pub expires_in: i64,
+}
+
+/// `PushedAuthorizationService` provides functions for Pushed Authorization Requests (RFC 9126).
+pub trait PushedAuthorizationService
+    : PushedAuthorizationRequestRepositoryComponent
+    + ClientAuthenticationServiceComponent
+    + RequestObjectFetcherComponent {
+    /// Stores the authorization parameters and returns the `request_uri` referring to them.
+    fn push_authorization_request(
+        &self,
+        cmd: &PushAuthorizationRequestCmd,
+    ) -> Result<PushAuthorizationRequestRet, ed::Error> {
+        let pushed_repo = self.pushed_authorization_request_repository();
+
+        let client = self.client_authentication_service()
+            .authenticate_client(&cmd.client_auth)?;
+
+        if cmd.request_uri.is_some() {
+            return Err(ek::InvalidRequest(
+                "request_uri cannot be pushed.".to_string(),
+            ).into());
+        }
+        let authorize_cmd = AuthorizeCmd {
+            end_user_id: None,
+            client_id: client.id.clone(),
+            response_type: cmd.response_type.clone().unwrap_or_default(),
+            redirect_uri: cmd.redirect_uri.clone().unwrap_or_default(),
+            scope: cmd.scope.clone(),
+            state: cmd.state.clone(),
+            nonce: cmd.nonce.clone(),
+            code_challenge: cmd.code_challenge.clone(),
+            code_challenge_method: cmd.code_challenge_method.clone(),
+            prompt: cmd.prompt.clone(),
+            max_age: cmd.max_age.clone(),
+            acr_values: cmd.acr_values.clone(),
+            claims: cmd.claims.clone(),
+            response_mode: cmd.response_mode.clone(),
+            request: cmd.request.clone(),
+            request_uri: None,
+            sid: None,
+            auth_time: None,
+            amr: None,
+        };
+        let authorize_cmd =
+            resolve_request_object(&authorize_cmd, &client, self.request_object_fetcher())?;
+
+        // The redirect_uri is validated here
+        // because the authorization endpoint responds with the stored one.
+        if authorize_cmd.response_type.is_empty() {
+            return Err(ek::InvalidRequest("response_type is required.".to_string()).into());
+        }
+        if !client.validate_redirect_uri(&authorize_cmd.redirect_uri) {
+            return Err(ek::InvalidRequest(format!(
+                "Unregistered redirect_uri: {}",
+                authorize_cmd.redirect_uri
+            )).into());
+        }
+
+        let pushed = PushedAuthorizationRequest {
+            scope: authorize_cmd.scope,
+            state: authorize_cmd.state,
+            nonce: authorize_cmd.nonce,
+            code_challenge: authorize_cmd.code_challenge,
+            code_challenge_method: authorize_cmd.code_challenge_method,
+            prompt: authorize_cmd.prompt,
+            max_age: authorize_cmd.max_age,
+            acr_values: authorize_cmd.acr_values,
+            claims: authorize_cmd.claims,
+            response_mode: authorize_cmd.response_mode,
+            ..PushedAuthorizationRequest::new(
+                &client.id,
+                &authorize_cmd.response_type,
+                &authorize_cmd.redirect_uri,
+            )
+        };
+        pushed_repo.add(&pushed)?;
+        Ok(PushAuthorizationRequestRet {
+            request_uri: pushed.request_uri.clone(),
+            expires_in: pushed.expires_in,
+        })
+    }
+}
+
+pub trait PushedAuthorizationServiceComponent {
+    type PushedAuthorizationService: PushedAuthorizationService;
+    fn pushed_authorization_service(&self) -> &Self::PushedAuthorizationService;
+}
+
+// Implement
+impl<
+    T: PushedAuthorizationRequestRepositoryComponent
+        + ClientAuthenticationServiceComponent
+        + RequestObjectFetcherComponent,
+> PushedAuthorizationService for T
+{
+}
+
+/// Returns the authorization request replaced with the pushed one.
+/// The other query parameters are ignored.
+/// The pushed request is not consumed here, so that the SPA can retry it after the login.
+pub fn resolve_pushed_authorization_request(
+    cmd: &AuthorizeCmd,
+    request_uri: &String,
+    pushed_repo: &PushedAuthorizationRequestRepository,
+) -> Result<AuthorizeCmd, ed::Error> {
+    let pushed = pushed_repo
+        .find_by_request_uri(request_uri)?
+        .ok_or::<ed::Error>(
+            ek::InvalidRequestUri(format!("Pushed request not found: {}", request_uri)).into(),
+        )?;
+    if pushed.client_id != cmd.client_id {
+        return Err(ek::InvalidRequestUri(
+            "The request was pushed by another client.".to_string(),
+        ).into());
+    }
+    if !pushed.is_valid() {
+        return Err(ek::InvalidRequestUri("The pushed request has expired.".to_string()).into());
+    }
+
+    Ok(AuthorizeCmd {
+        response_type: pushed.response_type,
+        redirect_uri: pushed.redirect_uri,
+        scope: pushed.scope,
+        state: pushed.state,
+        nonce: pushed.nonce,
+        code_challenge: pushed.code_challenge,
+        code_challenge_method: pushed.code_challenge_method,
+        prompt: pushed.prompt,
+        max_age: pushed.max_age,
+        acr_values: pushed.acr_values,
+        claims: pushed.claims,
+        response_mode: pushed.response_mode,
+        request: None,
+        request_uri: None,
+        ..cmd.clone()
+    })
+}
+
+/// Consumes the pushed request after the grant is issued.
+/// It fails if another authorization request has already used it.
+pub fn consume_pushed_authorization_request(
+    request_uri: &String,
+    pushed_repo: &PushedAuthorizationRequestRepository,
+) -> Result<(), ed::Error> {
+    pushed_repo
+        .consume(request_uri)?
+        .map(|_| ())
+        .ok_or(ek::InvalidRequestUri("The pushed request has been used.".to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::prelude::*;
+    use std::cell::RefCell;
+
+    struct StubRepository {
+        pushed: RefCell<Vec<PushedAuthorizationRequest>>,
+    }
+
+    impl PushedAuthorizationRequestRepository for StubRepository {
+        fn find_by_request_uri(
+            &self,
+            request_uri: &String,
+        ) -> Result<Option<PushedAuthorizationRequest>, ed::Error> {
+            Ok(self.pushed
+                .borrow()
+                .iter()
+                .find(|p| &p.request_uri == request_uri && !p.is_deleted)
+                .cloned())
+        }
+        fn consume(
+            &self,
+            request_uri: &String,
+        ) -> Result<Option<PushedAuthorizationRequest>, ed::Error> {
+            let mut pushed = self.pushed.borrow_mut();
+            Ok(pushed
+                .iter_mut()
+                .find(|p| &p.request_uri == request_uri && !p.is_deleted)
+                .map(|p| {
+                    p.is_deleted = true;
+                    p.clone()
+                }))
+        }
+        fn add(&self, model: &PushedAuthorizationRequest) -> Result<(), ed::Error> {
+            self.pushed.borrow_mut().push(model.clone());
+            Ok(())
+        }
+        fn remove(&self, model: PushedAuthorizationRequest) -> Result<(), ed::Error> {
+            self.pushed.borrow_mut().retain(|p| p.id != model.id);
+            Ok(())
+        }
+    }
+
+    fn pushed(request_uri: &str, expires_in: i64) -> PushedAuthorizationRequest {
+        PushedAuthorizationRequest {
+            id: request_uri.to_string(),
+            request_uri: request_uri.to_string(),
+            client_id: "client".to_string(),
+            response_type: "code".to_string(),
+            redirect_uri: "https://client.example.com/cb".to_string(),
+            scope: Some("openid".to_string()),
+            state: Some("pushed".to_string()),
+            nonce: None,
+            code_challenge: None,
+            code_challenge_method: None,
+            prompt: None,
+            max_age: None,
+            acr_values: None,
+            claims: None,
+            response_mode: None,
+            expires_in,
+            created_at: Utc::now(),
+            is_deleted: false,
+        }
+    }
+
+    fn authorize_cmd(client_id: &str, request_uri: &str) -> AuthorizeCmd {
+        AuthorizeCmd {
+            end_user_id: Some("end_user".to_string()),
+            client_id: client_id.to_string(),
+            response_type: String::new(),
+            redirect_uri: String::new(),
+            scope: None,
+            state: Some("query".to_string()),
+            nonce: None,
+            code_challenge: None,
+            code_challenge_method: None,
+            prompt: None,
+            max_age: None,
+            acr_values: None,
+            claims: None,
+            response_mode: None,
+            request: None,
+            request_uri: Some(request_uri.to_string()),
+            sid: None,
+            auth_time: None,
+            amr: None,
+        }
+    }
+
+    #[test]
+    fn should_consume_pushed_request_once() {
+        let valid = "urn:ietf:params:oauth:request_uri:valid".to_string();
+        let expired = "urn:ietf:params:oauth:request_uri:expired".to_string();
+        let repo = StubRepository {
+            pushed: RefCell::new(vec![pushed(&valid, 60), pushed(&expired, -60)]),
+        };
+
+        let e = resolve_pushed_authorization_request(&authorize_cmd("other", &valid), &valid, &repo)
+            .unwrap_err();
+        assert_eq!(e.description(), "invalid_request_uri");
+
+        let e = resolve_pushed_authorization_request(
+            &authorize_cmd("client", &expired),
+            &expired,
+            &repo,
+        ).unwrap_err();
+        assert_eq!(e.description(), "invalid_request_uri");
+
+        let cmd = resolve_pushed_authorization_request(
+            &authorize_cmd("client", &valid),
+            &valid,
+            &repo,
+        ).unwrap();
+        assert_eq!(cmd.redirect_uri, "https://client.example.com/cb");
+        assert_eq!(cmd.state, Some("pushed".to_string()));
+        assert_eq!(cmd.end_user_id, Some("end_user".to_string()));
+        assert!(cmd.request_uri.is_none());
+
+        // It can be resolved again until the grant is issued.
+        assert!(
+            resolve_pushed_authorization_request(&authorize_cmd("client", &valid), &valid, &repo)
+                .is_ok()
+        );
+        assert!(consume_pushed_authorization_request(&valid, &repo).is_ok());
+        assert!(consume_pushed_authorization_request(&valid, &repo).is_err());
+        assert!(
+            resolve_pushed_authorization_request(&authorize_cmd("client", &valid), &valid, &repo)
+                .is_err()
+        );
+    }
+}
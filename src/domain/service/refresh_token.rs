@@ -1,28 +1,27 @@
 use domain::error::domain as ed;
 use self::ed::ErrorKind as ek;
-use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent, ClientRepository,
-                         ClientRepositoryComponent, EndUserRepository, EndUserRepositoryComponent,
-                         IdTokenRepository, IdTokenRepositoryComponent, RefreshTokenRepository,
+use domain::model::{AccessToken, Client, Confirmation};
+use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent,
+                         EndUserRepository, EndUserRepositoryComponent, IdTokenRepository,
+                         IdTokenRepositoryComponent, RefreshTokenRepository,
                          RefreshTokenRepositoryComponent};
 use domain::service::{KeyService, KeyServiceComponent, TokensRet};
 
 pub struct RefreshTokenCmd {
-    pub client_id: Option<String>,
-    pub client_secret: Option<String>,
     pub refresh_token: Option<String>,
     pub scope: Option<String>,
+    /// The confirmation of the DPoP proof key which the tokens are bound to.
+    pub cnf: Option<Confirmation>,
 }
 
 pub trait RefreshTokenService
     : AccessTokenRepositoryComponent
-    + ClientRepositoryComponent
     + EndUserRepositoryComponent
     + IdTokenRepositoryComponent
     + RefreshTokenRepositoryComponent
     + KeyServiceComponent {
-    fn refresh_token(&self, cmd: &RefreshTokenCmd) -> TokensRet {
+    fn refresh_token(&self, client: &Client, cmd: &RefreshTokenCmd) -> TokensRet {
         let access_token_repo = self.access_token_repository();
-        let client_repo = self.client_repository();
         let end_user_repo = self.end_user_repository();
         let id_token_repo = self.id_token_repository();
         let refresh_token_repo = self.refresh_token_repository();
@@ -30,8 +29,8 @@ pub trait RefreshTokenService
 
         let ret = process_refresh_token(
             cmd,
+            client,
             access_token_repo,
-            client_repo,
             end_user_repo,
             id_token_repo,
             refresh_token_repo,
@@ -52,11 +51,10 @@ pub trait RefreshTokenServiceComponent {
 
 impl<
     T: AccessTokenRepositoryComponent
-        + ClientRepositoryComponent
         + EndUserRepositoryComponent
         + IdTokenRepositoryComponent
         + RefreshTokenRepositoryComponent
-        + KeyServiceComponent,
+            + KeyServiceComponent,
 > RefreshTokenService for T
 {
 }
@@ -64,29 +62,13 @@ impl<
 // Private functions
 fn process_refresh_token(
     cmd: &RefreshTokenCmd,
+    client: &Client,
     access_token_repo: &AccessTokenRepository,
-    client_repo: &ClientRepository,
     end_user_repo: &EndUserRepository,
     id_token_repo: &IdTokenRepository,
     refresh_token_repo: &RefreshTokenRepository,
     key_service: &KeyService,
 ) -> Result<TokensRet, ed::Error> {
-    // Authorize the client
-    let (client_id, client_secret) = cmd.client_id
-        .as_ref()
-        .and_then(|id| cmd.client_secret.as_ref().map(|secret| (id, secret)))
-        .ok_or(ek::UnauthorizedClient("Invalid credentials.".to_string()))?;
-
-    let client = client_repo.find_by_id(&client_id).and_then(|c| {
-        c.ok_or(ek::UnauthorizedClient(format!("Client not found. ID => {}", client_id)).into())
-    })?;
-
-    if !client.is_authenticated_by_secret(&client_secret) {
-        return Err(
-            ek::UnauthorizedClient(format!("Client not authorized. ID => {}", client_id)).into(),
-        );
-    }
-
     // Refresh token
     let token = cmd.refresh_token
         .as_ref()
@@ -99,11 +81,35 @@ fn process_refresh_token(
         return Err(ek::InvalidRequest("Invalid refresh_token.".to_string()).into());
     }
 
+    // A bound refresh token MUST be presented with the same keys.
+    let presented = cmd.cnf.clone().unwrap_or_default();
+    if refresh_token.jkt.is_some() && refresh_token.jkt != presented.jkt {
+        return Err(ek::InvalidDpopProof(
+            "The refresh token is bound to another DPoP key.".to_string(),
+        ).into());
+    }
+    if refresh_token.x5t_s256.is_some() && refresh_token.x5t_s256 != presented.x5t_s256 {
+        return Err(ek::InvalidGrant(
+            "The refresh token is bound to another client certificate.".to_string(),
+        ).into());
+    }
+
     // Refresh access token
     let access_token = access_token_repo
         .find_by_id(&refresh_token.access_token_id)
         .and_then(|v| v.ok_or(ek::InvalidRequest("Access token not found.".to_string()).into()))?;
-    let access_token = access_token.update();
+    // The refresh token MUST be issued to the authenticated client.
+    if !refresh_token.is_issued_to(&client.id, Some(&access_token.client_id)) {
+        return Err(ek::InvalidGrant(format!(
+            "The refresh token was not issued to the client. ID => {}",
+            client.id
+        )).into());
+    }
+    // The new access token is bound to the keys presented with this request.
+    let access_token = AccessToken {
+        cnf: refreshed_confirmation(client, &access_token.cnf, &cmd.cnf)?,
+        ..access_token.update()
+    };
     access_token_repo.update(&access_token)?;
 
     // Result
@@ -137,10 +143,12 @@ fn process_refresh_token(
 
         // Refresh id token
         let id_token = id_token.update(
-            &client,
+            client,
             &end_user,
-            key_service.jwt_public_key(),
-            key_service.jwt_private_key(),
+            &access_token.scope,
+            access_token.id_token_claims(),
+            &key_service.verification_keys()?,
+            &key_service.active_signing_key()?,
         )?;
         id_token_repo.update(&id_token)?;
 
@@ -150,3 +158,93 @@ fn process_refresh_token(
     builder = builder.refresh_token(&Some(refresh_token));
     Ok(builder.build())
 }
+
+/// Returns the confirmation of the refreshed access token.
+/// A bound access token is not downgraded to a bearer token by refreshing it without the keys.
+fn refreshed_confirmation(
+    client: &Client,
+    current: &Option<Confirmation>,
+    presented: &Option<Confirmation>,
+) -> Result<Option<Confirmation>, ed::Error> {
+    let current = current.clone().unwrap_or_default();
+    let presented = presented.clone().unwrap_or_default();
+    if current.jkt.is_some() && presented.jkt.is_none() {
+        return Err(ek::InvalidDpopProof(
+            "The access token is bound to a DPoP key. The DPoP proof is required.".to_string(),
+        ).into());
+    }
+    if current.x5t_s256.is_some() && presented.x5t_s256.is_none() {
+        return Err(ek::InvalidGrant(
+            "The access token is bound to a client certificate. The certificate is required."
+                .to_string(),
+        ).into());
+    }
+
+    let cnf = client
+        .confirmation(&Some(presented.clone()))?
+        .unwrap_or_default();
+    let x5t_s256 = if current.x5t_s256.is_some() {
+        presented.x5t_s256
+    } else {
+        cnf.x5t_s256
+    };
+    if cnf.jkt.is_none() && x5t_s256.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(Confirmation {
+        jkt: cnf.jkt,
+        x5t_s256,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use domain::model::{test_client, ClientMetadata};
+
+    fn client() -> Client {
+        test_client("client", ClientMetadata::default())
+    }
+
+    fn cnf(jkt: Option<&str>, x5t_s256: Option<&str>) -> Option<Confirmation> {
+        Some(Confirmation {
+            jkt: jkt.map(|v| v.to_string()),
+            x5t_s256: x5t_s256.map(|v| v.to_string()),
+        })
+    }
+
+    #[test]
+    fn test_refreshed_confirmation_keeps_dpop_binding() {
+        let ret = refreshed_confirmation(
+            &client(),
+            &cnf(Some("key"), None),
+            &cnf(Some("key"), None),
+        );
+        assert_eq!(ret.unwrap().and_then(|c| c.jkt), Some("key".to_string()));
+
+        let e = refreshed_confirmation(&client(), &cnf(Some("key"), None), &None).unwrap_err();
+        assert_eq!(e.description(), "invalid_dpop_proof");
+    }
+
+    #[test]
+    fn test_refreshed_confirmation_keeps_certificate_binding() {
+        let ret = refreshed_confirmation(
+            &client(),
+            &cnf(None, Some("cert")),
+            &cnf(None, Some("cert")),
+        );
+        assert_eq!(ret.unwrap().and_then(|c| c.x5t_s256), Some("cert".to_string()));
+
+        let e = refreshed_confirmation(&client(), &cnf(None, Some("cert")), &None).unwrap_err();
+        assert_eq!(e.description(), "invalid_grant");
+    }
+
+    #[test]
+    fn test_refreshed_confirmation_binds_bearer_token() {
+        assert!(refreshed_confirmation(&client(), &None, &None).unwrap().is_none());
+
+        let ret = refreshed_confirmation(&client(), &None, &cnf(Some("key"), None));
+        assert_eq!(ret.unwrap().and_then(|c| c.jkt), Some("key".to_string()));
+    }
+}
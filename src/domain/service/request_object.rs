@@ -0,0 +1,211 @@
+use serde_json;
+
+use config::AppConfig;
+use domain::error::domain as ed;
+use self::ed::ErrorKind as ek;
+use domain::model::{Client, RequestObject};
+use domain::service::AuthorizeCmd;
+
+/// `RequestObjectFetcher` fetches a request object from the `request_uri` of a client.
+pub trait RequestObjectFetcher {
+    fn fetch(&self, request_uri: &str) -> Result<String, ed::Error>;
+}
+
+pub trait RequestObjectFetcherComponent {
+    type RequestObjectFetcher: RequestObjectFetcher;
+    fn request_object_fetcher(&self) -> &Self::RequestObjectFetcher;
+}
+
+/// Returns the authorization request merged with the request object
+/// passed by `request` or `request_uri`.
+/// The parameters in the request object take precedence over the query parameters.
+pub fn resolve_request_object(
+    cmd: &AuthorizeCmd,
+    client: &Client,
+    fetcher: &RequestObjectFetcher,
+) -> Result<AuthorizeCmd, ed::Error> {
+    let token = match (&cmd.request, &cmd.request_uri) {
+        (&Some(_), &Some(_)) => {
+            return Err(ek::InvalidRequest(
+                "request and request_uri cannot be used together.".to_string(),
+            ).into())
+        }
+        (&Some(ref request), &None) => request.clone(),
+        (&None, &Some(ref request_uri)) => {
+            validate_request_uri(request_uri, client)?;
+            fetcher.fetch(request_uri).map_err(|e| {
+                ek::InvalidRequestUri(format!("Fetching the request object failed: {}", e))
+            })?
+        }
+        (&None, &None) => return Ok(cmd.clone()),
+    };
+    let object = RequestObject::decode(token.trim(), client)?;
+    object.validate(&AppConfig::issuer(), AppConfig::request_object_max_age_sec())?;
+    merge_request_object(cmd, &object)
+}
+
+/// The request_uri MUST exactly match one of the request_uris registered by the client,
+/// so that this server never fetches URIs chosen by the request.
+/// Clients registered before https was required may still have plain http request_uris.
+fn validate_request_uri(request_uri: &String, client: &Client) -> Result<(), ed::Error> {
+    if !request_uri.starts_with("https://") {
+        return Err(ek::InvalidRequestUri("request_uri must use https.".to_string()).into());
+    }
+    if !client.metadata.request_uris.contains(request_uri) {
+        return Err(
+            ek::InvalidRequestUri(format!("Unregistered request_uri: {}", request_uri)).into(),
+        );
+    }
+    Ok(())
+}
+
+fn merge_request_object(
+    cmd: &AuthorizeCmd,
+    object: &RequestObject,
+) -> Result<AuthorizeCmd, ed::Error> {
+    // client_id and response_type are still required as query parameters
+    if object
+        .response_type
+        .as_ref()
+        .map_or(false, |r| r != &cmd.response_type)
+    {
+        return Err(ek::InvalidRequestObject("response_type does not match.".to_string()).into());
+    }
+    let claims = match object.claims {
+        Some(ref c) => Some(serde_json::to_string(c).map_err(|_| {
+            ek::InvalidRequestObject("Invalid claims.".to_string())
+        })?),
+        None => cmd.claims.clone(),
+    };
+    Ok(AuthorizeCmd {
+        redirect_uri: object
+            .redirect_uri
+            .clone()
+            .unwrap_or_else(|| cmd.redirect_uri.clone()),
+        scope: object.scope.clone().or_else(|| cmd.scope.clone()),
+        state: object.state.clone().or_else(|| cmd.state.clone()),
+        nonce: object.nonce.clone().or_else(|| cmd.nonce.clone()),
+        code_challenge: object
+            .code_challenge
+            .clone()
+            .or_else(|| cmd.code_challenge.clone()),
+        code_challenge_method: object
+            .code_challenge_method
+            .clone()
+            .or_else(|| cmd.code_challenge_method.clone()),
+        prompt: object.prompt.clone().or_else(|| cmd.prompt.clone()),
+        max_age: object
+            .max_age
+            .map(|m| m.to_string())
+            .or_else(|| cmd.max_age.clone()),
+        acr_values: object.acr_values.clone().or_else(|| cmd.acr_values.clone()),
+        claims,
+        response_mode: object
+            .response_mode
+            .clone()
+            .or_else(|| cmd.response_mode.clone()),
+        request: None,
+        request_uri: None,
+        ..cmd.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use domain::model::{test_client, ClientMetadata};
+
+    struct StubFetcher {
+        body: Option<String>,
+    }
+
+    impl RequestObjectFetcher for StubFetcher {
+        fn fetch(&self, request_uri: &str) -> Result<String, ed::Error> {
+            self.body.clone().ok_or(ek::ServerError(request_uri.to_string()).into())
+        }
+    }
+
+    fn authorize_cmd() -> AuthorizeCmd {
+        AuthorizeCmd {
+            end_user_id: Some("end_user".to_string()),
+            client_id: "client".to_string(),
+            response_type: "code".to_string(),
+            redirect_uri: "https://client.example.com/cb".to_string(),
+            scope: Some("openid".to_string()),
+            state: Some("query".to_string()),
+            nonce: None,
+            code_challenge: None,
+            code_challenge_method: None,
+            prompt: None,
+            max_age: None,
+            acr_values: None,
+            claims: None,
+            response_mode: None,
+            request: None,
+            request_uri: None,
+            sid: None,
+            auth_time: None,
+            amr: None,
+        }
+    }
+
+    fn client(request_uris: Vec<String>) -> Client {
+        let metadata = ClientMetadata {
+            request_uris,
+            ..ClientMetadata::default()
+        };
+        test_client("client", metadata)
+    }
+
+    #[test]
+    fn should_merge_request_object() {
+        let object = RequestObject {
+            scope: Some("openid email".to_string()),
+            max_age: Some(60),
+            claims: Some(serde_json::from_str(r#"{"userinfo": {"email": null}}"#).unwrap()),
+            ..RequestObject::default()
+        };
+        let cmd = merge_request_object(&authorize_cmd(), &object).unwrap();
+        assert_eq!(cmd.scope, Some("openid email".to_string()));
+        assert_eq!(cmd.state, Some("query".to_string()));
+        assert_eq!(cmd.max_age, Some("60".to_string()));
+        assert_eq!(cmd.claims, Some(r#"{"userinfo":{"email":null}}"#.to_string()));
+
+        let object = RequestObject {
+            response_type: Some("token".to_string()),
+            ..RequestObject::default()
+        };
+        assert!(merge_request_object(&authorize_cmd(), &object).is_err());
+    }
+
+    #[test]
+    fn should_fetch_only_registered_request_uri() {
+        let registered = "https://client.example.com/request".to_string();
+        let client_without_request_uris = client(vec![]);
+        let client = client(vec![registered.clone()]);
+        let fetcher = StubFetcher {
+            body: Some("not a jwt".to_string()),
+        };
+        let mut cmd = authorize_cmd();
+
+        cmd.request_uri = Some("https://attacker.example.com/request".to_string());
+        let e = resolve_request_object(&cmd, &client, &fetcher).unwrap_err();
+        assert_eq!(e.description(), "invalid_request_uri");
+
+        // The fragment is a part of the registered value.
+        cmd.request_uri = Some(format!("{}#abc", registered));
+        let e = resolve_request_object(&cmd, &client, &fetcher).unwrap_err();
+        assert_eq!(e.description(), "invalid_request_uri");
+
+        cmd.request_uri = Some(registered.clone());
+        let e = resolve_request_object(&cmd, &client, &fetcher).unwrap_err();
+        assert_eq!(e.description(), "invalid_request_object");
+
+        // A client without request_uris can't use request_uri.
+        let e = resolve_request_object(&cmd, &client_without_request_uris, &fetcher).unwrap_err();
+        assert_eq!(e.description(), "invalid_request_uri");
+
+        let e = resolve_request_object(&cmd, &client, &StubFetcher { body: None }).unwrap_err();
+        assert_eq!(e.description(), "invalid_request_uri");
+    }
+}
@@ -1,37 +1,37 @@
 use domain::error::domain as ed;
 use self::ed::ErrorKind as ek;
-use domain::model::{AccessToken, Resource};
-use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent, ClientRepository,
-                         ClientRepositoryComponent, EndUserRepository, EndUserRepositoryComponent,
-                         ResourceRepository, ResourceRepositoryComponent};
+use domain::model::{AccessToken, AuthenticationLevel, AuthenticationMethod, Client, Confirmation,
+                    Resource};
+use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent,
+                         EndUserRepository, EndUserRepositoryComponent, ResourceRepository,
+                         ResourceRepositoryComponent};
 use domain::service::TokensRet;
 
 pub struct ResourceOwnerPasswordCredentialsCmd {
-    pub client_id: Option<String>,
-    pub client_secret: Option<String>,
     pub username: Option<String>,
     pub password: Option<String>,
     pub scope: Option<String>,
+    /// The confirmation of the DPoP proof key which the tokens are bound to.
+    pub cnf: Option<Confirmation>,
 }
 
 pub trait ResourceOwnerPasswordCredentialsService
     : AccessTokenRepositoryComponent
-    + ClientRepositoryComponent
     + EndUserRepositoryComponent
     + ResourceRepositoryComponent {
     fn execute_resource_owner_password_credentials(
         &self,
+        client: &Client,
         cmd: &ResourceOwnerPasswordCredentialsCmd,
     ) -> TokensRet {
         let access_token_repo = self.access_token_repository();
-        let client_repo = self.client_repository();
         let end_user_repo = self.end_user_repository();
         let resource_repo = self.resource_repository();
 
         let ret = process_resource_owner_password_credentials(
             cmd,
+            client,
             access_token_repo,
-            client_repo,
             end_user_repo,
             resource_repo,
         );
@@ -52,7 +52,6 @@ pub trait ResourceOwnerPasswordCredentialsServiceComponent {
 
 impl<
     T: AccessTokenRepositoryComponent
-        + ClientRepositoryComponent
         + EndUserRepositoryComponent
         + ResourceRepositoryComponent,
 > ResourceOwnerPasswordCredentialsService for T
@@ -62,25 +61,11 @@ impl<
 // Private functions
 fn process_resource_owner_password_credentials(
     cmd: &ResourceOwnerPasswordCredentialsCmd,
+    client: &Client,
     access_token_repo: &AccessTokenRepository,
-    client_repo: &ClientRepository,
     end_user_repo: &EndUserRepository,
     resource_repo: &ResourceRepository,
 ) -> Result<TokensRet, ed::Error> {
-    // Authorize the client
-    let (client_id, client_secret) = cmd.client_id
-        .as_ref()
-        .and_then(|id| cmd.client_secret.as_ref().map(|secret| (id, secret)))
-        .ok_or(ek::UnauthorizedClient("Invalid credentials.".to_string()))?;
-    let client = client_repo.find_by_id(&client_id).and_then(|c| {
-        c.ok_or(ek::UnauthorizedClient(format!("Client not found. ID => {}", client_id)).into())
-    })?;
-    if !client.is_authenticated_by_secret(&client_secret) {
-        return Err(
-            ek::UnauthorizedClient(format!("Client not authorized. ID => {}", client_id)).into(),
-        );
-    }
-
     // Authorize the end user
     let (name, password) = cmd.username
         .as_ref()
@@ -113,8 +98,12 @@ fn process_resource_owner_password_credentials(
         .map_or(Vec::new(), |s| validate_scope_str(&s, &resource));
 
     // Access token
-    let access_token = AccessToken::builder(&client_id, &resource.id)
+    let methods = vec![AuthenticationMethod::Pwd];
+    let access_token = AccessToken::builder(&client.id, &resource.id)
         .scope(&scope)
+        .acr(&Some(AuthenticationLevel::from_methods(&methods).to_str().to_string()))
+        .amr(&Some(AuthenticationMethod::to_amr(&methods)))
+        .cnf(&client.confirmation(&cmd.cnf)?)
         .build();
     access_token_repo.add(&access_token)?;
 
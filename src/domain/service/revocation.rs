@@ -0,0 +1,130 @@
+use domain::error::domain as ed;
+use self::ed::ErrorKind as ek;
+use domain::model::Client;
+use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent,
+                         IdTokenRepository, IdTokenRepositoryComponent, RefreshTokenRepository,
+                         RefreshTokenRepositoryComponent};
+use domain::service::{ClientAuthentication, ClientAuthenticationService,
+                      ClientAuthenticationServiceComponent};
+
+pub struct RevocationCmd {
+    pub client_auth: ClientAuthentication,
+    pub token: String,
+    pub token_type_hint: Option<String>,
+}
+
+/// `RevocationService` provides functions for Token Revocation (RFC 7009).
+pub trait RevocationService
+    : AccessTokenRepositoryComponent
+    + IdTokenRepositoryComponent
+    + RefreshTokenRepositoryComponent
+    + ClientAuthenticationServiceComponent {
+    /// Revokes an access token or a refresh token.
+    /// Invalid tokens are ignored because the purpose of the request has been achieved.
+    fn revoke(&self, cmd: &RevocationCmd) -> Result<(), ed::Error> {
+        let access_token_repo = self.access_token_repository();
+        let id_token_repo = self.id_token_repository();
+        let refresh_token_repo = self.refresh_token_repository();
+
+        // Authorize the client
+        let client = self.client_authentication_service()
+            .authenticate_client(&cmd.client_auth)?;
+
+        // The hint is only used to decide the lookup order.
+        let refresh_token_first = cmd.token_type_hint
+            .as_ref()
+            .map_or(false, |h| h == "refresh_token");
+        if refresh_token_first {
+            if !revoke_refresh_token(
+                &cmd.token,
+                &client,
+                access_token_repo,
+                id_token_repo,
+                refresh_token_repo,
+            )? {
+                revoke_access_token(&cmd.token, &client, access_token_repo)?;
+            }
+        } else if !revoke_access_token(&cmd.token, &client, access_token_repo)? {
+            revoke_refresh_token(
+                &cmd.token,
+                &client,
+                access_token_repo,
+                id_token_repo,
+                refresh_token_repo,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub trait RevocationServiceComponent {
+    type RevocationService: RevocationService;
+    fn revocation_service(&self) -> &Self::RevocationService;
+}
+
+// Implement
+impl<
+    T: AccessTokenRepositoryComponent
+        + IdTokenRepositoryComponent
+        + RefreshTokenRepositoryComponent
+        + ClientAuthenticationServiceComponent,
+> RevocationService for T
+{
+}
+
+// Private functions
+/// Revokes the access token. Returns false if the token is not found.
+fn revoke_access_token(
+    token: &String,
+    client: &Client,
+    access_token_repo: &AccessTokenRepository,
+) -> Result<bool, ed::Error> {
+    let access_token = match access_token_repo.find_by_token(token)? {
+        Some(t) => t,
+        None => return Ok(false),
+    };
+    if access_token.client_id != client.id {
+        return Err(ek::UnauthorizedClient(format!(
+            "The token was not issued to the client. ID => {}",
+            client.id
+        )).into());
+    }
+    access_token_repo.remove(access_token)?;
+    Ok(true)
+}
+
+/// Revokes the refresh token with the linked access token and id token.
+/// Returns false if the token is not found.
+fn revoke_refresh_token(
+    token: &String,
+    client: &Client,
+    access_token_repo: &AccessTokenRepository,
+    id_token_repo: &IdTokenRepository,
+    refresh_token_repo: &RefreshTokenRepository,
+) -> Result<bool, ed::Error> {
+    let refresh_token = match refresh_token_repo.find_by_token(token)? {
+        Some(t) => t,
+        None => return Ok(false),
+    };
+
+    let access_token = access_token_repo.find_by_id(&refresh_token.access_token_id)?;
+    if !refresh_token.is_issued_to(&client.id, access_token.as_ref().map(|t| &t.client_id)) {
+        return Err(ek::UnauthorizedClient(format!(
+            "The token was not issued to the client. ID => {}",
+            client.id
+        )).into());
+    }
+
+    let id_token = match refresh_token.id_token_id {
+        Some(ref id) => id_token_repo.find_by_id(id)?,
+        None => None,
+    };
+    refresh_token_repo.remove(refresh_token)?;
+    if let Some(t) = access_token {
+        access_token_repo.remove(t)?;
+    }
+    if let Some(t) = id_token {
+        id_token_repo.remove(t)?;
+    }
+    Ok(true)
+}
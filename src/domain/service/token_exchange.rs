@@ -0,0 +1,464 @@
+use chrono::prelude::*;
+
+use config::AppConfig;
+use domain::error::domain as ed;
+use self::ed::ErrorKind as ek;
+use domain::model::{AccessToken, Actor, Client, Confirmation, Resource};
+use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent,
+                         ResourceRepository, ResourceRepositoryComponent};
+use domain::service::TokensRet;
+
+/// The token type of access tokens issued by this server.
+pub const TOKEN_TYPE_ACCESS_TOKEN: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+pub struct TokenExchangeCmd {
+    pub subject_token: Option<String>,
+    pub subject_token_type: Option<String>,
+    pub actor_token: Option<String>,
+    pub actor_token_type: Option<String>,
+    pub resource: Option<String>,
+    pub audience: Option<String>,
+    pub requested_token_type: Option<String>,
+    pub scope: Option<String>,
+    /// The confirmation of the DPoP proof key which the tokens are bound to.
+    pub cnf: Option<Confirmation>,
+}
+
+/// `TokenExchangeService` provides functions for Token Exchange (RFC 8693).
+pub trait TokenExchangeService
+    : AccessTokenRepositoryComponent
+    + ResourceRepositoryComponent {
+    /// Exchanges the subject token for a token of the target resource.
+    fn execute_token_exchange(&self, client: &Client, cmd: &TokenExchangeCmd) -> TokensRet {
+        let access_token_repo = self.access_token_repository();
+        let resource_repo = self.resource_repository();
+
+        let ret = process_token_exchange(
+            cmd,
+            client,
+            AppConfig::default_access_token_max_age_sec(),
+            access_token_repo,
+            resource_repo,
+        );
+
+        match ret {
+            Ok(r) => r,
+            Err(e) => TokensRet::error(e),
+        }
+    }
+}
+
+pub trait TokenExchangeServiceComponent {
+    type TokenExchangeService: TokenExchangeService;
+    fn token_exchange_service(&self) -> &Self::TokenExchangeService;
+}
+
+impl<
+    T: AccessTokenRepositoryComponent
+        + ResourceRepositoryComponent,
+> TokenExchangeService for T
+{
+}
+
+// Private functions
+fn process_token_exchange(
+    cmd: &TokenExchangeCmd,
+    client: &Client,
+    access_token_max_age_sec: i64,
+    access_token_repo: &AccessTokenRepository,
+    resource_repo: &ResourceRepository,
+) -> Result<TokensRet, ed::Error> {
+    // Only access tokens issued by this server can be exchanged.
+    if let Some(ref t) = cmd.requested_token_type {
+        if t != TOKEN_TYPE_ACCESS_TOKEN {
+            return Err(ek::InvalidRequest(format!(
+                "Unsupported requested_token_type: {}",
+                t
+            )).into());
+        }
+    }
+    let subject_token = find_token(
+        &cmd.subject_token,
+        &cmd.subject_token_type,
+        "subject_token",
+        access_token_repo,
+    )?.ok_or(ek::InvalidRequest("subject_token is required.".to_string()))?;
+
+    // The subject token MUST be issued to the client
+    // or to the clients which admins allowed the client to act for.
+    let delegated = client
+        .metadata
+        .token_exchange_subject_clients
+        .contains(&subject_token.client_id);
+    if subject_token.client_id != client.id && !delegated {
+        return Err(ek::InvalidGrant(
+            "The subject_token is not intended for the client.".to_string(),
+        ).into());
+    }
+
+    // The actor is prepended to the chain of the subject token.
+    let actor_token = find_token(
+        &cmd.actor_token,
+        &cmd.actor_token_type,
+        "actor_token",
+        access_token_repo,
+    )?;
+    // The client can't present tokens of other actors.
+    if let Some(ref a) = actor_token {
+        if a.client_id != client.id {
+            return Err(ek::InvalidGrant(
+                "The actor_token was not issued to the client.".to_string(),
+            ).into());
+        }
+    }
+    let act = match actor_token {
+        Some(ref a) => Some(Actor {
+            sub: a.end_user_id.clone().unwrap_or(a.client_id.clone()),
+            act: subject_token.act.clone().map(Box::new),
+        }),
+        None => subject_token.act.clone(),
+    };
+
+    // Target resource
+    let resource = find_target(cmd, client, &subject_token, resource_repo)?;
+
+    // Scope is narrowed to the subject token and the target resource.
+    let available = resource.filter_scope(&subject_token.scope);
+    let scope = match cmd.scope {
+        Some(ref s) => {
+            let requested: Vec<String> = s.split(" ").map(|s| s.to_string()).collect();
+            if let Some(s) = requested.iter().find(|s| !available.contains(*s)) {
+                return Err(ek::InvalidScope(format!("The scope cannot be granted: {}", s)).into());
+            }
+            requested
+        }
+        None => available,
+    };
+
+    // The issued token never outlives the subject token.
+    let remaining = subject_token.expires_at().timestamp() - Utc::now().timestamp();
+    let expires_in = remaining.min(access_token_max_age_sec);
+
+    // Access token
+    let access_token = AccessToken::builder(&client.id, &resource.id)
+        .expires_in(expires_in)
+        .end_user_id(&subject_token.end_user_id)
+        .scope(&scope)
+        .acr(&subject_token.acr)
+        .amr(&subject_token.amr)
+        .auth_time(&subject_token.auth_time)
+        .act(&act)
+        .cnf(&client.confirmation(&cmd.cnf)?)
+        .build();
+    access_token_repo.add(&access_token)?;
+
+    Ok(TokensRet::builder(&access_token)
+        .issued_token_type(TOKEN_TYPE_ACCESS_TOKEN)
+        .build())
+}
+
+/// Returns the valid access token presented as the `name` parameter.
+fn find_token(
+    token: &Option<String>,
+    token_type: &Option<String>,
+    name: &str,
+    access_token_repo: &AccessTokenRepository,
+) -> Result<Option<AccessToken>, ed::Error> {
+    let token = match *token {
+        Some(ref t) => t,
+        None => return Ok(None),
+    };
+    match *token_type {
+        Some(ref t) if t == TOKEN_TYPE_ACCESS_TOKEN => {}
+        Some(ref t) => {
+            return Err(ek::InvalidRequest(format!("Unsupported {}_type: {}", name, t)).into())
+        }
+        None => return Err(ek::InvalidRequest(format!("{}_type is required.", name)).into()),
+    }
+    match access_token_repo.find_by_token(token)? {
+        Some(ref t) if t.is_valid() => Ok(Some(t.clone())),
+        _ => Err(ek::InvalidGrant(format!("Invalid {}.", name)).into()),
+    }
+}
+
+/// Finds the resource by `resource` as its website or `audience` as its name.
+/// The resource of the subject token is the default target,
+/// and the other resources MUST be allowed for the client by admins.
+fn find_target(
+    cmd: &TokenExchangeCmd,
+    client: &Client,
+    subject_token: &AccessToken,
+    resource_repo: &ResourceRepository,
+) -> Result<Resource, ed::Error> {
+    let (found, target) = match (&cmd.resource, &cmd.audience) {
+        (&Some(ref r), _) => (resource_repo.find_by_website(r)?, r),
+        (&None, &Some(ref a)) => (resource_repo.find_by_name(a)?, a),
+        (&None, &None) => (
+            resource_repo.find_by_id(&subject_token.resource_id)?,
+            &subject_token.resource_id,
+        ),
+    };
+    let resource = found.ok_or(ek::InvalidTarget(format!("Unknown target: {}", target)))?;
+
+    // Both of resource and audience MUST indicate the same resource.
+    if let (&Some(_), &Some(ref a)) = (&cmd.resource, &cmd.audience) {
+        if a != &resource.name {
+            return Err(ek::InvalidTarget(format!("Unknown audience: {}", a)).into());
+        }
+    }
+
+    let allowed = resource.id == subject_token.resource_id
+        || client.metadata.token_exchange_resources.contains(&resource.id);
+    if !allowed {
+        return Err(ek::InvalidTarget(format!(
+            "The client is not allowed to access the target: {}",
+            target
+        )).into());
+    }
+    Ok(resource)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use std::cell::RefCell;
+
+    use domain::model::{test_client, ClientMetadata, Scope};
+
+    struct AccessTokenRepositoryStub {
+        tokens: RefCell<Vec<AccessToken>>,
+    }
+
+    impl AccessTokenRepository for AccessTokenRepositoryStub {
+        fn find_by_id(&self, id: &String) -> Result<Option<AccessToken>, ed::Error> {
+            Ok(self.tokens.borrow().iter().find(|t| &t.id == id).cloned())
+        }
+        fn find_by_token(&self, token: &String) -> Result<Option<AccessToken>, ed::Error> {
+            Ok(self.tokens.borrow().iter().find(|t| &t.token == token).cloned())
+        }
+        fn find_all(&self) -> Result<Vec<AccessToken>, ed::Error> {
+            Ok(self.tokens.borrow().clone())
+        }
+        fn add(&self, model: &AccessToken) -> Result<(), ed::Error> {
+            self.tokens.borrow_mut().push(model.clone());
+            Ok(())
+        }
+        fn update(&self, _model: &AccessToken) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn remove(&self, _model: AccessToken) -> Result<(), ed::Error> {
+            Ok(())
+        }
+    }
+
+    struct ResourceRepositoryStub {
+        resources: Vec<Resource>,
+    }
+
+    impl ResourceRepository for ResourceRepositoryStub {
+        fn find_by_id(&self, id: &String) -> Result<Option<Resource>, ed::Error> {
+            Ok(self.resources.iter().find(|r| &r.id == id).cloned())
+        }
+        fn find_by_name(&self, name: &String) -> Result<Option<Resource>, ed::Error> {
+            Ok(self.resources.iter().find(|r| &r.name == name).cloned())
+        }
+        fn find_by_website(&self, website: &String) -> Result<Option<Resource>, ed::Error> {
+            Ok(self.resources.iter().find(|r| &r.website == website).cloned())
+        }
+        fn find_all(&self) -> Result<Vec<Resource>, ed::Error> {
+            Ok(self.resources.clone())
+        }
+        fn add(&self, _model: &Resource) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn update(&self, _model: &Resource) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn remove(&self, _model: Resource) -> Result<(), ed::Error> {
+            Ok(())
+        }
+    }
+
+    fn resource(id: &str) -> Resource {
+        let mut resource = Resource::builder(
+            &id.to_string(),
+            &"password".to_string(),
+            &format!("https://{}.example.com", id),
+        ).scope(&vec![
+            Scope {
+                name: "read".to_string(),
+                description: "read".to_string(),
+            },
+        ])
+            .build();
+        resource.id = id.to_string();
+        resource
+    }
+
+    fn token(token: &str, client_id: &str, expires_in: i64) -> AccessToken {
+        AccessToken {
+            id: token.to_string(),
+            client_id: client_id.to_string(),
+            resource_id: "api".to_string(),
+            token: token.to_string(),
+            expires_in,
+            created_at: Utc::now(),
+            scope: vec!["read".to_string()],
+            end_user_id: Some("end_user".to_string()),
+            state: None,
+            nonce: None,
+            acr: None,
+            amr: None,
+            auth_time: None,
+            claims: None,
+            act: None,
+            cnf: None,
+            is_deleted: false,
+        }
+    }
+
+    fn cmd(subject_token: &str) -> TokenExchangeCmd {
+        TokenExchangeCmd {
+            subject_token: Some(subject_token.to_string()),
+            subject_token_type: Some(TOKEN_TYPE_ACCESS_TOKEN.to_string()),
+            actor_token: None,
+            actor_token_type: None,
+            resource: None,
+            audience: None,
+            requested_token_type: None,
+            scope: None,
+            cnf: None,
+        }
+    }
+
+    fn repositories() -> (AccessTokenRepositoryStub, ResourceRepositoryStub) {
+        let access_token_repo = AccessTokenRepositoryStub {
+            tokens: RefCell::new(vec![
+                token("subject", "client", 600),
+                token("other_subject", "other", 600),
+                token("actor", "client", 3600),
+                token("other_actor", "other", 3600),
+            ]),
+        };
+        let resource_repo = ResourceRepositoryStub {
+            resources: vec![resource("api"), resource("backend"), resource("admin")],
+        };
+        (access_token_repo, resource_repo)
+    }
+
+    fn issued(access_token_repo: &AccessTokenRepositoryStub) -> AccessToken {
+        access_token_repo.tokens.borrow().last().unwrap().clone()
+    }
+
+    #[test]
+    fn should_exchange_token() {
+        let (access_token_repo, resource_repo) = repositories();
+        let metadata = ClientMetadata {
+            token_exchange_resources: vec!["backend".to_string()],
+            ..ClientMetadata::default()
+        };
+        let client = test_client("client", metadata);
+        let cmd = TokenExchangeCmd {
+            actor_token: Some("actor".to_string()),
+            actor_token_type: Some(TOKEN_TYPE_ACCESS_TOKEN.to_string()),
+            audience: Some("backend".to_string()),
+            ..cmd("subject")
+        };
+
+        let ret = process_token_exchange(&cmd, &client, 3600, &access_token_repo, &resource_repo);
+        assert!(ret.is_ok());
+        let issued = issued(&access_token_repo);
+        assert_eq!(issued.resource_id, "backend");
+        assert_eq!(issued.end_user_id, Some("end_user".to_string()));
+        assert_eq!(issued.scope, vec!["read".to_string()]);
+        assert_eq!(issued.act.map(|a| a.sub), Some("end_user".to_string()));
+        // The lifetime is capped at the expiry of the subject token.
+        assert!(issued.expires_in <= 600 && issued.expires_in > 590);
+    }
+
+    #[test]
+    fn should_exchange_token_of_delegated_client() {
+        let (access_token_repo, resource_repo) = repositories();
+        let delegated = test_client(
+            "client",
+            ClientMetadata {
+                token_exchange_subject_clients: vec!["other".to_string()],
+                ..ClientMetadata::default()
+            },
+        );
+        let not_delegated = test_client("client", ClientMetadata::default());
+
+        let e = process_token_exchange(
+            &cmd("other_subject"),
+            &not_delegated,
+            3600,
+            &access_token_repo,
+            &resource_repo,
+        ).err()
+            .unwrap();
+        assert_eq!(e.description(), "invalid_grant");
+        let ret = process_token_exchange(
+            &cmd("other_subject"),
+            &delegated,
+            3600,
+            &access_token_repo,
+            &resource_repo,
+        );
+        assert!(ret.is_ok());
+    }
+
+    #[test]
+    fn should_reject_target_not_allowed() {
+        let (access_token_repo, resource_repo) = repositories();
+        let metadata = ClientMetadata {
+            token_exchange_resources: vec!["backend".to_string()],
+            ..ClientMetadata::default()
+        };
+        let client = test_client("client", metadata);
+        let cmd = TokenExchangeCmd {
+            resource: Some("https://admin.example.com".to_string()),
+            ..cmd("subject")
+        };
+
+        let e = process_token_exchange(&cmd, &client, 3600, &access_token_repo, &resource_repo)
+            .err()
+            .unwrap();
+        assert_eq!(e.description(), "invalid_target");
+    }
+
+    #[test]
+    fn should_reject_actor_token_of_other_client() {
+        let (access_token_repo, resource_repo) = repositories();
+        let client = test_client("client", ClientMetadata::default());
+        let cmd = TokenExchangeCmd {
+            actor_token: Some("other_actor".to_string()),
+            actor_token_type: Some(TOKEN_TYPE_ACCESS_TOKEN.to_string()),
+            ..cmd("subject")
+        };
+
+        let e = process_token_exchange(&cmd, &client, 3600, &access_token_repo, &resource_repo)
+            .err()
+            .unwrap();
+        assert_eq!(e.description(), "invalid_grant");
+    }
+
+    #[test]
+    fn should_reject_expired_subject_token() {
+        let (access_token_repo, resource_repo) = repositories();
+        let mut expired = token("expired", "client", 600);
+        expired.created_at = Utc::now() - Duration::seconds(601);
+        access_token_repo.add(&expired).unwrap();
+        let client = test_client("client", ClientMetadata::default());
+
+        let e = process_token_exchange(
+            &cmd("expired"),
+            &client,
+            3600,
+            &access_token_repo,
+            &resource_repo,
+        ).err()
+            .unwrap();
+        assert_eq!(e.description(), "invalid_grant");
+    }
+}
@@ -7,6 +7,8 @@ pub enum TokensRetKind {
     Token {
         access_token: String,
         #[serde(skip_serializing_if = "Option::is_none")]
+        issued_token_type: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         refresh_token: Option<String>,
         token_type: String,
         expires_in: i64,
@@ -43,7 +45,9 @@ impl TokensRet {
 
 pub struct AuthorizeRetBuilder {
     access_token: String,
+    token_type: String,
     expires_in: i64,
+    issued_token_type: Option<String>,
     refresh_token: Option<String>,
     id_token: Option<String>,
 }
@@ -52,7 +56,9 @@ impl AuthorizeRetBuilder {
     fn new(access_token: &AccessToken) -> Self {
         AuthorizeRetBuilder {
             access_token: access_token.token.clone(),
+            token_type: access_token.token_type().to_string(),
             expires_in: access_token.expires_in,
+            issued_token_type: None,
             refresh_token: None,
             id_token: None,
         }
@@ -72,11 +78,19 @@ impl AuthorizeRetBuilder {
         }
     }
 
+    pub fn issued_token_type(self, issued_token_type: &str) -> Self {
+        AuthorizeRetBuilder {
+            issued_token_type: Some(issued_token_type.to_string()),
+            ..self
+        }
+    }
+
     pub fn build(self) -> TokensRet {
         let kind = TokensRetKind::Token {
             access_token: self.access_token,
+            issued_token_type: self.issued_token_type,
             refresh_token: self.refresh_token,
-            token_type: "Bearer".to_string(),
+            token_type: self.token_type,
             expires_in: self.expires_in,
             id_token: self.id_token,
         };
@@ -1,47 +1,35 @@
 use config::AppConfig;
 use domain::error::domain as ed;
 use self::ed::ErrorKind as ek;
-use domain::model::EndUserClaims;
-use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent, EndUserRepository,
+use domain::model::{ClientCertificate, EndUserClaims};
+use domain::repository::{AccessTokenRepository, AccessTokenRepositoryComponent,
+                         DpopProofRepository, DpopProofRepositoryComponent, EndUserRepository,
                          EndUserRepositoryComponent};
+use domain::service::{check_dpop_proof, DpopProofCmd};
 
 pub struct UserinfoCmd {
     pub access_token: String,
+    /// True if the access token is sent with the DPoP authorization scheme.
+    pub dpop: bool,
+    pub dpop_proof: Option<String>,
+    /// The certificate presented in the mutual TLS handshake.
+    pub client_certificate: Option<ClientCertificate>,
 }
 
 pub trait UserinfoService
-    : AccessTokenRepositoryComponent + EndUserRepositoryComponent {
+    : AccessTokenRepositoryComponent + EndUserRepositoryComponent + DpopProofRepositoryComponent {
     fn get_userinfo(&self, cmd: &UserinfoCmd) -> Result<EndUserClaims, ed::Error> {
-        let access_token_repo = self.access_token_repository();
-        let end_user_repo = self.end_user_repository();
-
-        // Access token
-        let access_token = access_token_repo
-            .find_by_token(&cmd.access_token)
-            .and_then(|v| {
-                v.ok_or(ek::UserinfoError("The access token was not found.".to_string()).into())
-            })?;
-
-        if !access_token.is_valid() {
-            return Err(ek::UserinfoError("The access token is expired.".to_string()).into());
-        }
-
-        if let Some(ref end_user_id) = access_token.end_user_id {
-            end_user_repo
-                .find_by_id(&end_user_id)
-                .and_then(|v| {
-                    v.ok_or(ek::UserinfoError("The user does not exist.".to_string()).into())
-                })
-                .map(|u| {
-                    EndUserClaims::from_end_user(&AppConfig::issuer(), &u, &access_token.client_id)
-                })
-        } else {
-            Err(ek::UserinfoError(
-                "The access token does not support openid. \
-                 This may be created by OAuth 2.0 flow."
-                    .to_string(),
-            ).into())
-        }
+        let userinfo_endpoint =
+            format!("{}/oidc/userinfo", AppConfig::endpoint().trim_right_matches('/'));
+        process_get_userinfo(
+            cmd,
+            &AppConfig::issuer(),
+            &userinfo_endpoint,
+            AppConfig::dpop_proof_max_age_sec(),
+            self.access_token_repository(),
+            self.end_user_repository(),
+            self.dpop_proof_repository(),
+        )
     }
 }
 
@@ -50,4 +38,268 @@ pub trait UserinfoServiceComponent {
     fn userinfo_service(&self) -> &Self::UserinfoService;
 }
 
-impl<T: AccessTokenRepositoryComponent + EndUserRepositoryComponent> UserinfoService for T {}
+impl<
+    T: AccessTokenRepositoryComponent + EndUserRepositoryComponent + DpopProofRepositoryComponent,
+> UserinfoService for T
+{
+}
+
+// Private functions
+fn process_get_userinfo(
+    cmd: &UserinfoCmd,
+    issuer: &str,
+    userinfo_endpoint: &str,
+    dpop_proof_max_age_sec: i64,
+    access_token_repo: &AccessTokenRepository,
+    end_user_repo: &EndUserRepository,
+    dpop_proof_repo: &DpopProofRepository,
+) -> Result<EndUserClaims, ed::Error> {
+    // Access token
+    let access_token = access_token_repo
+        .find_by_token(&cmd.access_token)
+        .and_then(|v| {
+            v.ok_or(ek::UserinfoError("The access token was not found.".to_string()).into())
+        })?;
+
+    if !access_token.is_valid() {
+        return Err(ek::UserinfoError("The access token is expired.".to_string()).into());
+    }
+
+    // Sender-constrained access token (RFC 9449)
+    let cnf = access_token.cnf.clone().unwrap_or_default();
+    match cnf.jkt {
+        Some(ref bound_jkt) => {
+            if !cmd.dpop {
+                return Err(ek::UserinfoError(
+                    "The access token is bound to a DPoP key.".to_string(),
+                ).into());
+            }
+            let proof = cmd.dpop_proof
+                .clone()
+                .ok_or(ek::InvalidDpopProof("DPoP proof is required.".to_string()))?;
+            let dpop_cmd = DpopProofCmd {
+                proof,
+                htm: "GET".to_string(),
+                htu: userinfo_endpoint.to_string(),
+                access_token: Some(cmd.access_token.clone()),
+            };
+            let jkt = check_dpop_proof(&dpop_cmd, dpop_proof_max_age_sec, dpop_proof_repo)?;
+            if &jkt != bound_jkt {
+                return Err(ek::InvalidDpopProof(
+                    "The proof is not signed with the key bound to the access token."
+                        .to_string(),
+                ).into());
+            }
+        }
+        None if cmd.dpop => {
+            return Err(ek::UserinfoError(
+                "The access token is not bound to a DPoP key.".to_string(),
+            ).into());
+        }
+        None => {}
+    }
+
+    // Certificate-bound access token (RFC 8705)
+    if let Some(ref x5t_s256) = cnf.x5t_s256 {
+        let presented = cmd.client_certificate.as_ref().map(|c| c.thumbprint());
+        if presented.as_ref() != Some(x5t_s256) {
+            return Err(ek::UserinfoError(
+                "The access token is bound to another client certificate.".to_string(),
+            ).into());
+        }
+    }
+
+    if let Some(ref end_user_id) = access_token.end_user_id {
+        end_user_repo
+            .find_by_id(&end_user_id)
+            .and_then(|v| {
+                v.ok_or(ek::UserinfoError("The user does not exist.".to_string()).into())
+            })
+            .map(|u| {
+                EndUserClaims::from_end_user(
+                    issuer,
+                    &u,
+                    &access_token.client_id,
+                    &access_token.auth_time,
+                    &access_token.scope,
+                    access_token.userinfo_claims(),
+                )
+            })
+    } else {
+        Err(ek::UserinfoError(
+            "The access token does not support openid. \
+             This may be created by OAuth 2.0 flow."
+                .to_string(),
+        ).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::prelude::*;
+    use jsonwebtoken::{sign, Algorithm};
+    use serde_json::{self, Value};
+
+    use domain::model::{access_token_hash, test_jwk, AccessToken, Confirmation, DpopProof, EndUser,
+                        Jwk, OTHER_PUBLIC_KEY, TEST_PRIVATE_KEY};
+    use util::encode_base64url;
+
+    struct Stub {
+        access_token: AccessToken,
+    }
+
+    impl AccessTokenRepository for Stub {
+        fn find_by_id(&self, _id: &String) -> Result<Option<AccessToken>, ed::Error> {
+            Ok(None)
+        }
+        fn find_by_token(&self, token: &String) -> Result<Option<AccessToken>, ed::Error> {
+            if token == &self.access_token.token {
+                Ok(Some(self.access_token.clone()))
+            } else {
+                Ok(None)
+            }
+        }
+        fn find_all(&self) -> Result<Vec<AccessToken>, ed::Error> {
+            Ok(vec![])
+        }
+        fn add(&self, _model: &AccessToken) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn update(&self, _model: &AccessToken) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn remove(&self, _model: AccessToken) -> Result<(), ed::Error> {
+            Ok(())
+        }
+    }
+
+    impl EndUserRepository for Stub {
+        fn find_by_id(&self, _id: &String) -> Result<Option<EndUser>, ed::Error> {
+            Ok(None)
+        }
+        fn find_by_name(&self, _name: &String) -> Result<Option<EndUser>, ed::Error> {
+            Ok(None)
+        }
+        fn find_all(&self) -> Result<Vec<EndUser>, ed::Error> {
+            Ok(vec![])
+        }
+        fn add(&self, _model: &EndUser) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn update(&self, _model: &EndUser) -> Result<(), ed::Error> {
+            Ok(())
+        }
+        fn remove(&self, _model: EndUser) -> Result<(), ed::Error> {
+            Ok(())
+        }
+    }
+
+    impl DpopProofRepository for Stub {
+        fn add_if_absent(&self, _jti: &String, _expires_in: i64) -> Result<bool, ed::Error> {
+            Ok(true)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct TestHeader {
+        typ: &'static str,
+        alg: &'static str,
+        jwk: Value,
+    }
+
+    /// Returns a proof signed with the test key for the userinfo request.
+    fn proof(access_token: &str) -> String {
+        let header = TestHeader {
+            typ: "dpop+jwt",
+            alg: "RS256",
+            jwk: serde_json::to_value(test_jwk()).unwrap(),
+        };
+        let claims = DpopProof {
+            jti: "jti".to_string(),
+            htm: "GET".to_string(),
+            htu: "http://127.0.0.1:8000/oidc/userinfo".to_string(),
+            iat: Utc::now().timestamp(),
+            ath: Some(access_token_hash(access_token)),
+            jkt: "".to_string(),
+        };
+        let input = format!(
+            "{}.{}",
+            encode_base64url(serde_json::to_string(&header).unwrap().as_bytes()),
+            encode_base64url(serde_json::to_string(&claims).unwrap().as_bytes())
+        );
+        let signature = sign(&input, TEST_PRIVATE_KEY, Algorithm::RS256).unwrap();
+        format!("{}.{}", input, signature)
+    }
+
+    fn stub(jkt: String) -> Stub {
+        Stub {
+            access_token: AccessToken {
+                id: "token".to_string(),
+                client_id: "client".to_string(),
+                resource_id: "api".to_string(),
+                token: "token".to_string(),
+                expires_in: 3600,
+                created_at: Utc::now(),
+                scope: vec!["openid".to_string()],
+                end_user_id: None,
+                state: None,
+                nonce: None,
+                acr: None,
+                amr: None,
+                auth_time: None,
+                claims: None,
+                act: None,
+                cnf: Some(Confirmation {
+                    jkt: Some(jkt),
+                    x5t_s256: None,
+                }),
+                is_deleted: false,
+            },
+        }
+    }
+
+    fn cmd(dpop_proof: Option<String>) -> UserinfoCmd {
+        UserinfoCmd {
+            access_token: "token".to_string(),
+            dpop: true,
+            dpop_proof,
+            client_certificate: None,
+        }
+    }
+
+    fn get_userinfo(stub: &Stub, cmd: &UserinfoCmd) -> Result<EndUserClaims, ed::Error> {
+        process_get_userinfo(
+            cmd,
+            "http://127.0.0.1:8000",
+            "http://127.0.0.1:8000/oidc/userinfo",
+            60,
+            stub,
+            stub,
+            stub,
+        )
+    }
+
+    #[test]
+    fn should_reject_proof_signed_with_other_key() {
+        let other_jkt = Jwk::from_rsa_public_key_der(OTHER_PUBLIC_KEY)
+            .unwrap()
+            .thumbprint();
+        let stub = stub(other_jkt);
+
+        let e = get_userinfo(&stub, &cmd(Some(proof("token")))).unwrap_err();
+        assert_eq!(e.description(), "invalid_dpop_proof");
+    }
+
+    #[test]
+    fn should_accept_proof_signed_with_bound_key() {
+        let stub = stub(test_jwk().thumbprint());
+
+        // The access token passes the binding check and fails as it has no end user.
+        let e = get_userinfo(&stub, &cmd(Some(proof("token")))).unwrap_err();
+        assert_eq!(e.description(), "invalid_token");
+
+        let e = get_userinfo(&stub, &cmd(None)).unwrap_err();
+        assert_eq!(e.description(), "invalid_dpop_proof");
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+use reqwest;
+
+use domain::error::domain as ed;
+use domain::service::LogoutTokenSender;
+use self::ed::ResultExt;
+
+/// `LogoutTokenSenderHttp` POSTs logout tokens as form parameters.
+#[derive(Clone)]
+pub struct LogoutTokenSenderHttp {
+    pub timeout_sec: u64,
+}
+
+impl LogoutTokenSender for LogoutTokenSenderHttp {
+    fn send(&self, backchannel_logout_uri: &str, logout_token: &str) -> Result<(), ed::Error> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.timeout_sec))
+            .build()
+            .chain_err(|| ed::ErrorKind::ServerError("Building HTTP client failed.".to_string()))?;
+        let res = client
+            .post(backchannel_logout_uri)
+            .form(&[("logout_token", logout_token)])
+            .send()
+            .chain_err(|| {
+                ed::ErrorKind::ServerError(format!(
+                    "Sending logout token failed. URI => {}",
+                    backchannel_logout_uri
+                ))
+            })?;
+        if res.status().is_success() {
+            Ok(())
+        } else {
+            Err(ed::ErrorKind::ServerError(format!(
+                "The client rejected the logout token. Status => {}",
+                res.status()
+            )).into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use infra::http::stand_in::stand_in_server;
+
+    #[test]
+    fn should_post_logout_token() {
+        let (uri, handle) = stand_in_server("/backchannel_logout", "HTTP/1.1 200 OK", "");
+        let sender = LogoutTokenSenderHttp { timeout_sec: 5 };
+
+        assert!(sender.send(&uri, "header.payload.signature").is_ok());
+        let request = handle.join().unwrap();
+        assert!(request.starts_with("POST /backchannel_logout"));
+        assert!(request.contains("application/x-www-form-urlencoded"));
+        assert!(request.contains("logout_token=header.payload.signature"));
+    }
+
+    #[test]
+    fn should_fail_when_receiver_rejects() {
+        let (uri, handle) = stand_in_server("/backchannel_logout", "HTTP/1.1 400 Bad Request", "");
+        let sender = LogoutTokenSenderHttp { timeout_sec: 5 };
+
+        assert!(sender.send(&uri, "header.payload.signature").is_err());
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,7 @@
+mod logout_token_sender;
+mod request_object_fetcher;
+#[cfg(test)]
+mod stand_in;
+
+pub use self::logout_token_sender::*;
+pub use self::request_object_fetcher::*;
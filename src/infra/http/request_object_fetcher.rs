@@ -0,0 +1,98 @@
+use std::io::Read;
+use std::time::Duration;
+use reqwest;
+
+use domain::error::domain as ed;
+use domain::service::RequestObjectFetcher;
+use self::ed::ResultExt;
+
+/// `RequestObjectFetcherHttp` GETs request objects from the `request_uri`.
+/// `timeout_sec` applies to connecting and to every read,
+/// and a body larger than `max_body_bytes` is rejected.
+/// Redirects are not followed, so only the registered URI is fetched.
+#[derive(Clone)]
+pub struct RequestObjectFetcherHttp {
+    pub timeout_sec: u64,
+    pub max_body_bytes: u64,
+}
+
+impl RequestObjectFetcher for RequestObjectFetcherHttp {
+    fn fetch(&self, request_uri: &str) -> Result<String, ed::Error> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(self.timeout_sec))
+            .redirect(reqwest::RedirectPolicy::none())
+            .build()
+            .chain_err(|| ed::ErrorKind::ServerError("Building HTTP client failed.".to_string()))?;
+        let res = client.get(request_uri).send().chain_err(|| {
+            ed::ErrorKind::ServerError(format!(
+                "Fetching request object failed. URI => {}",
+                request_uri
+            ))
+        })?;
+        if !res.status().is_success() {
+            return Err(ed::ErrorKind::ServerError(format!(
+                "The client did not serve the request object. Status => {}",
+                res.status()
+            )).into());
+        }
+        // Read one more byte to know whether the body exceeds the limit
+        let mut body = String::new();
+        res.take(self.max_body_bytes + 1)
+            .read_to_string(&mut body)
+            .chain_err(|| {
+                ed::ErrorKind::ServerError("Reading request object failed.".to_string())
+            })?;
+        if body.len() as u64 > self.max_body_bytes {
+            return Err(ed::ErrorKind::ServerError(format!(
+                "The request object exceeds {} bytes.",
+                self.max_body_bytes
+            )).into());
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use infra::http::stand_in::stand_in_server;
+
+    #[test]
+    fn should_get_request_object() {
+        let (uri, handle) =
+            stand_in_server("/request", "HTTP/1.1 200 OK", "header.payload.signature");
+        let fetcher = RequestObjectFetcherHttp {
+            timeout_sec: 5,
+            max_body_bytes: 1024,
+        };
+
+        assert_eq!(fetcher.fetch(&uri).unwrap(), "header.payload.signature");
+        let request = handle.join().unwrap();
+        assert!(request.starts_with("GET /request"));
+    }
+
+    #[test]
+    fn should_fail_when_not_found() {
+        let (uri, handle) = stand_in_server("/request", "HTTP/1.1 404 Not Found", "");
+        let fetcher = RequestObjectFetcherHttp {
+            timeout_sec: 5,
+            max_body_bytes: 1024,
+        };
+
+        assert!(fetcher.fetch(&uri).is_err());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn should_fail_when_too_large() {
+        let (uri, handle) =
+            stand_in_server("/request", "HTTP/1.1 200 OK", "header.payload.signature");
+        let fetcher = RequestObjectFetcherHttp {
+            timeout_sec: 5,
+            max_body_bytes: 10,
+        };
+
+        assert!(fetcher.fetch(&uri).is_err());
+        handle.join().unwrap();
+    }
+}
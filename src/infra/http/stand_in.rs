@@ -0,0 +1,58 @@
+//! A stand-in HTTP server for testing the HTTP clients.
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+/// Accepts one request at `path` and responds with the status line and the body,
+/// then returns the raw request.
+pub fn stand_in_server(
+    path: &str,
+    status_line: &'static str,
+    body: &'static str,
+) -> (String, thread::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let uri = format!("http://{}{}", listener.local_addr().unwrap(), path);
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        // Read the headers, then the body of Content-Length
+        while !is_complete(&String::from_utf8_lossy(&request)) {
+            let n = stream.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..n]);
+        }
+        let response = format!(
+            "{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status_line,
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).unwrap();
+        String::from_utf8_lossy(&request).to_string()
+    });
+    (uri, handle)
+}
+
+fn is_complete(request: &str) -> bool {
+    let pos = match request.find("\r\n\r\n") {
+        Some(pos) => pos,
+        None => return false,
+    };
+    let content_length = request[..pos]
+        .lines()
+        .filter_map(|l| {
+            let mut kv = l.splitn(2, ':');
+            match (kv.next(), kv.next()) {
+                (Some(k), Some(v)) if k.trim().to_lowercase() == "content-length" => {
+                    v.trim().parse::<usize>().ok()
+                }
+                _ => None,
+            }
+        })
+        .next()
+        .unwrap_or(0);
+    request.len() >= pos + 4 + content_length
+}
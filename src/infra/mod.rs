@@ -1,3 +1,4 @@
+pub mod http;
 pub mod persistence;
 pub mod rest;
 pub mod session;
@@ -0,0 +1,32 @@
+use domain::model::ConsumedAssertion;
+use domain::repository::ConsumedAssertionRepository;
+use domain::error::domain as ed;
+use infra::persistence::{MongoClient, MongoModel};
+
+#[derive(Clone)]
+pub struct ConsumedAssertionRepositoryMongo {
+    pub mongo_client: MongoClient,
+}
+
+// Implement MongoModel for ConsumedAssertion to use repository
+impl MongoModel for ConsumedAssertion {
+    fn collection_name() -> String {
+        "consumed_assertions".to_string()
+    }
+    fn key_value(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl ConsumedAssertionRepository for ConsumedAssertionRepositoryMongo {
+    fn add_if_absent(&self, model: &ConsumedAssertion) -> Result<bool, ed::Error> {
+        // The unique index on issuer and jti rejects the replayed assertion.
+        match self.mongo_client.insert(model) {
+            Ok(_) => Ok(true),
+            Err(e) => match *e.kind() {
+                ed::ErrorKind::DuplicatedEntity(_) => Ok(false),
+                _ => Err(e),
+            },
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use bson;
+
+use domain::model::{DeviceAuthorization, DeviceAuthorizationStatus};
+use domain::repository::DeviceAuthorizationRepository;
+use domain::error::domain as ed;
+use infra::persistence::{MongoClient, MongoModel};
+
+#[derive(Clone)]
+pub struct DeviceAuthorizationRepositoryMongo {
+    pub mongo_client: MongoClient,
+}
+
+// Implement MongoModel for DeviceAuthorization to use repository
+impl MongoModel for DeviceAuthorization {
+    fn collection_name() -> String {
+        "device_authorizations".to_string()
+    }
+    fn key_value(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl DeviceAuthorizationRepository for DeviceAuthorizationRepositoryMongo {
+    fn find_by_device_code(
+        &self,
+        device_code: &String,
+    ) -> Result<Option<DeviceAuthorization>, ed::Error> {
+        let query = doc! {"device_code" => device_code, "is_deleted": false};
+        self.mongo_client.find(&query).map(|mut v| v.pop())
+    }
+    fn find_by_user_code(
+        &self,
+        user_code: &String,
+    ) -> Result<Option<DeviceAuthorization>, ed::Error> {
+        let query = doc! {"user_code" => user_code, "is_deleted": false};
+        self.mongo_client.find(&query).map(|mut v| v.pop())
+    }
+    fn find_by_id_and_change_status(
+        &self,
+        id: &String,
+        current: &DeviceAuthorizationStatus,
+        status: &DeviceAuthorizationStatus,
+    ) -> Result<Option<DeviceAuthorization>, ed::Error> {
+        let current = bson::to_bson(current).unwrap();
+        let query = doc! {"id" => id, "status" => current, "is_deleted": false};
+        let status = bson::to_bson(status).unwrap();
+        let modify = doc! {"$set": {"status": status}};
+        self.mongo_client.find_and_modify(&query, &modify)
+    }
+    fn update_polling(&self, model: &DeviceAuthorization) -> Result<(), ed::Error> {
+        let query = doc! {"id" => &model.id, "is_deleted": false};
+        let last_polled_at = bson::to_bson(&model.last_polled_at).unwrap();
+        let modify = doc! {
+            "$set": {"last_polled_at": last_polled_at, "interval": model.interval}
+        };
+        self.mongo_client
+            .find_and_modify::<DeviceAuthorization>(&query, &modify)
+            .map(|_| ())
+    }
+    fn add(&self, model: &DeviceAuthorization) -> Result<(), ed::Error> {
+        self.mongo_client.insert(model)
+    }
+    fn update(&self, model: &DeviceAuthorization) -> Result<(), ed::Error> {
+        self.mongo_client.update(model)
+    }
+    fn remove(&self, mut model: DeviceAuthorization) -> Result<(), ed::Error> {
+        model.is_deleted = true;
+        self.mongo_client.update(&model)
+    }
+}
@@ -53,6 +53,10 @@ impl GrantRepository for GrantRepositoryMongo {
         let modify = doc! {"$set": {"status": status}};
         self.mongo_client.find_and_modify(&query, &modify)
     }
+    fn find_by_sid(&self, sid: &String) -> Result<Vec<Grant>, ed::Error> {
+        let query = doc! {"sid" => sid, "is_deleted": false};
+        self.mongo_client.find(&query)
+    }
     fn find_all(&self) -> Result<Vec<Grant>, ed::Error> {
         let query = doc! {"is_deleted": false};
         self.mongo_client.find(&query)
@@ -0,0 +1,55 @@
+use serde_json;
+
+use domain::model::{LogoutDelivery, LogoutDeliveryStatus};
+use domain::repository::LogoutDeliveryRepository;
+use domain::error::domain as ed;
+use infra::persistence::{MongoClient, MongoModel};
+
+#[derive(Clone)]
+pub struct LogoutDeliveryRepositoryMongo {
+    pub mongo_client: MongoClient,
+}
+
+// Implement MongoModel for LogoutDelivery to use repository
+impl MongoModel for LogoutDelivery {
+    fn collection_name() -> String {
+        "logout_deliveries".to_string()
+    }
+    fn key_value(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl LogoutDeliveryRepository for LogoutDeliveryRepositoryMongo {
+    fn find_by_id(&self, id: &String) -> Result<Option<LogoutDelivery>, ed::Error> {
+        let query = doc! {"id" => id, "is_deleted": false};
+        self.mongo_client.find(&query).map(|mut v| v.pop())
+    }
+    fn find_by_sid(&self, sid: &String) -> Result<Vec<LogoutDelivery>, ed::Error> {
+        let query = doc! {"sid" => sid, "is_deleted": false};
+        self.mongo_client.find(&query)
+    }
+    fn find_by_status(
+        &self,
+        status: &LogoutDeliveryStatus,
+    ) -> Result<Vec<LogoutDelivery>, ed::Error> {
+        let temp = serde_json::to_string(status).unwrap();
+        let status = temp.trim_matches('"');
+        let query = doc! {"status" => status, "is_deleted": false};
+        self.mongo_client.find(&query)
+    }
+    fn find_all(&self) -> Result<Vec<LogoutDelivery>, ed::Error> {
+        let query = doc! {"is_deleted": false};
+        self.mongo_client.find(&query)
+    }
+    fn add(&self, model: &LogoutDelivery) -> Result<(), ed::Error> {
+        self.mongo_client.insert(model)
+    }
+    fn update(&self, model: &LogoutDelivery) -> Result<(), ed::Error> {
+        self.mongo_client.update(model)
+    }
+    fn remove(&self, mut model: LogoutDelivery) -> Result<(), ed::Error> {
+        model.is_deleted = true;
+        self.mongo_client.update(&model)
+    }
+}
@@ -1,21 +1,31 @@
 mod access_token;
 mod admin;
 mod client;
+mod consumed_assertion;
+mod device_authorization;
 mod end_user;
 mod grant;
 mod id_token;
+mod logout_delivery;
 mod mongo_client;
 mod mongo_model;
+mod pushed_authorization_request;
 mod refresh_token;
 mod resource;
+mod signing_key;
 
 pub use self::access_token::*;
 pub use self::admin::*;
 pub use self::client::*;
+pub use self::consumed_assertion::*;
+pub use self::device_authorization::*;
 pub use self::end_user::*;
 pub use self::grant::*;
 pub use self::id_token::*;
+pub use self::logout_delivery::*;
 pub use self::mongo_client::*;
 pub use self::mongo_model::*;
+pub use self::pushed_authorization_request::*;
 pub use self::refresh_token::*;
 pub use self::resource::*;
+pub use self::signing_key::*;
@@ -0,0 +1,44 @@
+use domain::model::PushedAuthorizationRequest;
+use domain::repository::PushedAuthorizationRequestRepository;
+use domain::error::domain as ed;
+use infra::persistence::{MongoClient, MongoModel};
+
+#[derive(Clone)]
+pub struct PushedAuthorizationRequestRepositoryMongo {
+    pub mongo_client: MongoClient,
+}
+
+// Implement MongoModel for PushedAuthorizationRequest to use repository
+impl MongoModel for PushedAuthorizationRequest {
+    fn collection_name() -> String {
+        "pushed_authorization_requests".to_string()
+    }
+    fn key_value(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl PushedAuthorizationRequestRepository for PushedAuthorizationRequestRepositoryMongo {
+    fn find_by_request_uri(
+        &self,
+        request_uri: &String,
+    ) -> Result<Option<PushedAuthorizationRequest>, ed::Error> {
+        let query = doc! {"request_uri" => request_uri, "is_deleted": false};
+        self.mongo_client.find(&query).map(|mut v| v.pop())
+    }
+    fn consume(
+        &self,
+        request_uri: &String,
+    ) -> Result<Option<PushedAuthorizationRequest>, ed::Error> {
+        let query = doc! {"request_uri" => request_uri, "is_deleted": false};
+        let modify = doc! {"$set": {"is_deleted": true}};
+        self.mongo_client.find_and_modify(&query, &modify)
+    }
+    fn add(&self, model: &PushedAuthorizationRequest) -> Result<(), ed::Error> {
+        self.mongo_client.insert(model)
+    }
+    fn remove(&self, mut model: PushedAuthorizationRequest) -> Result<(), ed::Error> {
+        model.is_deleted = true;
+        self.mongo_client.update(&model)
+    }
+}
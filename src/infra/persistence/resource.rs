@@ -27,6 +27,10 @@ impl ResourceRepository for ResourceRepositoryMongo {
         let query = doc! {"name" => name, "is_deleted": false};
         self.mongo_client.find(&query).map(|mut v| v.pop())
     }
+    fn find_by_website(&self, website: &String) -> Result<Option<Resource>, ed::Error> {
+        let query = doc! {"website" => website, "is_deleted": false};
+        self.mongo_client.find(&query).map(|mut v| v.pop())
+    }
     fn find_all(&self) -> Result<Vec<Resource>, ed::Error> {
         let query = doc! {"is_deleted": false};
         self.mongo_client.find(&query)
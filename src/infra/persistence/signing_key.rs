@@ -0,0 +1,48 @@
+use serde_json;
+
+use domain::model::{SigningKey, SigningKeyStatus};
+use domain::repository::SigningKeyRepository;
+use domain::error::domain as ed;
+use infra::persistence::{MongoClient, MongoModel};
+
+#[derive(Clone)]
+pub struct SigningKeyRepositoryMongo {
+    pub mongo_client: MongoClient,
+}
+
+// Implement MongoModel for SigningKey to use repository
+impl MongoModel for SigningKey {
+    fn collection_name() -> String {
+        "signing_keys".to_string()
+    }
+    fn key_value(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl SigningKeyRepository for SigningKeyRepositoryMongo {
+    fn find_by_id(&self, id: &String) -> Result<Option<SigningKey>, ed::Error> {
+        let query = doc! {"id" => id, "is_deleted": false};
+        self.mongo_client.find(&query).map(|mut v| v.pop())
+    }
+    fn find_by_status(&self, status: &SigningKeyStatus) -> Result<Vec<SigningKey>, ed::Error> {
+        let temp = serde_json::to_string(status).unwrap();
+        let status = temp.trim_matches('"');
+        let query = doc! {"status" => status, "is_deleted": false};
+        self.mongo_client.find(&query)
+    }
+    fn find_all(&self) -> Result<Vec<SigningKey>, ed::Error> {
+        let query = doc! {"is_deleted": false};
+        self.mongo_client.find(&query)
+    }
+    fn add(&self, model: &SigningKey) -> Result<(), ed::Error> {
+        self.mongo_client.insert(model)
+    }
+    fn update(&self, model: &SigningKey) -> Result<(), ed::Error> {
+        self.mongo_client.update(model)
+    }
+    fn remove(&self, mut model: SigningKey) -> Result<(), ed::Error> {
+        model.is_deleted = true;
+        self.mongo_client.update(&model)
+    }
+}
@@ -4,10 +4,12 @@ use rocket::http::Status;
 use rocket_contrib::Json;
 use rocket_cors::{self, Guard};
 
-use app::admin::{AdminRepr, AdminService, AdminServiceComponent, RegisterAdminCmd, UpdateAdminCmd};
+use app::admin::{AdminRepr, AdminService, AdminServiceComponent, RegisterAdminCmd,
+                 RegisterSigningKeyCmd, SigningKeyRepr, UpdateAdminCmd};
 use domain::error::domain as ed;
 use constant;
-use infra::rest::common::{AuthorizationHeader, AuthorizationType, CommonResponse};
+use infra::rest::common::{AuthorizationHeader, AuthorizationType, CommonListResponse,
+                          CommonResponse};
 use infra::session::RedisStore;
 use util::generate_random_id;
 use server::Server;
@@ -174,3 +176,66 @@ pub fn delete_admin(
     }
     Err(ed::ErrorKind::RequireLogin(format!("ID => {}", id)).into())
 }
+
+#[get("/keys")]
+pub fn get_signing_keys(
+    authorization_header: AuthorizationHeader,
+    redis_store: RedisStore,
+    server: Server,
+) -> Result<CommonListResponse<SigningKeyRepr>, ed::Error> {
+    if let AuthorizationType::Bearer = authorization_header.auth_type {
+        if let Some(token) = authorization_header.token {
+            let self_id = redis_store.get(&token, constant::ADMIN_SESS_ID_FIELD)?;
+            let service = server.admin_service();
+            return service
+                .get_signing_keys(&self_id)
+                .map(|v| CommonListResponse { list: v });
+        }
+    }
+    Err(ed::ErrorKind::RequireLogin("Admin login is required.".to_string()).into())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SigningKeyRegisterForm {
+    pub private_key: String,
+    pub public_key: String,
+}
+
+#[post("/keys", data = "<input>")]
+pub fn register_signing_key(
+    input: Json<SigningKeyRegisterForm>,
+    authorization_header: AuthorizationHeader,
+    redis_store: RedisStore,
+    server: Server,
+) -> Result<SigningKeyRepr, ed::Error> {
+    let form = input.into_inner();
+    if let AuthorizationType::Bearer = authorization_header.auth_type {
+        if let Some(token) = authorization_header.token {
+            let self_id = redis_store.get(&token, constant::ADMIN_SESS_ID_FIELD)?;
+            let cmd = RegisterSigningKeyCmd {
+                self_id,
+                private_key: form.private_key,
+                public_key: form.public_key,
+            };
+            let service = server.admin_service();
+            return service.register_signing_key(&cmd);
+        }
+    }
+    Err(ed::ErrorKind::RequireLogin("Admin login is required.".to_string()).into())
+}
+
+#[post("/keys/rotate")]
+pub fn rotate_signing_keys(
+    authorization_header: AuthorizationHeader,
+    redis_store: RedisStore,
+    server: Server,
+) -> Result<SigningKeyRepr, ed::Error> {
+    if let AuthorizationType::Bearer = authorization_header.auth_type {
+        if let Some(token) = authorization_header.token {
+            let self_id = redis_store.get(&token, constant::ADMIN_SESS_ID_FIELD)?;
+            let service = server.admin_service();
+            return service.execute_key_rotation(&self_id);
+        }
+    }
+    Err(ed::ErrorKind::RequireLogin("Admin login is required.".to_string()).into())
+}
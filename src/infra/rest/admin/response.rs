@@ -2,7 +2,7 @@ use rocket::response::{Responder, Response};
 use rocket::request::Request;
 use rocket::http::Status;
 
-use app::admin::AdminRepr;
+use app::admin::{AdminRepr, SigningKeyRepr};
 use infra::rest::common::CommonResponse;
 
 impl<'r> Responder<'r> for AdminRepr {
@@ -10,3 +10,9 @@ impl<'r> Responder<'r> for AdminRepr {
         CommonResponse::respond(&self, Status::Ok).ok()
     }
 }
+
+impl<'r> Responder<'r> for SigningKeyRepr {
+    fn respond_to(self, _request: &Request) -> Result<Response<'r>, Status> {
+        CommonResponse::respond(&self, Status::Ok).ok()
+    }
+}
@@ -123,6 +123,12 @@ pub struct ClientRegisterForm {
     pub client_type: String,
     pub redirect_uris: Vec<String>,
     pub resource_id: String,
+    #[serde(default)]
+    pub require_pkce: bool,
+    #[serde(default)]
+    pub post_logout_redirect_uris: Vec<String>,
+    pub backchannel_logout_uri: Option<String>,
+    pub frontchannel_logout_uri: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -150,6 +156,10 @@ pub fn register_client<'r>(
         client_type: form.client_type,
         redirect_uris: form.redirect_uris,
         resource_id: form.resource_id,
+        require_pkce: form.require_pkce,
+        post_logout_redirect_uris: form.post_logout_redirect_uris,
+        backchannel_logout_uri: form.backchannel_logout_uri,
+        frontchannel_logout_uri: form.frontchannel_logout_uri,
     };
     let service = server.client_service();
     cors.responder(
@@ -166,6 +176,10 @@ pub struct ClientUpdateForm {
     pub client_type: Option<String>,
     pub redirect_uris: Option<Vec<String>>,
     pub resource_id: Option<String>,
+    pub require_pkce: Option<bool>,
+    pub post_logout_redirect_uris: Option<Vec<String>>,
+    pub backchannel_logout_uri: Option<String>,
+    pub frontchannel_logout_uri: Option<String>,
     pub new_password: Option<String>,
     pub current_password: Option<String>,
 }
@@ -193,6 +207,11 @@ pub fn update_client(
                 client_type: form.client_type,
                 redirect_uris: form.redirect_uris,
                 resource_id: form.resource_id,
+                require_pkce: form.require_pkce,
+                post_logout_redirect_uris: form.post_logout_redirect_uris,
+                backchannel_logout_uri: form.backchannel_logout_uri,
+                frontchannel_logout_uri: form.frontchannel_logout_uri,
+                metadata: None,
                 current_password: form.current_password,
             };
             let service = server.client_service();
@@ -6,6 +6,7 @@ use std::collections::HashMap;
 use error_chain::ChainedError;
 
 use domain::error::domain as ed;
+use domain::model::ResponseMode;
 use infra::rest::common::CommonResponse;
 
 pub enum ResponseType {
@@ -33,6 +34,12 @@ impl ed::Error {
             &ed::Error(ed::ErrorKind::UserinfoError(_), _) => {
                 (Status::Unauthorized, content, ResponseType::Bearer)
             }
+            &ed::Error(ed::ErrorKind::RegistrationTokenError(_), _) => {
+                (Status::Unauthorized, content, ResponseType::Bearer)
+            }
+            &ed::Error(ed::ErrorKind::InvalidDpopProof(_), _) => {
+                (Status::Unauthorized, content, ResponseType::Bearer)
+            }
             _ => (Status::BadRequest, content, ResponseType::Undefined),
         }
     }
@@ -44,7 +51,7 @@ impl ed::Error {
 
     pub fn redirect<'r>(self, redirect_uri: String) -> ResponseBuilder<'r> {
         let (_, content, _) = self.convert_status_content();
-        CommonResponse::redirect(content, redirect_uri)
+        CommonResponse::redirect(content, redirect_uri, &ResponseMode::Query)
     }
 
     pub fn bearer<'r>(self) -> ResponseBuilder<'r> {
@@ -1,13 +1,20 @@
 //! Authorization Header Parser for Rocket
 use base64::decode;
+use std::net::IpAddr;
 use std::str;
-use rocket::Outcome;
+use rocket::{Outcome, State};
 use rocket::request::{self, FromRequest, Request};
 use regex::{Captures, Regex};
+use url::percent_encoding::percent_decode;
+
+use config::AppConfig;
+use domain::error::domain as ed;
+use domain::model::ClientCertificate;
 
 pub enum AuthorizationType {
     Basic,
     Bearer,
+    Dpop,
     Undefined,
 }
 
@@ -17,6 +24,7 @@ impl AuthorizationType {
         match lower.as_str() {
             "basic" => AuthorizationType::Basic,
             "bearer" => AuthorizationType::Bearer,
+            "dpop" => AuthorizationType::Dpop,
             _ => AuthorizationType::Undefined,
         }
     }
@@ -84,3 +92,91 @@ impl<'a, 'r> FromRequest<'a, 'r> for AuthorizationHeader {
         Outcome::Success(AuthorizationHeader::new(auth_type, token))
     }
 }
+
+/// `DpopHeader` holds the values of the DPoP header (RFC 9449).
+pub struct DpopHeader {
+    pub proofs: Vec<String>,
+}
+
+impl DpopHeader {
+    /// Returns the proof if any. The request MUST NOT have more than one DPoP header.
+    pub fn proof(&self) -> Result<Option<String>, ed::Error> {
+        if self.proofs.len() > 1 {
+            return Err(ed::ErrorKind::InvalidDpopProof(
+                "Multiple DPoP headers are not allowed.".to_string(),
+            ).into());
+        }
+        Ok(self.proofs.first().cloned())
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for DpopHeader {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let proofs = request
+            .headers()
+            .get("dpop")
+            .map(|v| v.to_string())
+            .collect();
+        Outcome::Success(DpopHeader { proofs })
+    }
+}
+
+/// `ClientCertificateConfig` is the settings of the TLS terminating proxy.
+/// It is read once at startup and managed by Rocket.
+pub struct ClientCertificateConfig {
+    pub header: String,
+    pub verify_header: String,
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl ClientCertificateConfig {
+    pub fn from_env() -> Self {
+        ClientCertificateConfig {
+            header: AppConfig::tls_client_cert_header(),
+            verify_header: AppConfig::tls_client_cert_verify_header(),
+            trusted_proxies: AppConfig::tls_client_cert_trusted_proxies(),
+        }
+    }
+}
+
+/// `ClientCertificateHeader` holds the client certificate of the mutual TLS connection
+/// forwarded by the TLS terminating proxy (RFC 8705).
+pub struct ClientCertificateHeader {
+    pub certificate: Option<ClientCertificate>,
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for ClientCertificateHeader {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let config = request.guard::<State<ClientCertificateConfig>>()?;
+        let value = match request.headers().get_one(&config.header) {
+            Some(v) if !v.is_empty() => v,
+            _ => return Outcome::Success(ClientCertificateHeader { certificate: None }),
+        };
+
+        // Anyone could send the header unless the request comes through the proxy.
+        let trusted = request
+            .remote()
+            .map_or(false, |addr| config.trusted_proxies.contains(&addr.ip()));
+        if !trusted {
+            warn!("{} from an untrusted address is ignored.", config.header);
+            return Outcome::Success(ClientCertificateHeader { certificate: None });
+        }
+
+        // The proxy accepts self-signed certificates too, so the result of verifying
+        // the chain is kept for tls_client_auth.
+        let verified = request.headers().get_one(&config.verify_header) == Some("SUCCESS");
+        let certificate = percent_decode(value.as_bytes())
+            .decode_utf8()
+            .ok()
+            .and_then(|pem| ClientCertificate::from_pem(&pem).ok())
+            .map(|cert| ClientCertificate { verified, ..cert });
+        if certificate.is_none() {
+            warn!("{} is not a valid certificate.", config.header);
+        }
+        Outcome::Success(ClientCertificateHeader { certificate })
+    }
+}
@@ -7,9 +7,21 @@ use serde_json;
 use serde_urlencoded;
 use std::io::Cursor;
 use std::collections::HashMap;
+use url::form_urlencoded;
+
+use domain::model::ResponseMode;
 
 pub struct CommonResponse {}
 
+/// The redirection which the SPA follows.
+#[derive(Serialize)]
+struct RedirectContent {
+    status: String,
+    location: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    params: Option<HashMap<String, String>>,
+}
+
 impl CommonResponse {
     pub fn respond<'r, T: Serialize>(model: T, status: Status) -> ResponseBuilder<'r> {
         let mut builder = Response::build();
@@ -21,21 +33,70 @@ impl CommonResponse {
         builder
     }
 
-    pub fn redirect<'r, T: Serialize>(model: T, redirect_uri: String) -> ResponseBuilder<'r> {
-        // Jongleur is a SPA. So all requests are xhr,
-        // Jongleur returns 200 and a location property instead of 302
-        let mut builder = Response::build();
-        let qs = serde_urlencoded::to_string(&model).unwrap();
-        let redirect_uri = format!("{}?{}", redirect_uri, &qs);
-        let mut content = HashMap::new();
-        content.insert("status", "redirect".to_string());
-        content.insert("location", redirect_uri);
-        builder.header(ContentType::JSON);
-        builder.raw_header("Cache-Control", "no-store");
-        builder.raw_header("Pragma", "no-cache");
-        builder.sized_body(Cursor::new(serde_json::to_string(&content).unwrap()));
-        builder.status(Status::Ok);
-        builder
+    /// Returns where the SPA sends the authorization response.
+    /// Jongleur is a SPA. So all requests are xhr, which can't follow 302,
+    /// and Jongleur returns 200 and a location property instead.
+    /// The status tells the SPA to redirect to the location,
+    /// or to post the params to the location by `form_post`.
+    pub fn redirect<'r, T: Serialize>(
+        model: T,
+        redirect_uri: String,
+        response_mode: &ResponseMode,
+    ) -> ResponseBuilder<'r> {
+        let content = match *response_mode {
+            ResponseMode::FormPost => RedirectContent {
+                status: "form_post".to_string(),
+                location: redirect_uri,
+                params: Some(CommonResponse::form_params(&model).into_iter().collect()),
+            },
+            _ => RedirectContent {
+                status: "redirect".to_string(),
+                location: CommonResponse::redirect_uri_with(&model, redirect_uri, response_mode),
+                params: None,
+            },
+        };
+        CommonResponse::respond(content, Status::Ok)
+    }
+
+    /// Appends the model to the redirect_uri as the query string.
+    pub fn redirect_uri<T: Serialize>(model: &T, redirect_uri: String) -> String {
+        CommonResponse::redirect_uri_with(model, redirect_uri, &ResponseMode::Query)
+    }
+
+    /// Appends the model to the redirect_uri as the query string or the fragment.
+    /// The parameters are merged into the existing query string or fragment.
+    /// `form_post` has nothing to append.
+    pub fn redirect_uri_with<T: Serialize>(
+        model: &T,
+        redirect_uri: String,
+        response_mode: &ResponseMode,
+    ) -> String {
+        let qs = serde_urlencoded::to_string(model).unwrap();
+        if qs.is_empty() {
+            return redirect_uri;
+        }
+        match *response_mode {
+            ResponseMode::Query if redirect_uri.contains('?') => {
+                format!("{}&{}", redirect_uri, &qs)
+            }
+            ResponseMode::Query => format!("{}?{}", redirect_uri, &qs),
+            ResponseMode::Fragment if redirect_uri.ends_with('#') => {
+                format!("{}{}", redirect_uri, &qs)
+            }
+            ResponseMode::Fragment if redirect_uri.contains('#') => {
+                format!("{}&{}", redirect_uri, &qs)
+            }
+            ResponseMode::Fragment => format!("{}#{}", redirect_uri, &qs),
+            ResponseMode::FormPost => redirect_uri,
+        }
+    }
+
+    /// Returns the model as the pairs of form parameters.
+    fn form_params<T: Serialize>(model: &T) -> Vec<(String, String)> {
+        let qs = serde_urlencoded::to_string(model).unwrap();
+        form_urlencoded::parse(qs.as_bytes())
+            .into_owned()
+            .collect()
     }
 
     pub fn bearer<'r, T: Serialize>(model: T, status: Status) -> ResponseBuilder<'r> {
@@ -59,3 +120,56 @@ impl<'r, T: Serialize> Responder<'r> for CommonListResponse<T> {
         CommonResponse::respond(self.list, Status::Ok).ok()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_return_params_by_response_mode() {
+        let mut model = HashMap::new();
+        model.insert("state", "a<b");
+        let uri = "https://client.example.com/cb".to_string();
+
+        assert_eq!(
+            CommonResponse::redirect_uri_with(&model, uri.clone(), &ResponseMode::Query),
+            "https://client.example.com/cb?state=a%3Cb"
+        );
+        assert_eq!(
+            CommonResponse::redirect_uri_with(&model, uri.clone(), &ResponseMode::Fragment),
+            "https://client.example.com/cb#state=a%3Cb"
+        );
+
+        assert_eq!(
+            CommonResponse::redirect_uri_with(&model, uri.clone(), &ResponseMode::FormPost),
+            "https://client.example.com/cb"
+        );
+        assert_eq!(
+            CommonResponse::form_params(&model),
+            vec![("state".to_string(), "a<b".to_string())]
+        );
+    }
+
+    #[test]
+    fn should_merge_params_into_existing_fragment() {
+        let mut model = HashMap::new();
+        model.insert("state", "abc");
+
+        assert_eq!(
+            CommonResponse::redirect_uri_with(
+                &model,
+                "https://client.example.com/cb#page=1".to_string(),
+                &ResponseMode::Fragment
+            ),
+            "https://client.example.com/cb#page=1&state=abc"
+        );
+        assert_eq!(
+            CommonResponse::redirect_uri_with(
+                &model,
+                "https://client.example.com/cb?a=1#".to_string(),
+                &ResponseMode::Fragment
+            ),
+            "https://client.example.com/cb?a=1#state=abc"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::prelude::*;
 use rocket::response::{Responder, Response};
 use rocket::request::Request;
 use rocket::http::Status;
@@ -6,7 +7,10 @@ use rocket_cors::{self, Guard};
 
 use app::end_user::{DetailedEndUserRepr, EndUserRepr, EndUserService, EndUserServiceComponent,
                     RegisterEndUserCmd, UpdateEndUserCmd};
+use app::oidc::{OidcService, OidcServiceComponent};
 use domain::error::domain as ed;
+use domain::model::{Address, AuthenticationMethod};
+use domain::service::{EndSessionCmd, EndSessionRet};
 use constant;
 use infra::rest::common::{AuthorizationHeader, AuthorizationType, CommonListResponse,
                           CommonResponse};
@@ -48,30 +52,83 @@ pub fn login(
     if let AuthorizationType::Bearer = authorization_header.auth_type {
         if let Some(token) = authorization_header.token {
             sid = token.clone();
-            redis_store.set(&sid, constant::END_USER_SESS_ID_FIELD, &ret.id)?;
-        } else {
-            redis_store.set(&sid, constant::END_USER_SESS_ID_FIELD, &ret.id)?;
         }
-    } else {
-        redis_store.set(&sid, constant::END_USER_SESS_ID_FIELD, &ret.id)?;
     }
+    let oidc_sid = oidc_sid_after_login(&sid, &ret.id, &redis_store)?;
+    redis_store.set(&sid, constant::END_USER_SESS_ID_FIELD, &ret.id)?;
+    redis_store.set(&sid, constant::END_USER_OIDC_SID_FIELD, &oidc_sid)?;
+    redis_store.set(
+        &sid,
+        constant::END_USER_AUTH_TIME_FIELD,
+        &Utc::now().timestamp().to_string(),
+    )?;
+    redis_store.set(
+        &sid,
+        constant::END_USER_AMR_FIELD,
+        &AuthenticationMethod::to_space_delimited(&vec![AuthenticationMethod::Pwd]),
+    )?;
     Ok(EndUserLoginResponse {
         sid,
         end_user_id: ret.id.clone(),
     })
 }
 
+/// Returns the session ID for OpenID Connect, which is not the secret `sid` of the cookie.
+/// It is kept while the same end user logs in again in the session (prompt=login or max_age),
+/// so that the logout notifications find the grants issued before.
+fn oidc_sid_after_login(
+    sid: &String,
+    end_user_id: &String,
+    redis_store: &RedisStore,
+) -> Result<String, ed::Error> {
+    let logged_in_id = redis_store.get(sid, constant::END_USER_SESS_ID_FIELD)?;
+    if logged_in_id.as_ref() == Some(end_user_id) {
+        if let Some(oidc_sid) = redis_store.get(sid, constant::END_USER_OIDC_SID_FIELD)? {
+            return Ok(oidc_sid);
+        }
+    }
+    Ok(generate_random_id(32usize))
+}
+
+/// Terminates the session of the end user.
+/// The response has the `frontchannel_logout_uris` of the clients in the session,
+/// which the SPA loads in iframes as with RP-Initiated Logout.
 #[post("/logout")]
 pub fn logout(
     authorization_header: AuthorizationHeader,
     redis_store: RedisStore,
-) -> Result<(), ed::Error> {
-    if let AuthorizationType::Bearer = authorization_header.auth_type {
-        if let Some(token) = authorization_header.token {
-            redis_store.del(&token, None)?
-        }
+    server: Server,
+) -> Result<EndSessionRet, ed::Error> {
+    let token = match authorization_header.auth_type {
+        AuthorizationType::Bearer => authorization_header.token,
+        _ => None,
+    };
+    let (end_user_id, oidc_sid, client_ids) = match token {
+        Some(ref token) => (
+            redis_store.get(token, constant::END_USER_SESS_ID_FIELD)?,
+            redis_store.get(token, constant::END_USER_OIDC_SID_FIELD)?,
+            redis_store.get_clients(token)?,
+        ),
+        None => (None, None, Vec::new()),
+    };
+    let cmd = EndSessionCmd {
+        end_user_id: end_user_id.clone(),
+        id_token_hint: None,
+        client_id: None,
+        post_logout_redirect_uri: None,
+        state: None,
+        sid: oidc_sid.clone(),
+        client_ids,
+    };
+    let service = server.oidc_service();
+    let ret = service.end_session(&cmd)?;
+    if let Some(ref token) = token {
+        redis_store.del(token, None)?;
+    }
+    if let (Some(ref oidc_sid), Some(ref end_user_id)) = (oidc_sid, end_user_id) {
+        service.notify_logout(oidc_sid, end_user_id);
     }
-    Ok(())
+    Ok(ret)
 }
 
 #[get("/")]
@@ -132,6 +189,7 @@ pub struct EndUserRegisterForm {
     pub zoneinfo: Option<String>,
     pub locale: Option<String>,
     pub phone_number: Option<String>,
+    pub address: Option<Address>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -175,6 +233,7 @@ pub fn register_end_user<'r>(
         zoneinfo: form.zoneinfo,
         locale: form.locale,
         phone_number: form.phone_number,
+        address: form.address,
     };
     let service = server.end_user_service();
     cors.responder(
@@ -200,6 +259,7 @@ pub struct EndUserUpdateForm {
     pub zoneinfo: Option<String>,
     pub locale: Option<String>,
     pub phone_number: Option<String>,
+    pub address: Option<Address>,
     pub new_password: Option<String>,
     pub current_password: Option<String>,
 }
@@ -238,6 +298,7 @@ pub fn update_end_user(
                 zoneinfo: form.zoneinfo,
                 locale: form.locale,
                 phone_number: form.phone_number,
+                address: form.address,
             };
             let service = server.end_user_service();
             return service.update_end_user(&cmd);
@@ -3,4 +3,5 @@ pub mod client;
 pub mod common;
 pub mod end_user;
 pub mod oidc;
+pub mod registration;
 pub mod resource;
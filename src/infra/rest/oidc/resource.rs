@@ -1,3 +1,4 @@
+use error_chain::ChainedError;
 use rocket::request::LenientForm;
 use rocket_contrib::Json;
 use rocket_cors::{self, Guard};
@@ -6,23 +7,40 @@ use app::oidc::{GetTokensCmd, OidcService, OidcServiceComponent};
 use domain::error::domain as ed;
 use self::ed::ErrorKind as ek;
 use constant;
-use domain::model::EndUserClaims;
-use domain::service::{AcceptClientCmd, AuthorizeCmd, AuthorizeRet, IntrospectCmd, IntrospectRet,
-                      TokensRet, UserinfoCmd};
-use infra::rest::common::{AuthorizationHeader, AuthorizationType};
+use domain::model::{ClientCertificate, EndUserClaims, JwkSet, ProviderMetadata};
+use domain::service::{AcceptClientCmd, AcceptDeviceCmd, AuthorizeCmd, AuthorizeRet,
+                      ClientAuthentication, DeviceAuthorizationCmd, DeviceAuthorizationRet,
+                      EndSessionCmd, EndSessionRet, IntrospectCmd, IntrospectRet,
+                      PushAuthorizationRequestCmd, PushAuthorizationRequestRet, ReviewDeviceCmd,
+                      ReviewDeviceRet, RevocationCmd, TokensRet, UserinfoCmd};
+use infra::rest::common::{AuthorizationHeader, AuthorizationType, ClientCertificateHeader,
+                          DpopHeader};
 use infra::session::RedisStore;
 use server::Server;
 
 #[derive(Deserialize, Debug, FromForm)]
 pub struct AuthorizeParams {
-    pub response_type: String,
+    // response_type and redirect_uri may be omitted with a pushed request_uri.
+    pub response_type: Option<String>,
     pub client_id: String,
-    pub redirect_uri: String,
+    pub redirect_uri: Option<String>,
     pub scope: Option<String>,
     pub state: Option<String>,
     pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub prompt: Option<String>,
+    pub max_age: Option<String>,
+    pub acr_values: Option<String>,
+    pub claims: Option<String>,
+    pub response_mode: Option<String>,
+    pub request: Option<String>,
+    pub request_uri: Option<String>,
 }
 
+/// Authorizes the request of the client.
+/// A request without a session is also passed to the service,
+/// so that `prompt=none` is answered with `login_required` to the client.
 #[get("/authorize?<authorize_params>")]
 pub fn authorize(
     authorize_params: AuthorizeParams,
@@ -30,30 +48,59 @@ pub fn authorize(
     redis_store: RedisStore,
     server: Server,
 ) -> AuthorizeRet {
-    if let AuthorizationType::Bearer = authorization_header.auth_type {
-        if let Some(token) = authorization_header.token {
-            let end_user_id = redis_store.get(&token, constant::END_USER_SESS_ID_FIELD);
-            if let Err(e) = end_user_id {
-                return AuthorizeRet::error(e, None, None);
-            }
-            let cmd = AuthorizeCmd {
-                end_user_id: end_user_id.unwrap(),
-                client_id: authorize_params.client_id.clone(),
-                response_type: authorize_params.response_type.clone(),
-                redirect_uri: authorize_params.redirect_uri.clone(),
-                scope: authorize_params.scope.clone(),
-                state: authorize_params.state.clone(),
-                nonce: authorize_params.nonce.clone(),
-            };
-            let service = server.oidc_service();
-            return service.authorize(&cmd);
-        }
+    let token = match authorization_header.auth_type {
+        AuthorizationType::Bearer => authorization_header.token,
+        _ => None,
+    };
+    let (end_user_id, sid, auth_time, amr) = match token {
+        Some(ref token) => match load_session(token, &redis_store) {
+            Ok(session) => session,
+            Err(e) => return AuthorizeRet::error(e, None, None),
+        },
+        None => (None, None, None, None),
+    };
+    let cmd = AuthorizeCmd {
+        end_user_id,
+        client_id: authorize_params.client_id.clone(),
+        response_type: authorize_params.response_type.clone().unwrap_or_default(),
+        redirect_uri: authorize_params.redirect_uri.clone().unwrap_or_default(),
+        scope: authorize_params.scope.clone(),
+        state: authorize_params.state.clone(),
+        nonce: authorize_params.nonce.clone(),
+        code_challenge: authorize_params.code_challenge.clone(),
+        code_challenge_method: authorize_params.code_challenge_method.clone(),
+        prompt: authorize_params.prompt.clone(),
+        max_age: authorize_params.max_age.clone(),
+        acr_values: authorize_params.acr_values.clone(),
+        claims: authorize_params.claims.clone(),
+        response_mode: authorize_params.response_mode.clone(),
+        request: authorize_params.request.clone(),
+        request_uri: authorize_params.request_uri.clone(),
+        sid,
+        auth_time,
+        amr,
+    };
+    let service = server.oidc_service();
+    let ret = service.authorize(&cmd);
+    if let Some(ref token) = token {
+        record_client(&ret, token, &redis_store);
     }
-    AuthorizeRet::error(
-        ek::RequireLogin("Login required.".to_string()).into(),
-        None,
-        None,
-    )
+    ret
+}
+
+/// Returns the end user ID, the session ID for OpenID Connect,
+/// the authentication time and the authentication methods of the session.
+fn load_session(
+    token: &String,
+    redis_store: &RedisStore,
+) -> Result<(Option<String>, Option<String>, Option<i64>, Option<String>), ed::Error> {
+    let end_user_id = redis_store.get(token, constant::END_USER_SESS_ID_FIELD)?;
+    let sid = redis_store.get(token, constant::END_USER_OIDC_SID_FIELD)?;
+    let auth_time = redis_store
+        .get(token, constant::END_USER_AUTH_TIME_FIELD)?
+        .and_then(|t| t.parse::<i64>().ok());
+    let amr = redis_store.get(token, constant::END_USER_AMR_FIELD)?;
+    Ok((end_user_id, sid, auth_time, amr))
 }
 
 #[derive(Deserialize, Debug)]
@@ -82,7 +129,9 @@ pub fn accept_client(
                 grant_id: form.grant_id.clone(),
             };
             let service = server.oidc_service();
-            return service.accept_client(&cmd);
+            let ret = service.accept_client(&cmd);
+            record_client(&ret, &token, &redis_store);
+            return ret;
         }
     }
     AuthorizeRet::error(
@@ -92,6 +141,16 @@ pub fn accept_client(
     )
 }
 
+/// Records the client in the session for Front-Channel Logout.
+/// The tokens have been already issued, so a failure is only logged.
+fn record_client(ret: &AuthorizeRet, sid: &str, redis_store: &RedisStore) {
+    if let Some(ref client_id) = ret.client_id {
+        if let Err(e) = redis_store.add_client(sid, client_id) {
+            error!("{}", e.display_chain().to_string());
+        }
+    }
+}
+
 #[derive(FromForm, Debug)]
 pub struct TokensForm {
     pub grant_type: Option<String>,
@@ -100,6 +159,20 @@ pub struct TokensForm {
     pub scope: Option<String>,
     pub username: Option<String>,
     pub password: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub client_assertion_type: Option<String>,
+    pub client_assertion: Option<String>,
+    pub code_verifier: Option<String>,
+    pub device_code: Option<String>,
+    pub subject_token: Option<String>,
+    pub subject_token_type: Option<String>,
+    pub actor_token: Option<String>,
+    pub actor_token_type: Option<String>,
+    pub resource: Option<String>,
+    pub audience: Option<String>,
+    pub requested_token_type: Option<String>,
+    pub assertion: Option<String>,
 }
 
 #[post("/tokens", data = "<input>")]
@@ -107,32 +180,215 @@ pub fn get_tokens<'r>(
     cors: Guard<'r>,
     input: LenientForm<TokensForm>,
     authorization_header: AuthorizationHeader,
+    client_certificate_header: ClientCertificateHeader,
+    dpop_header: DpopHeader,
     server: Server,
 ) -> rocket_cors::Responder<'r, TokensRet> {
-    let (client_id, client_secret) = match authorization_header.get_basic_name_and_password() {
-        Some((a, b)) => (Some(a), Some(b)),
-        None => (None, None),
-    };
     let form = input.into_inner();
+    let dpop_proof = match dpop_header.proof() {
+        Ok(proof) => proof,
+        Err(e) => return cors.responder(TokensRet::error(e)),
+    };
+    let client_auth = client_authentication(
+        &authorization_header,
+        &form.client_id,
+        &form.client_secret,
+        &form.client_assertion_type,
+        &form.client_assertion,
+        &client_certificate_header.certificate,
+    );
     let cmd = GetTokensCmd {
-        client_id,
-        client_secret,
+        client_auth,
         grant_type: form.grant_type.clone(),
         code: form.code.clone(),
         refresh_token: form.refresh_token.clone(),
         scope: form.scope.clone(),
         username: form.username.clone(),
         password: form.password.clone(),
+        code_verifier: form.code_verifier.clone(),
+        device_code: form.device_code.clone(),
+        subject_token: form.subject_token.clone(),
+        subject_token_type: form.subject_token_type.clone(),
+        actor_token: form.actor_token.clone(),
+        actor_token_type: form.actor_token_type.clone(),
+        resource: form.resource.clone(),
+        audience: form.audience.clone(),
+        requested_token_type: form.requested_token_type.clone(),
+        assertion: form.assertion.clone(),
+        dpop_proof,
     };
     let service = server.oidc_service();
     let ret = service.get_tokens(&cmd);
     cors.responder(ret)
 }
 
+#[derive(FromForm, Debug)]
+pub struct PushedAuthorizationForm {
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub client_assertion_type: Option<String>,
+    pub client_assertion: Option<String>,
+    pub response_type: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub scope: Option<String>,
+    pub state: Option<String>,
+    pub nonce: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub prompt: Option<String>,
+    pub max_age: Option<String>,
+    pub acr_values: Option<String>,
+    pub claims: Option<String>,
+    pub response_mode: Option<String>,
+    pub request: Option<String>,
+    pub request_uri: Option<String>,
+}
+
+#[post("/par", data = "<input>")]
+pub fn push_authorization_request<'r>(
+    cors: Guard<'r>,
+    input: LenientForm<PushedAuthorizationForm>,
+    authorization_header: AuthorizationHeader,
+    client_certificate_header: ClientCertificateHeader,
+    server: Server,
+) -> rocket_cors::Responder<'r, Result<PushAuthorizationRequestRet, ed::Error>> {
+    let form = input.into_inner();
+    let client_auth = client_authentication(
+        &authorization_header,
+        &form.client_id,
+        &form.client_secret,
+        &form.client_assertion_type,
+        &form.client_assertion,
+        &client_certificate_header.certificate,
+    );
+    let cmd = PushAuthorizationRequestCmd {
+        client_auth,
+        response_type: form.response_type.clone(),
+        redirect_uri: form.redirect_uri.clone(),
+        scope: form.scope.clone(),
+        state: form.state.clone(),
+        nonce: form.nonce.clone(),
+        code_challenge: form.code_challenge.clone(),
+        code_challenge_method: form.code_challenge_method.clone(),
+        prompt: form.prompt.clone(),
+        max_age: form.max_age.clone(),
+        acr_values: form.acr_values.clone(),
+        claims: form.claims.clone(),
+        response_mode: form.response_mode.clone(),
+        request: form.request.clone(),
+        request_uri: form.request_uri.clone(),
+    };
+    let service = server.oidc_service();
+    let ret = service.push_authorization_request(&cmd);
+    cors.responder(ret)
+}
+
+#[derive(FromForm, Debug)]
+pub struct DeviceAuthorizationForm {
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub client_assertion_type: Option<String>,
+    pub client_assertion: Option<String>,
+    pub scope: Option<String>,
+}
+
+#[post("/device_authorization", data = "<input>")]
+pub fn authorize_device<'r>(
+    cors: Guard<'r>,
+    input: LenientForm<DeviceAuthorizationForm>,
+    authorization_header: AuthorizationHeader,
+    client_certificate_header: ClientCertificateHeader,
+    server: Server,
+) -> rocket_cors::Responder<'r, Result<DeviceAuthorizationRet, ed::Error>> {
+    let form = input.into_inner();
+    let client_auth = client_authentication(
+        &authorization_header,
+        &form.client_id,
+        &form.client_secret,
+        &form.client_assertion_type,
+        &form.client_assertion,
+        &client_certificate_header.certificate,
+    );
+    let cmd = DeviceAuthorizationCmd {
+        client_auth,
+        scope: form.scope.clone(),
+    };
+    let service = server.oidc_service();
+    let ret = service.authorize_device(&cmd);
+    cors.responder(ret)
+}
+
+#[derive(FromForm, Debug)]
+pub struct ReviewDeviceParams {
+    pub user_code: String,
+}
+
+#[get("/device?<review_device_params>")]
+pub fn review_device(
+    review_device_params: ReviewDeviceParams,
+    authorization_header: AuthorizationHeader,
+    redis_store: RedisStore,
+    server: Server,
+) -> Result<ReviewDeviceRet, ed::Error> {
+    let end_user_id = match authorization_header.auth_type {
+        AuthorizationType::Bearer => match authorization_header.token {
+            Some(ref token) => redis_store.get(token, constant::END_USER_SESS_ID_FIELD)?,
+            None => None,
+        },
+        _ => None,
+    };
+    let cmd = ReviewDeviceCmd {
+        end_user_id,
+        user_code: review_device_params.user_code,
+    };
+    let service = server.oidc_service();
+    service.review_device(&cmd)
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DeviceAcceptanceForm {
+    pub action: String,
+    pub user_code: String,
+}
+
+#[post("/device", data = "<input>")]
+pub fn accept_device(
+    input: Json<DeviceAcceptanceForm>,
+    authorization_header: AuthorizationHeader,
+    redis_store: RedisStore,
+    server: Server,
+) -> Result<(), ed::Error> {
+    let token = match authorization_header.auth_type {
+        AuthorizationType::Bearer => authorization_header.token,
+        _ => None,
+    };
+    let token = match token {
+        Some(t) => t,
+        None => return Err(ek::RequireLogin("Login required.".to_string()).into()),
+    };
+    let form = input.into_inner();
+    let cmd = AcceptDeviceCmd {
+        end_user_id: redis_store.get(&token, constant::END_USER_SESS_ID_FIELD)?,
+        action: form.action,
+        user_code: form.user_code,
+        sid: redis_store.get(&token, constant::END_USER_OIDC_SID_FIELD)?,
+        auth_time: redis_store
+            .get(&token, constant::END_USER_AUTH_TIME_FIELD)?
+            .and_then(|t| t.parse::<i64>().ok()),
+        amr: redis_store.get(&token, constant::END_USER_AMR_FIELD)?,
+    };
+    let service = server.oidc_service();
+    service.accept_device(&cmd)
+}
+
 #[derive(FromForm, Debug)]
 pub struct IntrospectForm {
     pub token: String,
     pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub client_assertion_type: Option<String>,
+    pub client_assertion: Option<String>,
 }
 
 #[post("/introspect", data = "<input>")]
@@ -140,16 +396,20 @@ pub fn introspect<'r>(
     cors: Guard<'r>,
     input: LenientForm<IntrospectForm>,
     authorization_header: AuthorizationHeader,
+    client_certificate_header: ClientCertificateHeader,
     server: Server,
 ) -> rocket_cors::Responder<'r, Result<IntrospectRet, ed::Error>> {
-    let (client_id, client_secret) = match authorization_header.get_basic_name_and_password() {
-        Some((a, b)) => (Some(a), Some(b)),
-        None => (None, None),
-    };
     let form = input.into_inner();
+    let client_auth = client_authentication(
+        &authorization_header,
+        &form.client_id,
+        &form.client_secret,
+        &form.client_assertion_type,
+        &form.client_assertion,
+        &client_certificate_header.certificate,
+    );
     let cmd = IntrospectCmd {
-        client_id,
-        client_secret,
+        client_auth,
         token: form.token.clone(),
         token_type_hint: form.token_type_hint.clone(),
     };
@@ -158,30 +418,195 @@ pub fn introspect<'r>(
     cors.responder(ret)
 }
 
+#[derive(FromForm, Debug)]
+pub struct RevocationForm {
+    pub token: String,
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub client_assertion_type: Option<String>,
+    pub client_assertion: Option<String>,
+}
+
+#[post("/revoke", data = "<input>")]
+pub fn revoke<'r>(
+    cors: Guard<'r>,
+    input: LenientForm<RevocationForm>,
+    authorization_header: AuthorizationHeader,
+    client_certificate_header: ClientCertificateHeader,
+    server: Server,
+) -> rocket_cors::Responder<'r, Result<(), ed::Error>> {
+    let form = input.into_inner();
+    let client_auth = client_authentication(
+        &authorization_header,
+        &form.client_id,
+        &form.client_secret,
+        &form.client_assertion_type,
+        &form.client_assertion,
+        &client_certificate_header.certificate,
+    );
+    let cmd = RevocationCmd {
+        client_auth,
+        token: form.token.clone(),
+        token_type_hint: form.token_type_hint.clone(),
+    };
+    let service = server.oidc_service();
+    let ret = service.revoke_token(&cmd);
+    cors.responder(ret)
+}
+
+#[derive(FromForm, Debug)]
+pub struct EndSessionParams {
+    pub id_token_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub post_logout_redirect_uri: Option<String>,
+    pub state: Option<String>,
+}
+
+#[get("/end_session?<end_session_params>")]
+pub fn end_session(
+    end_session_params: EndSessionParams,
+    authorization_header: AuthorizationHeader,
+    redis_store: RedisStore,
+    server: Server,
+) -> Result<EndSessionRet, ed::Error> {
+    execute_end_session(end_session_params, authorization_header, redis_store, server)
+}
+
+#[get("/end_session")]
+pub fn end_session_without_params(
+    authorization_header: AuthorizationHeader,
+    redis_store: RedisStore,
+    server: Server,
+) -> Result<EndSessionRet, ed::Error> {
+    let params = EndSessionParams {
+        id_token_hint: None,
+        client_id: None,
+        post_logout_redirect_uri: None,
+        state: None,
+    };
+    execute_end_session(params, authorization_header, redis_store, server)
+}
+
+/// Validates the logout request, then terminates the session of the end user.
+fn execute_end_session(
+    params: EndSessionParams,
+    authorization_header: AuthorizationHeader,
+    redis_store: RedisStore,
+    server: Server,
+) -> Result<EndSessionRet, ed::Error> {
+    let sid = match authorization_header.auth_type {
+        AuthorizationType::Bearer => authorization_header.token,
+        _ => None,
+    };
+    let (end_user_id, oidc_sid, client_ids) = match sid {
+        Some(ref sid) => (
+            redis_store.get(sid, constant::END_USER_SESS_ID_FIELD)?,
+            redis_store.get(sid, constant::END_USER_OIDC_SID_FIELD)?,
+            redis_store.get_clients(sid)?,
+        ),
+        None => (None, None, Vec::new()),
+    };
+    let cmd = EndSessionCmd {
+        end_user_id: end_user_id.clone(),
+        id_token_hint: params.id_token_hint,
+        client_id: params.client_id,
+        post_logout_redirect_uri: params.post_logout_redirect_uri,
+        state: params.state,
+        sid: oidc_sid.clone(),
+        client_ids,
+    };
+    let service = server.oidc_service();
+    let ret = service.end_session(&cmd)?;
+    if let Some(ref sid) = sid {
+        redis_store.del(sid, None)?;
+    }
+    if let (Some(ref oidc_sid), Some(ref end_user_id)) = (oidc_sid, end_user_id) {
+        service.notify_logout(oidc_sid, end_user_id);
+    }
+    Ok(ret)
+}
+
 #[get("/userinfo")]
 pub fn get_userinfo<'r>(
     cors: Guard<'r>,
     authorization_header: AuthorizationHeader,
+    client_certificate_header: ClientCertificateHeader,
+    dpop_header: DpopHeader,
     server: Server,
 ) -> rocket_cors::Responder<'r, Result<EndUserClaims, ed::Error>> {
-    if let AuthorizationType::Bearer = authorization_header.auth_type {
-        if let Some(token) = authorization_header.token {
+    // The access token is sent with either the Bearer or the DPoP scheme.
+    let dpop = match authorization_header.auth_type {
+        AuthorizationType::Bearer => Some(false),
+        AuthorizationType::Dpop => Some(true),
+        _ => None,
+    };
+    if let (Some(dpop), Some(token)) = (dpop, authorization_header.token) {
+        let ret = dpop_header.proof().and_then(|dpop_proof| {
             let cmd = UserinfoCmd {
                 access_token: token,
+                dpop,
+                dpop_proof,
+                client_certificate: client_certificate_header.certificate,
             };
             let service = server.oidc_service();
-            let ret = service.get_userinfo(&cmd);
-            return cors.responder(ret);
-        }
-    };
+            service.get_userinfo(&cmd)
+        });
+        return cors.responder(ret);
+    }
     cors.responder(Err(ek::UserinfoError(
         "Access token is required.".to_string(),
     ).into()))
 }
 
-#[get("/publickey")]
-pub fn get_publickey<'r>(cors: Guard<'r>, server: Server) -> rocket_cors::Responder<'r, String> {
+#[get("/jwks")]
+pub fn get_jwks<'r>(
+    cors: Guard<'r>,
+    server: Server,
+) -> rocket_cors::Responder<'r, Result<JwkSet, ed::Error>> {
     let service = server.oidc_service();
-    let ret = service.get_publickey();
+    let ret = service.get_jwks();
     cors.responder(ret)
 }
+
+#[get("/openid-configuration")]
+pub fn get_provider_metadata<'r>(
+    cors: Guard<'r>,
+    server: Server,
+) -> rocket_cors::Responder<'r, ProviderMetadata> {
+    let service = server.oidc_service();
+    let ret = service.get_provider_metadata();
+    cors.responder(ret)
+}
+
+/// Collects the client credentials from the Authorization header, the form
+/// and the client certificate. Public clients send only client_id in the form.
+fn client_authentication(
+    authorization_header: &AuthorizationHeader,
+    client_id: &Option<String>,
+    client_secret: &Option<String>,
+    client_assertion_type: &Option<String>,
+    client_assertion: &Option<String>,
+    client_certificate: &Option<ClientCertificate>,
+) -> ClientAuthentication {
+    let auth = ClientAuthentication {
+        client_assertion_type: client_assertion_type.clone(),
+        client_assertion: client_assertion.clone(),
+        client_certificate: client_certificate.clone(),
+        ..ClientAuthentication::default()
+    };
+    match authorization_header.get_basic_name_and_password() {
+        Some((id, secret)) => ClientAuthentication {
+            client_id: Some(id),
+            client_secret: Some(secret),
+            basic: true,
+            basic_with_form_secret: client_secret.is_some(),
+            ..auth
+        },
+        None => ClientAuthentication {
+            client_id: client_id.clone(),
+            client_secret: client_secret.clone(),
+            ..auth
+        },
+    }
+}
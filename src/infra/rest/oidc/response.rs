@@ -2,9 +2,13 @@ use rocket::response::{Responder, Response};
 use rocket::request::Request;
 use rocket::http::Status;
 use error_chain::ChainedError;
+use std::collections::HashMap;
 
-use domain::model::EndUserClaims;
-use domain::service::{AuthorizeRet, AuthorizeRetKind, IntrospectRet, TokensRet, TokensRetKind};
+use domain::error::domain as ed;
+use domain::model::{EndUserClaims, JwkSet, ProviderMetadata};
+use domain::service::{AuthorizeRet, AuthorizeRetKind, DeviceAuthorizationRet, EndSessionRet,
+                      IntrospectRet, PushAuthorizationRequestRet, ReviewDeviceRet, TokensRet,
+                      TokensRetKind};
 use infra::rest::common::CommonResponse;
 
 impl<'r> Responder<'r> for EndUserClaims {
@@ -13,23 +17,57 @@ impl<'r> Responder<'r> for EndUserClaims {
     }
 }
 
+impl<'r> Responder<'r> for JwkSet {
+    fn respond_to(self, _request: &Request) -> Result<Response<'r>, Status> {
+        CommonResponse::respond(&self, Status::Ok).ok()
+    }
+}
+
+impl<'r> Responder<'r> for ProviderMetadata {
+    fn respond_to(self, _request: &Request) -> Result<Response<'r>, Status> {
+        CommonResponse::respond(&self, Status::Ok).ok()
+    }
+}
+
 impl<'r> Responder<'r> for IntrospectRet {
     fn respond_to(self, _request: &Request) -> Result<Response<'r>, Status> {
         CommonResponse::respond(&self, Status::Ok).ok()
     }
 }
 
+impl<'r> Responder<'r> for PushAuthorizationRequestRet {
+    fn respond_to(self, _request: &Request) -> Result<Response<'r>, Status> {
+        CommonResponse::respond(&self, Status::Created).ok()
+    }
+}
+
+impl<'r> Responder<'r> for DeviceAuthorizationRet {
+    fn respond_to(self, _request: &Request) -> Result<Response<'r>, Status> {
+        CommonResponse::respond(&self, Status::Ok).ok()
+    }
+}
+
+impl<'r> Responder<'r> for ReviewDeviceRet {
+    fn respond_to(self, _request: &Request) -> Result<Response<'r>, Status> {
+        CommonResponse::respond(&self, Status::Ok).ok()
+    }
+}
+
 impl<'r> Responder<'r> for AuthorizeRet {
     fn respond_to(self, _request: &Request) -> Result<Response<'r>, Status> {
         match self.redirect_uri {
             Some(uri) => {
                 match self.kind {
-                    AuthorizeRetKind::Error { ref _cause, .. } => {
+                    AuthorizeRetKind::Error { ref _cause, .. }
+                    | AuthorizeRetKind::Jwt {
+                        _cause: Some(ref _cause),
+                        ..
+                    } => {
                         error!("{}", _cause.display_chain().to_string());
                     }
                     _ => {}
                 }
-                CommonResponse::redirect(self.kind, uri).ok()
+                CommonResponse::redirect(self.kind, uri, &self.response_mode.base()).ok()
             }
             None => {
                 let status = match self.kind {
@@ -46,13 +84,57 @@ impl<'r> Responder<'r> for AuthorizeRet {
     }
 }
 
+/// The SPA loads `frontchannel_logout_uris` in iframes before following `location`.
+#[derive(Serialize)]
+struct EndSessionResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    state: Option<String>,
+    frontchannel_logout_uris: Vec<String>,
+}
+
+impl<'r> Responder<'r> for EndSessionRet {
+    fn respond_to(self, _request: &Request) -> Result<Response<'r>, Status> {
+        let content = match self.post_logout_redirect_uri {
+            Some(uri) => {
+                let mut query = HashMap::new();
+                if let Some(state) = self.state {
+                    query.insert("state", state);
+                }
+                EndSessionResponse {
+                    status: Some("redirect".to_string()),
+                    location: Some(CommonResponse::redirect_uri(&query, uri)),
+                    state: None,
+                    frontchannel_logout_uris: self.frontchannel_logout_uris,
+                }
+            }
+            None => EndSessionResponse {
+                status: None,
+                location: None,
+                state: self.state,
+                frontchannel_logout_uris: self.frontchannel_logout_uris,
+            },
+        };
+        CommonResponse::respond(content, Status::Ok).ok()
+    }
+}
+
 impl<'r> Responder<'r> for TokensRet {
     fn respond_to(self, _request: &Request) -> Result<Response<'r>, Status> {
         let status = match self.kind {
             TokensRetKind::Error { ref _cause, .. } => {
                 error!("{}", _cause.display_chain().to_string());
-                let (status, _, _) = _cause.convert_status_content();
-                status
+                match *_cause.kind() {
+                    // The token endpoint responds with 400 unlike protected resources (RFC 9449).
+                    ed::ErrorKind::InvalidDpopProof(_) => Status::BadRequest,
+                    _ => {
+                        let (status, _, _) = _cause.convert_status_content();
+                        status
+                    }
+                }
             }
             _ => Status::Ok,
         };
@@ -0,0 +1,4 @@
+mod resource;
+mod response;
+pub use self::resource::*;
+pub use self::response::*;
@@ -0,0 +1,177 @@
+use rocket::response::status::{Created, NoContent};
+use rocket_contrib::Json;
+use rocket_cors::{self, Guard};
+use serde_json::{self, Value};
+
+use app::client::{ClientRegistrationRepr, ClientService, ClientServiceComponent,
+                  DynamicRegisterClientCmd, UpdateClientRegistrationCmd};
+use domain::error::domain as ed;
+use domain::model::{ClientMetadata, JwkSet};
+use infra::rest::common::{AuthorizationHeader, AuthorizationType};
+use server::Server;
+
+/// The client metadata of the registration request.
+/// `resource_id` is an extension to bind the client to a resource.
+/// `client_id` and `client_secret` are used only by the update request.
+/// The admin-only metadata such as `tls_client_auth_subject_dn` is not accepted.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientRegistrationForm {
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+    pub resource_id: Option<String>,
+    pub client_name: Option<String>,
+    #[serde(default)]
+    pub grant_types: Vec<String>,
+    #[serde(default)]
+    pub response_types: Vec<String>,
+    pub token_endpoint_auth_method: Option<String>,
+    pub logo_uri: Option<String>,
+    pub client_uri: Option<String>,
+    pub policy_uri: Option<String>,
+    pub tos_uri: Option<String>,
+    #[serde(default)]
+    pub contacts: Vec<String>,
+    #[serde(default)]
+    pub post_logout_redirect_uris: Vec<String>,
+    pub backchannel_logout_uri: Option<String>,
+    #[serde(default)]
+    pub backchannel_logout_session_required: bool,
+    pub frontchannel_logout_uri: Option<String>,
+    #[serde(default)]
+    pub frontchannel_logout_session_required: bool,
+    pub jwks: Option<JwkSet>,
+    #[serde(default)]
+    pub request_uris: Vec<String>,
+    #[serde(default)]
+    pub require_pushed_authorization_requests: bool,
+    #[serde(default)]
+    pub tls_client_certificate_bound_access_tokens: bool,
+}
+
+impl ClientRegistrationForm {
+    fn to_metadata(&self) -> ClientMetadata {
+        ClientMetadata {
+            client_name: self.client_name.clone(),
+            grant_types: self.grant_types.clone(),
+            response_types: self.response_types.clone(),
+            token_endpoint_auth_method: self.token_endpoint_auth_method.clone(),
+            logo_uri: self.logo_uri.clone(),
+            client_uri: self.client_uri.clone(),
+            policy_uri: self.policy_uri.clone(),
+            tos_uri: self.tos_uri.clone(),
+            contacts: self.contacts.clone(),
+            post_logout_redirect_uris: self.post_logout_redirect_uris.clone(),
+            backchannel_logout_uri: self.backchannel_logout_uri.clone(),
+            backchannel_logout_session_required: self.backchannel_logout_session_required,
+            frontchannel_logout_uri: self.frontchannel_logout_uri.clone(),
+            frontchannel_logout_session_required: self.frontchannel_logout_session_required,
+            jwks: self.jwks.clone(),
+            request_uris: self.request_uris.clone(),
+            require_pushed_authorization_requests: self.require_pushed_authorization_requests,
+            tls_client_certificate_bound_access_tokens: self
+                .tls_client_certificate_bound_access_tokens,
+            ..ClientMetadata::default()
+        }
+    }
+}
+
+fn bearer_token(authorization_header: AuthorizationHeader) -> Option<String> {
+    match authorization_header.auth_type {
+        AuthorizationType::Bearer => authorization_header.token,
+        _ => None,
+    }
+}
+
+#[post("/register", data = "<input>")]
+pub fn register_client<'r>(
+    cors: Guard<'r>,
+    input: Json<ClientRegistrationForm>,
+    authorization_header: AuthorizationHeader,
+    server: Server,
+) -> rocket_cors::Responder<'r, Result<Created<ClientRegistrationRepr>, ed::Error>> {
+    let form = input.into_inner();
+    let cmd = DynamicRegisterClientCmd {
+        initial_access_token: bearer_token(authorization_header),
+        redirect_uris: form.redirect_uris.clone(),
+        resource_id: form.resource_id.clone(),
+        metadata: form.to_metadata(),
+    };
+    let service = server.client_service();
+    cors.responder(
+        service
+            .register_client_dynamically(&cmd)
+            .map(|r| Created(r.registration_client_uri.clone(), Some(r))),
+    )
+}
+
+#[get("/register/<id>")]
+pub fn get_client_registration<'r>(
+    cors: Guard<'r>,
+    id: String,
+    authorization_header: AuthorizationHeader,
+    server: Server,
+) -> rocket_cors::Responder<'r, Result<ClientRegistrationRepr, ed::Error>> {
+    let service = server.client_service();
+    cors.responder(service.get_client_registration(&id, &bearer_token(authorization_header)))
+}
+
+/// The fields which are not sent are kept (not deleted) to protect the settings by admins.
+#[put("/register/<id>", data = "<input>")]
+pub fn update_client_registration<'r>(
+    cors: Guard<'r>,
+    id: String,
+    input: Json<Value>,
+    authorization_header: AuthorizationHeader,
+    server: Server,
+) -> rocket_cors::Responder<'r, Result<ClientRegistrationRepr, ed::Error>> {
+    let fields = match input.into_inner() {
+        Value::Object(fields) => fields,
+        _ => {
+            return cors.responder(Err(ed::ErrorKind::InvalidClientMetadata(
+                "The request must be a JSON object.".to_string(),
+            ).into()))
+        }
+    };
+    let form = match serde_json::from_value::<ClientRegistrationForm>(Value::Object(fields.clone()))
+    {
+        Ok(form) => form,
+        Err(e) => {
+            return cors.responder(Err(
+                ed::ErrorKind::InvalidClientMetadata(format!("{}", e)).into(),
+            ))
+        }
+    };
+    let redirect_uris = if fields.contains_key("redirect_uris") {
+        Some(form.redirect_uris.clone())
+    } else {
+        None
+    };
+    let cmd = UpdateClientRegistrationCmd {
+        target_id: id,
+        registration_access_token: bearer_token(authorization_header),
+        client_id: form.client_id.clone(),
+        client_secret: form.client_secret.clone(),
+        redirect_uris,
+        resource_id: form.resource_id.clone(),
+        metadata: fields,
+    };
+    let service = server.client_service();
+    cors.responder(service.update_client_registration(&cmd))
+}
+
+#[delete("/register/<id>")]
+pub fn delete_client_registration<'r>(
+    cors: Guard<'r>,
+    id: String,
+    authorization_header: AuthorizationHeader,
+    server: Server,
+) -> rocket_cors::Responder<'r, Result<NoContent, ed::Error>> {
+    let service = server.client_service();
+    cors.responder(
+        service
+            .delete_client_registration(&id, &bearer_token(authorization_header))
+            .map(|_| NoContent),
+    )
+}
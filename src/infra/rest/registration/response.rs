@@ -0,0 +1,12 @@
+use rocket::response::{Responder, Response};
+use rocket::request::Request;
+use rocket::http::Status;
+
+use app::client::ClientRegistrationRepr;
+use infra::rest::common::CommonResponse;
+
+impl<'r> Responder<'r> for ClientRegistrationRepr {
+    fn respond_to(self, _request: &Request) -> Result<Response<'r>, Status> {
+        CommonResponse::respond(&self, Status::Ok).ok()
+    }
+}
@@ -0,0 +1,33 @@
+use redis;
+
+use domain::error::domain as ed;
+use domain::repository::DpopProofRepository;
+use infra::session::RedisClient;
+use infra::session::redis::Pool;
+use self::ed::ResultExt;
+
+/// `DpopProofRepositoryRedis` keeps `jti` of DPoP proofs in Redis until they expire.
+#[derive(Clone)]
+pub struct DpopProofRepositoryRedis {
+    pub pool: Pool,
+}
+
+impl DpopProofRepository for DpopProofRepositoryRedis {
+    fn add_if_absent(&self, jti: &String, expires_in: i64) -> Result<bool, ed::Error> {
+        let client = self.pool
+            .get()
+            .map(RedisClient)
+            .chain_err(|| ed::ErrorKind::ServerError("Connecting to Redis failed.".to_string()))?;
+        let mut cmd = redis::cmd("SET");
+        let c = cmd.arg(format!("dpop_jti:{}", jti))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(expires_in);
+        // SET with NX replies nil if the key already exists.
+        client
+            .query_cmd::<Option<String>>(c)
+            .map(|v| v.is_some())
+            .chain_err(|| ed::ErrorKind::ServerError("Setting value to Redis failed.".to_string()))
+    }
+}
@@ -1,5 +1,9 @@
+mod dpop_proof;
 mod redis;
 mod redis_store;
+mod user_code_attempt;
 
+pub use self::dpop_proof::*;
 pub use self::redis::*;
 pub use self::redis_store::*;
+pub use self::user_code_attempt::*;
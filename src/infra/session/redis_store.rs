@@ -54,17 +54,47 @@ impl RedisStore {
         }
     }
 
+    /// Deletes the field, or the whole session including its clients if `field` is None.
     pub fn del(&self, key: &str, field: Option<&str>) -> Result<(), ed::Error> {
         let mut pipe = redis::pipe();
         let p;
         match field {
             Some(_) => p = pipe.cmd("HDEL").arg(key).arg(field),
-            None => p = pipe.cmd("DEL").arg(key),
+            None => p = pipe.cmd("DEL").arg(key).arg(clients_key(key)),
         };
         self.client.query_pipeline::<()>(p).chain_err(|| {
             ed::ErrorKind::ServerError("Deleting value from Redis failed.".to_string())
         })
     }
+
+    /// Records the client which has been issued tokens in the session.
+    pub fn add_client(&self, key: &str, client_id: &str) -> Result<(), ed::Error> {
+        let clients_key = clients_key(key);
+        let mut pipe = redis::pipe();
+        let p = pipe.cmd("SADD")
+            .arg(&clients_key)
+            .arg(client_id)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&clients_key)
+            .arg(AppConfig::redis_expires_sec());
+        self.client
+            .query_pipeline::<()>(p)
+            .chain_err(|| ed::ErrorKind::ServerError("Adding client to Redis failed.".to_string()))
+    }
+
+    /// Returns the clients which have been issued tokens in the session.
+    pub fn get_clients(&self, key: &str) -> Result<Vec<String>, ed::Error> {
+        let mut cmd = redis::cmd("SMEMBERS");
+        let c = cmd.arg(clients_key(key));
+        self.client.query_cmd::<Vec<String>>(c).chain_err(|| {
+            ed::ErrorKind::ServerError("Getting clients from Redis failed.".to_string())
+        })
+    }
+}
+
+fn clients_key(key: &str) -> String {
+    format!("{}:clients", key)
 }
 
 impl<'a, 'r> FromRequest<'a, 'r> for RedisStore {
@@ -0,0 +1,54 @@
+use redis;
+
+use domain::error::domain as ed;
+use domain::repository::UserCodeAttemptRepository;
+use infra::session::RedisClient;
+use infra::session::redis::Pool;
+use self::ed::ResultExt;
+
+/// `UserCodeAttemptRepositoryRedis` counts wrong `user_code`s in Redis.
+#[derive(Clone)]
+pub struct UserCodeAttemptRepositoryRedis {
+    pub pool: Pool,
+}
+
+impl UserCodeAttemptRepositoryRedis {
+    fn client(&self) -> Result<RedisClient, ed::Error> {
+        self.pool
+            .get()
+            .map(RedisClient)
+            .chain_err(|| ed::ErrorKind::ServerError("Connecting to Redis failed.".to_string()))
+    }
+}
+
+impl UserCodeAttemptRepository for UserCodeAttemptRepositoryRedis {
+    fn count(&self, end_user_id: &String) -> Result<i64, ed::Error> {
+        let mut cmd = redis::cmd("GET");
+        let c = cmd.arg(attempts_key(end_user_id));
+        self.client()?
+            .query_cmd::<Option<i64>>(c)
+            .map(|v| v.unwrap_or(0))
+            .chain_err(|| {
+                ed::ErrorKind::ServerError("Getting value from Redis failed.".to_string())
+            })
+    }
+
+    fn add(&self, end_user_id: &String, expires_in: i64) -> Result<(), ed::Error> {
+        let key = attempts_key(end_user_id);
+        let mut pipe = redis::pipe();
+        let p = pipe.cmd("INCR")
+            .arg(&key)
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(expires_in)
+            .ignore();
+        self.client()?
+            .query_pipeline::<()>(p)
+            .chain_err(|| ed::ErrorKind::ServerError("Setting value to Redis failed.".to_string()))
+    }
+}
+
+fn attempts_key(end_user_id: &str) -> String {
+    format!("user_code_attempts:{}", end_user_id)
+}
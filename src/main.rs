@@ -23,6 +23,8 @@ extern crate r2d2_redis;
 extern crate rand;
 extern crate redis;
 extern crate regex;
+extern crate reqwest;
+extern crate ring;
 extern crate rocket;
 extern crate rocket_contrib;
 extern crate rocket_cors;
@@ -31,6 +33,7 @@ extern crate serde;
 extern crate serde_derive;
 extern crate serde_json;
 extern crate serde_urlencoded;
+extern crate sha2;
 extern crate time;
 extern crate url;
 
@@ -42,10 +45,17 @@ mod infra;
 mod util;
 mod server;
 
+use std::thread;
+use std::time::Duration;
+
 use rocket_cors::Cors;
 
+use app::oidc::{OidcService, OidcServiceComponent};
+use config::AppConfig;
 use infra::rest;
+use infra::rest::common::ClientCertificateConfig;
 use infra::session::RedisClient;
+use server::Server;
 
 fn configure_cors() -> Cors {
     Cors {
@@ -53,12 +63,25 @@ fn configure_cors() -> Cors {
     }
 }
 
+/// Sends queued logout tokens of Back-Channel Logout in the background.
+fn spawn_logout_token_worker(server: Server) {
+    let interval = Duration::from_secs(AppConfig::backchannel_logout_poll_interval_sec());
+    thread::spawn(move || loop {
+        server.oidc_service().deliver_pending_logout_tokens();
+        thread::sleep(interval);
+    });
+}
+
 fn main() {
     dotenv::from_filename("./Config.env").ok();
+    // Fail fast rather than on the first use of a signing key.
+    AppConfig::signing_key_encryption_key();
     let server = server::build_server();
+    spawn_logout_token_worker(server.clone());
     let cors = configure_cors();
     rocket::ignite()
         .manage(RedisClient::init_pool())
+        .manage(ClientCertificateConfig::from_env())
         .manage(server)
         .manage(cors)
         .mount("/", rocket_cors::catch_all_options_routes())
@@ -70,7 +93,10 @@ fn main() {
                 rest::admin::get_admin,
                 rest::admin::register_admin,
                 rest::admin::update_admin,
-                rest::admin::delete_admin
+                rest::admin::delete_admin,
+                rest::admin::get_signing_keys,
+                rest::admin::register_signing_key,
+                rest::admin::rotate_signing_keys
             ],
         )
         .mount(
@@ -117,11 +143,23 @@ fn main() {
             routes![
                 rest::oidc::authorize,
                 rest::oidc::accept_client,
+                rest::oidc::push_authorization_request,
+                rest::oidc::authorize_device,
+                rest::oidc::review_device,
+                rest::oidc::accept_device,
                 rest::oidc::get_tokens,
                 rest::oidc::introspect,
+                rest::oidc::revoke,
+                rest::oidc::end_session,
+                rest::oidc::end_session_without_params,
                 rest::oidc::get_userinfo,
-                rest::oidc::get_publickey,
+                rest::oidc::get_jwks,
+                rest::registration::register_client,
+                rest::registration::get_client_registration,
+                rest::registration::update_client_registration,
+                rest::registration::delete_client_registration,
             ],
         )
+        .mount("/.well-known", routes![rest::oidc::get_provider_metadata])
         .launch();
 }
@@ -8,28 +8,53 @@ use app::client::ClientServiceComponent;
 use app::end_user::EndUserServiceComponent;
 use app::oidc::OidcServiceComponent;
 use app::resource::ResourceServiceComponent;
+use config::AppConfig;
 use domain::repository::{AccessTokenRepositoryComponent, AdminRepositoryComponent,
-                         ClientRepositoryComponent, EndUserRepositoryComponent,
-                         GrantRepositoryComponent, IdTokenRepositoryComponent,
-                         RefreshTokenRepositoryComponent, ResourceRepositoryComponent};
-use domain::service::{AuthorizeServiceComponent, ClientCredentialsServiceComponent,
-                      IntrospectServiceComponent, KeyServiceComponent,
-                      RefreshTokenServiceComponent,
-                      ResourceOwnerPasswordCredentialsServiceComponent, UserinfoServiceComponent};
+                         ClientRepositoryComponent, ConsumedAssertionRepositoryComponent,
+                         DeviceAuthorizationRepositoryComponent, DpopProofRepositoryComponent,
+                         EndUserRepositoryComponent, GrantRepositoryComponent,
+                         IdTokenRepositoryComponent, LogoutDeliveryRepositoryComponent,
+                         PushedAuthorizationRequestRepositoryComponent,
+                         RefreshTokenRepositoryComponent, ResourceRepositoryComponent,
+                         SigningKeyRepositoryComponent, UserCodeAttemptRepositoryComponent};
+use domain::service::{AuthorizeServiceComponent, BackchannelLogoutServiceComponent,
+                      ClientAuthenticationServiceComponent, ClientCredentialsServiceComponent,
+                      DeviceAuthorizationServiceComponent, DpopServiceComponent,
+                      EndSessionServiceComponent, IntrospectServiceComponent,
+                      JwtBearerServiceComponent, KeyServiceComponent, LogoutTokenSenderComponent,
+                      PushedAuthorizationServiceComponent, RefreshTokenServiceComponent,
+                      RequestObjectFetcherComponent,
+                      ResourceOwnerPasswordCredentialsServiceComponent,
+                      RevocationServiceComponent, TokenExchangeServiceComponent,
+                      UserinfoServiceComponent};
+use infra::http::{LogoutTokenSenderHttp, RequestObjectFetcherHttp};
 use infra::persistence::{AccessTokenRepositoryMongo, AdminRepositoryMongo, ClientRepositoryMongo,
+                         ConsumedAssertionRepositoryMongo, DeviceAuthorizationRepositoryMongo,
                          EndUserRepositoryMongo, GrantRepositoryMongo, IdTokenRepositoryMongo,
-                         MongoClient, RefreshTokenRepositoryMongo, ResourceRepositoryMongo};
+                         LogoutDeliveryRepositoryMongo, MongoClient,
+                         PushedAuthorizationRequestRepositoryMongo, RefreshTokenRepositoryMongo,
+                         ResourceRepositoryMongo, SigningKeyRepositoryMongo};
+use infra::session::{DpopProofRepositoryRedis, RedisClient, UserCodeAttemptRepositoryRedis};
 
 #[derive(Clone)]
 pub struct Server {
     access_token_repository: AccessTokenRepositoryMongo,
     admin_repository: AdminRepositoryMongo,
     client_repository: ClientRepositoryMongo,
+    consumed_assertion_repository: ConsumedAssertionRepositoryMongo,
+    device_authorization_repository: DeviceAuthorizationRepositoryMongo,
+    dpop_proof_repository: DpopProofRepositoryRedis,
     end_user_repository: EndUserRepositoryMongo,
     grant_repository: GrantRepositoryMongo,
     id_token_repository: IdTokenRepositoryMongo,
+    logout_delivery_repository: LogoutDeliveryRepositoryMongo,
+    pushed_authorization_request_repository: PushedAuthorizationRequestRepositoryMongo,
     refresh_token_repository: RefreshTokenRepositoryMongo,
     resource_repository: ResourceRepositoryMongo,
+    signing_key_repository: SigningKeyRepositoryMongo,
+    user_code_attempt_repository: UserCodeAttemptRepositoryRedis,
+    logout_token_sender: LogoutTokenSenderHttp,
+    request_object_fetcher: RequestObjectFetcherHttp,
 }
 
 // Dependency injection
@@ -58,6 +83,30 @@ impl ClientRepositoryComponent for Server {
     }
 }
 
+impl ConsumedAssertionRepositoryComponent for Server {
+    type ConsumedAssertionRepository = ConsumedAssertionRepositoryMongo;
+
+    fn consumed_assertion_repository(&self) -> &Self::ConsumedAssertionRepository {
+        &self.consumed_assertion_repository
+    }
+}
+
+impl DeviceAuthorizationRepositoryComponent for Server {
+    type DeviceAuthorizationRepository = DeviceAuthorizationRepositoryMongo;
+
+    fn device_authorization_repository(&self) -> &Self::DeviceAuthorizationRepository {
+        &self.device_authorization_repository
+    }
+}
+
+impl DpopProofRepositoryComponent for Server {
+    type DpopProofRepository = DpopProofRepositoryRedis;
+
+    fn dpop_proof_repository(&self) -> &Self::DpopProofRepository {
+        &self.dpop_proof_repository
+    }
+}
+
 impl EndUserRepositoryComponent for Server {
     type EndUserRepository = EndUserRepositoryMongo;
 
@@ -82,6 +131,24 @@ impl IdTokenRepositoryComponent for Server {
     }
 }
 
+impl LogoutDeliveryRepositoryComponent for Server {
+    type LogoutDeliveryRepository = LogoutDeliveryRepositoryMongo;
+
+    fn logout_delivery_repository(&self) -> &Self::LogoutDeliveryRepository {
+        &self.logout_delivery_repository
+    }
+}
+
+impl PushedAuthorizationRequestRepositoryComponent for Server {
+    type PushedAuthorizationRequestRepository = PushedAuthorizationRequestRepositoryMongo;
+
+    fn pushed_authorization_request_repository(
+        &self,
+    ) -> &Self::PushedAuthorizationRequestRepository {
+        &self.pushed_authorization_request_repository
+    }
+}
+
 impl RefreshTokenRepositoryComponent for Server {
     type RefreshTokenRepository = RefreshTokenRepositoryMongo;
 
@@ -98,6 +165,39 @@ impl ResourceRepositoryComponent for Server {
     }
 }
 
+impl SigningKeyRepositoryComponent for Server {
+    type SigningKeyRepository = SigningKeyRepositoryMongo;
+
+    fn signing_key_repository(&self) -> &Self::SigningKeyRepository {
+        &self.signing_key_repository
+    }
+}
+
+impl UserCodeAttemptRepositoryComponent for Server {
+    type UserCodeAttemptRepository = UserCodeAttemptRepositoryRedis;
+
+    fn user_code_attempt_repository(&self) -> &Self::UserCodeAttemptRepository {
+        &self.user_code_attempt_repository
+    }
+}
+
+// HTTP clients
+impl LogoutTokenSenderComponent for Server {
+    type LogoutTokenSender = LogoutTokenSenderHttp;
+
+    fn logout_token_sender(&self) -> &Self::LogoutTokenSender {
+        &self.logout_token_sender
+    }
+}
+
+impl RequestObjectFetcherComponent for Server {
+    type RequestObjectFetcher = RequestObjectFetcherHttp;
+
+    fn request_object_fetcher(&self) -> &Self::RequestObjectFetcher {
+        &self.request_object_fetcher
+    }
+}
+
 // Domain Services
 impl AuthorizeServiceComponent for Server {
     type AuthorizeService = Self;
@@ -107,6 +207,22 @@ impl AuthorizeServiceComponent for Server {
     }
 }
 
+impl BackchannelLogoutServiceComponent for Server {
+    type BackchannelLogoutService = Self;
+
+    fn backchannel_logout_service(&self) -> &Self::BackchannelLogoutService {
+        self
+    }
+}
+
+impl ClientAuthenticationServiceComponent for Server {
+    type ClientAuthenticationService = Self;
+
+    fn client_authentication_service(&self) -> &Self::ClientAuthenticationService {
+        self
+    }
+}
+
 impl ClientCredentialsServiceComponent for Server {
     type ClientCredentialsService = Self;
 
@@ -115,6 +231,30 @@ impl ClientCredentialsServiceComponent for Server {
     }
 }
 
+impl DeviceAuthorizationServiceComponent for Server {
+    type DeviceAuthorizationService = Self;
+
+    fn device_authorization_service(&self) -> &Self::DeviceAuthorizationService {
+        self
+    }
+}
+
+impl DpopServiceComponent for Server {
+    type DpopService = Self;
+
+    fn dpop_service(&self) -> &Self::DpopService {
+        self
+    }
+}
+
+impl EndSessionServiceComponent for Server {
+    type EndSessionService = Self;
+
+    fn end_session_service(&self) -> &Self::EndSessionService {
+        self
+    }
+}
+
 impl IntrospectServiceComponent for Server {
     type IntrospectService = Self;
 
@@ -123,6 +263,14 @@ impl IntrospectServiceComponent for Server {
     }
 }
 
+impl JwtBearerServiceComponent for Server {
+    type JwtBearerService = Self;
+
+    fn jwt_bearer_service(&self) -> &Self::JwtBearerService {
+        self
+    }
+}
+
 impl KeyServiceComponent for Server {
     type KeyService = Self;
 
@@ -131,6 +279,14 @@ impl KeyServiceComponent for Server {
     }
 }
 
+impl PushedAuthorizationServiceComponent for Server {
+    type PushedAuthorizationService = Self;
+
+    fn pushed_authorization_service(&self) -> &Self::PushedAuthorizationService {
+        self
+    }
+}
+
 impl RefreshTokenServiceComponent for Server {
     type RefreshTokenService = Self;
 
@@ -149,6 +305,22 @@ impl ResourceOwnerPasswordCredentialsServiceComponent for Server {
     }
 }
 
+impl RevocationServiceComponent for Server {
+    type RevocationService = Self;
+
+    fn revocation_service(&self) -> &Self::RevocationService {
+        self
+    }
+}
+
+impl TokenExchangeServiceComponent for Server {
+    type TokenExchangeService = Self;
+
+    fn token_exchange_service(&self) -> &Self::TokenExchangeService {
+        self
+    }
+}
+
 impl UserinfoServiceComponent for Server {
     type UserinfoService = Self;
 
@@ -223,6 +395,15 @@ pub fn build_server() -> Server {
         client_repository: ClientRepositoryMongo {
             mongo_client: MongoClient::new(&db_name, pool.clone()),
         },
+        consumed_assertion_repository: ConsumedAssertionRepositoryMongo {
+            mongo_client: MongoClient::new(&db_name, pool.clone()),
+        },
+        device_authorization_repository: DeviceAuthorizationRepositoryMongo {
+            mongo_client: MongoClient::new(&db_name, pool.clone()),
+        },
+        dpop_proof_repository: DpopProofRepositoryRedis {
+            pool: RedisClient::init_pool(),
+        },
         end_user_repository: EndUserRepositoryMongo {
             mongo_client: MongoClient::new(&db_name, pool.clone()),
         },
@@ -232,11 +413,30 @@ pub fn build_server() -> Server {
         id_token_repository: IdTokenRepositoryMongo {
             mongo_client: MongoClient::new(&db_name, pool.clone()),
         },
+        logout_delivery_repository: LogoutDeliveryRepositoryMongo {
+            mongo_client: MongoClient::new(&db_name, pool.clone()),
+        },
+        pushed_authorization_request_repository: PushedAuthorizationRequestRepositoryMongo {
+            mongo_client: MongoClient::new(&db_name, pool.clone()),
+        },
         refresh_token_repository: RefreshTokenRepositoryMongo {
             mongo_client: MongoClient::new(&db_name, pool.clone()),
         },
         resource_repository: ResourceRepositoryMongo {
             mongo_client: MongoClient::new(&db_name, pool.clone()),
         },
+        signing_key_repository: SigningKeyRepositoryMongo {
+            mongo_client: MongoClient::new(&db_name, pool.clone()),
+        },
+        user_code_attempt_repository: UserCodeAttemptRepositoryRedis {
+            pool: RedisClient::init_pool(),
+        },
+        logout_token_sender: LogoutTokenSenderHttp {
+            timeout_sec: AppConfig::backchannel_logout_timeout_sec(),
+        },
+        request_object_fetcher: RequestObjectFetcherHttp {
+            timeout_sec: AppConfig::request_object_fetch_timeout_sec(),
+            max_body_bytes: AppConfig::request_object_max_bytes(),
+        },
     }
 }
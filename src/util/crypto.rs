@@ -0,0 +1,64 @@
+use rand::{OsRng, Rng};
+use ring::aead::{open_in_place, seal_in_place, OpeningKey, SealingKey, AES_256_GCM};
+use ring::constant_time::verify_slices_are_equal;
+
+const AES_GCM_NONCE_LEN: usize = 12;
+
+/// Encrypts `plain` by AES-256-GCM and returns the nonce followed by the ciphertext and the tag.
+pub fn encrypt_aes256gcm(key: &[u8], plain: &[u8]) -> Option<Vec<u8>> {
+    let sealing_key = SealingKey::new(&AES_256_GCM, key).ok()?;
+    let mut nonce = [0u8; AES_GCM_NONCE_LEN];
+    OsRng::new().ok()?.fill_bytes(&mut nonce);
+    let tag_len = AES_256_GCM.tag_len();
+    let mut in_out = plain.to_vec();
+    in_out.extend(vec![0u8; tag_len]);
+    let len = seal_in_place(&sealing_key, &nonce, &[], &mut in_out, tag_len).ok()?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&in_out[..len]);
+    Some(sealed)
+}
+
+/// Decrypts the output of `encrypt_aes256gcm`. Returns None if it has been tampered.
+pub fn decrypt_aes256gcm(key: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < AES_GCM_NONCE_LEN {
+        return None;
+    }
+    let opening_key = OpeningKey::new(&AES_256_GCM, key).ok()?;
+    let (nonce, ciphertext) = sealed.split_at(AES_GCM_NONCE_LEN);
+    let mut in_out = ciphertext.to_vec();
+    open_in_place(&opening_key, nonce, &[], 0, &mut in_out)
+        .ok()
+        .map(|plain| plain.to_vec())
+}
+
+/// Compares secrets in constant time not to leak them by the response time.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    verify_slices_are_equal(a, b).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_encrypt_and_decrypt() {
+        let key = [7u8; 32];
+        let sealed = encrypt_aes256gcm(&key, b"private key").unwrap();
+
+        assert_ne!(&sealed[AES_GCM_NONCE_LEN..], b"private key");
+        assert_eq!(decrypt_aes256gcm(&key, &sealed).unwrap(), b"private key");
+        assert!(decrypt_aes256gcm(&[8u8; 32], &sealed).is_none());
+
+        let mut tampered = sealed.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(decrypt_aes256gcm(&key, &tampered).is_none());
+    }
+
+    #[test]
+    fn should_compare_in_constant_time() {
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token2"));
+    }
+}
@@ -0,0 +1,97 @@
+//! Minimal DER reader and writer for keys and certificates
+pub const DER_TAG_BOOLEAN: u8 = 0x01;
+pub const DER_TAG_INTEGER: u8 = 0x02;
+pub const DER_TAG_BIT_STRING: u8 = 0x03;
+pub const DER_TAG_OCTET_STRING: u8 = 0x04;
+pub const DER_TAG_NULL: u8 = 0x05;
+pub const DER_TAG_OID: u8 = 0x06;
+pub const DER_TAG_UTF8_STRING: u8 = 0x0c;
+pub const DER_TAG_PRINTABLE_STRING: u8 = 0x13;
+pub const DER_TAG_T61_STRING: u8 = 0x14;
+pub const DER_TAG_IA5_STRING: u8 = 0x16;
+pub const DER_TAG_SEQUENCE: u8 = 0x30;
+pub const DER_TAG_SET: u8 = 0x31;
+
+/// `DerReader` reads TLV elements of DER encoded bytes in order.
+pub struct DerReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> DerReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        DerReader { data, pos: 0 }
+    }
+
+    /// Returns true if all elements have been read.
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    /// Reads the next element and returns its tag and content.
+    pub fn read(&mut self) -> Option<(u8, &'a [u8])> {
+        let tag = *self.data.get(self.pos)?;
+        let first = *self.data.get(self.pos + 1)? as usize;
+        let (len, header_len) = if first & 0x80 == 0 {
+            (first, 2)
+        } else {
+            let n = first & 0x7f;
+            if n == 0 || n > 4 {
+                return None;
+            }
+            let mut len = 0usize;
+            for i in 0..n {
+                len = (len << 8) | *self.data.get(self.pos + 2 + i)? as usize;
+            }
+            (len, 2 + n)
+        };
+        let start = self.pos + header_len;
+        let end = start.checked_add(len)?;
+        if end > self.data.len() {
+            return None;
+        }
+        self.pos = end;
+        Some((tag, &self.data[start..end]))
+    }
+
+    /// Reads the next element and returns its content if the tag is the expected one.
+    pub fn read_expected(&mut self, tag: u8) -> Option<&'a [u8]> {
+        match self.read() {
+            Some((t, content)) if t == tag => Some(content),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes an element of the tag and the content as DER.
+pub fn encode_der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut ret = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        ret.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = (0..4)
+            .rev()
+            .map(|i| (len >> (i * 8)) as u8)
+            .skip_while(|b| *b == 0)
+            .collect();
+        ret.push(0x80 | bytes.len() as u8);
+        ret.extend(bytes);
+    }
+    ret.extend_from_slice(content);
+    ret
+}
+
+/// Encodes unsigned big-endian bytes as a DER INTEGER.
+pub fn encode_der_unsigned_integer(value: &[u8]) -> Vec<u8> {
+    let pos = value.iter().position(|b| *b != 0).unwrap_or(value.len());
+    let value = &value[pos..];
+    // A leading zero keeps the integer positive
+    if value.is_empty() || value[0] & 0x80 != 0 {
+        let mut content = vec![0u8];
+        content.extend_from_slice(value);
+        encode_der(DER_TAG_INTEGER, &content)
+    } else {
+        encode_der(DER_TAG_INTEGER, value)
+    }
+}
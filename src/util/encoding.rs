@@ -0,0 +1,11 @@
+use base64::{decode_config, encode_config, URL_SAFE_NO_PAD};
+
+/// Encode bytes by base64url without padding.
+pub fn encode_base64url(src: &[u8]) -> String {
+    encode_config(src, URL_SAFE_NO_PAD)
+}
+
+/// Decode a base64url string without padding.
+pub fn decode_base64url(src: &str) -> Option<Vec<u8>> {
+    decode_config(src, URL_SAFE_NO_PAD).ok()
+}
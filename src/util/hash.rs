@@ -1,4 +1,5 @@
 use blake2::{Blake2b, Digest};
+use sha2::Sha256;
 
 /// Hash string by Blake2d and return a HEX string.
 pub fn hash_str(src: &String) -> String {
@@ -8,6 +9,13 @@ pub fn hash_str(src: &String) -> String {
     format!("{:x}", hash)
 }
 
+/// Hash bytes by SHA-256 and return the digest.
+pub fn hash_sha256(src: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.input(src);
+    hasher.result().to_vec()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
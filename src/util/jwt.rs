@@ -0,0 +1,37 @@
+use serde_json;
+
+use util::decode_base64url;
+
+/// `JwtHeader` is the type represents members of a JOSE header
+/// which are needed before verifying the signature.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JwtHeader {
+    pub alg: Option<String>,
+    pub typ: Option<String>,
+    pub kid: Option<String>,
+}
+
+/// Decode the header of a JWT without verifying the signature.
+pub fn decode_jwt_header(token: &str) -> Option<JwtHeader> {
+    let encoded = match token.split('.').next() {
+        Some(h) => h,
+        None => return None,
+    };
+    decode_base64url(encoded).and_then(|v| serde_json::from_slice(&v).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_decode_jwt_header() {
+        // {"alg":"RS256","typ":"JWT","kid":"k1"}
+        let token = "eyJhbGciOiJSUzI1NiIsInR5cCI6IkpXVCIsImtpZCI6ImsxIn0.e30.c2ln";
+        let header = decode_jwt_header(token).unwrap();
+
+        assert_eq!(header.alg, Some("RS256".to_string()));
+        assert_eq!(header.kid, Some("k1".to_string()));
+        assert!(decode_jwt_header("invalid.e30.c2ln").is_none());
+    }
+}
@@ -1,7 +1,15 @@
+mod crypto;
 mod datetime;
+mod der;
+mod encoding;
+mod jwt;
 mod random_id;
 mod hash;
 
+pub use self::crypto::*;
 pub use self::datetime::*;
+pub use self::der::*;
+pub use self::encoding::*;
+pub use self::jwt::*;
 pub use self::random_id::*;
 pub use self::hash::*;
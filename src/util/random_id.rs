@@ -6,3 +6,14 @@ pub fn generate_random_id(len: usize) -> String {
         .map(|mut r| r.gen_ascii_chars().take(len).collect())
         .unwrap()
 }
+
+/// Generate secure random string whose length is `len` from the characters in `charset`.
+pub fn generate_random_string(len: usize, charset: &[u8]) -> String {
+    OsRng::new()
+        .map(|mut r| {
+            (0..len)
+                .map(|_| charset[r.gen_range(0, charset.len())] as char)
+                .collect()
+        })
+        .unwrap()
+}